@@ -7,6 +7,16 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::{OtellError, Result};
 
+/// Default capacity of the live log broadcast channel used by tail streams.
+pub const DEFAULT_TAIL_BUFFER: usize = 8192;
+
+/// Default cap, in bytes, on a compiled search/tail regex and its lazy DFA;
+/// the same 10 MiB the `regex` crate uses for compiled programs.
+pub const DEFAULT_REGEX_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
+/// Default cap on logs loaded to expand time (`-C 2s`) context around search matches.
+pub const DEFAULT_CONTEXT_SCAN_LIMIT: usize = 200_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Config {
     pub db_path: PathBuf,
@@ -19,11 +29,30 @@ pub struct Config {
     pub retention_max_bytes: u64,
     pub write_batch_size: usize,
     pub write_flush_ms: u64,
+    pub write_log_batch_size: Option<usize>,
+    pub write_log_flush_ms: Option<u64>,
+    pub write_span_batch_size: Option<usize>,
+    pub write_span_flush_ms: Option<u64>,
+    pub write_metric_batch_size: Option<usize>,
+    pub write_metric_flush_ms: Option<u64>,
     pub forward_otlp_endpoint: Option<String>,
     pub forward_otlp_protocol: String,
     pub forward_otlp_compression: String,
     pub forward_otlp_headers: Vec<(String, String)>,
     pub forward_otlp_timeout: Duration,
+    pub forward_otlp_require: bool,
+    pub tail_buffer: usize,
+    pub ingest_reject_older_than: Option<Duration>,
+    pub parse_body: String,
+    pub log_time_source: String,
+    pub query_audit: Option<PathBuf>,
+    pub query_token: Option<String>,
+    pub query_cors_origins: Vec<String>,
+    pub store_raw_otlp: Option<PathBuf>,
+    pub drop_span_names: Vec<String>,
+    pub max_concurrent_requests: usize,
+    pub query_regex_size_limit: usize,
+    pub context_scan_limit: usize,
 }
 
 impl Default for Config {
@@ -52,11 +81,30 @@ impl Default for Config {
             retention_max_bytes: 2 * 1024 * 1024 * 1024,
             write_batch_size: 2048,
             write_flush_ms: 200,
+            write_log_batch_size: None,
+            write_log_flush_ms: None,
+            write_span_batch_size: None,
+            write_span_flush_ms: None,
+            write_metric_batch_size: None,
+            write_metric_flush_ms: None,
             forward_otlp_endpoint: None,
             forward_otlp_protocol: "grpc".to_string(),
             forward_otlp_compression: "none".to_string(),
             forward_otlp_headers: Vec::new(),
             forward_otlp_timeout: Duration::from_secs(10),
+            forward_otlp_require: false,
+            tail_buffer: DEFAULT_TAIL_BUFFER,
+            ingest_reject_older_than: None,
+            parse_body: "off".to_string(),
+            log_time_source: "event".to_string(),
+            query_audit: None,
+            query_token: None,
+            query_cors_origins: Vec::new(),
+            store_raw_otlp: None,
+            drop_span_names: Vec::new(),
+            max_concurrent_requests: 512,
+            query_regex_size_limit: DEFAULT_REGEX_SIZE_LIMIT,
+            context_scan_limit: DEFAULT_CONTEXT_SCAN_LIMIT,
         }
     }
 }
@@ -93,14 +141,33 @@ struct ConfigOverrides {
     retention_max_bytes: Option<u64>,
     write_batch_size: Option<usize>,
     write_flush_ms: Option<u64>,
+    write_log_batch_size: Option<usize>,
+    write_log_flush_ms: Option<u64>,
+    write_span_batch_size: Option<usize>,
+    write_span_flush_ms: Option<u64>,
+    write_metric_batch_size: Option<usize>,
+    write_metric_flush_ms: Option<u64>,
     forward_otlp_endpoint: Option<String>,
     forward_otlp_protocol: Option<String>,
     forward_otlp_compression: Option<String>,
     forward_otlp_headers: Option<String>,
     forward_otlp_timeout: Option<String>,
+    forward_otlp_require: Option<bool>,
+    tail_buffer: Option<usize>,
+    ingest_reject_older_than: Option<String>,
+    parse_body: Option<String>,
+    log_time_source: Option<String>,
+    query_audit: Option<PathBuf>,
+    query_token: Option<String>,
+    query_cors_origins: Option<String>,
+    store_raw_otlp: Option<PathBuf>,
+    drop_span_names: Option<String>,
+    max_concurrent_requests: Option<usize>,
+    query_regex_size_limit: Option<usize>,
+    context_scan_limit: Option<usize>,
 }
 
-fn config_file_path() -> PathBuf {
+pub fn config_file_path() -> PathBuf {
     if let Ok(path) = env::var("OTELL_CONFIG") {
         return PathBuf::from(path);
     }
@@ -131,6 +198,45 @@ fn load_env_overrides() -> Result<ConfigOverrides> {
         })?),
         Err(_) => None,
     };
+    let forward_otlp_require = match env::var("OTELL_FORWARD_REQUIRE") {
+        Ok(v) => Some(parse_bool(&v).ok_or_else(|| {
+            OtellError::Config(format!(
+                "bad OTELL_FORWARD_REQUIRE in environment: expected true/false (value={v})"
+            ))
+        })?),
+        Err(_) => None,
+    };
+    let tail_buffer = match env::var("OTELL_TAIL_BUFFER") {
+        Ok(v) => Some(v.parse::<usize>().map_err(|e| {
+            OtellError::Config(format!("bad OTELL_TAIL_BUFFER in environment: {e}"))
+        })?),
+        Err(_) => None,
+    };
+
+    let max_concurrent_requests = match env::var("OTELL_MAX_CONCURRENT_REQUESTS") {
+        Ok(v) => Some(v.parse::<usize>().map_err(|e| {
+            OtellError::Config(format!(
+                "bad OTELL_MAX_CONCURRENT_REQUESTS in environment: {e}"
+            ))
+        })?),
+        Err(_) => None,
+    };
+
+    let query_regex_size_limit = match env::var("OTELL_QUERY_REGEX_SIZE_LIMIT") {
+        Ok(v) => Some(v.parse::<usize>().map_err(|e| {
+            OtellError::Config(format!(
+                "bad OTELL_QUERY_REGEX_SIZE_LIMIT in environment: {e}"
+            ))
+        })?),
+        Err(_) => None,
+    };
+
+    let context_scan_limit = match env::var("OTELL_CONTEXT_SCAN_LIMIT") {
+        Ok(v) => Some(v.parse::<usize>().map_err(|e| {
+            OtellError::Config(format!("bad OTELL_CONTEXT_SCAN_LIMIT in environment: {e}"))
+        })?),
+        Err(_) => None,
+    };
 
     Ok(ConfigOverrides {
         db_path: env::var("OTELL_DB_PATH").ok().map(PathBuf::from),
@@ -143,11 +249,30 @@ fn load_env_overrides() -> Result<ConfigOverrides> {
         retention_max_bytes,
         write_batch_size: None,
         write_flush_ms: None,
+        write_log_batch_size: None,
+        write_log_flush_ms: None,
+        write_span_batch_size: None,
+        write_span_flush_ms: None,
+        write_metric_batch_size: None,
+        write_metric_flush_ms: None,
         forward_otlp_endpoint: env::var("OTELL_FORWARD_OTLP_ENDPOINT").ok(),
         forward_otlp_protocol: env::var("OTELL_FORWARD_OTLP_PROTOCOL").ok(),
         forward_otlp_compression: env::var("OTELL_FORWARD_OTLP_COMPRESSION").ok(),
         forward_otlp_headers: env::var("OTELL_FORWARD_OTLP_HEADERS").ok(),
         forward_otlp_timeout: env::var("OTELL_FORWARD_OTLP_TIMEOUT").ok(),
+        forward_otlp_require,
+        tail_buffer,
+        ingest_reject_older_than: env::var("OTELL_INGEST_REJECT_OLDER_THAN").ok(),
+        parse_body: env::var("OTELL_PARSE_BODY").ok(),
+        log_time_source: env::var("OTELL_LOG_TIME_SOURCE").ok(),
+        query_audit: env::var("OTELL_QUERY_AUDIT").ok().map(PathBuf::from),
+        query_token: env::var("OTELL_QUERY_TOKEN").ok(),
+        query_cors_origins: env::var("OTELL_QUERY_CORS_ORIGINS").ok(),
+        store_raw_otlp: env::var("OTELL_STORE_RAW_OTLP").ok().map(PathBuf::from),
+        drop_span_names: env::var("OTELL_DROP_SPAN_NAMES").ok(),
+        max_concurrent_requests,
+        query_regex_size_limit,
+        context_scan_limit,
     })
 }
 
@@ -184,6 +309,24 @@ fn apply_overrides(cfg: &mut Config, overrides: ConfigOverrides, source: &str) -
     if let Some(v) = overrides.write_flush_ms {
         cfg.write_flush_ms = v;
     }
+    if let Some(v) = overrides.write_log_batch_size {
+        cfg.write_log_batch_size = Some(v);
+    }
+    if let Some(v) = overrides.write_log_flush_ms {
+        cfg.write_log_flush_ms = Some(v);
+    }
+    if let Some(v) = overrides.write_span_batch_size {
+        cfg.write_span_batch_size = Some(v);
+    }
+    if let Some(v) = overrides.write_span_flush_ms {
+        cfg.write_span_flush_ms = Some(v);
+    }
+    if let Some(v) = overrides.write_metric_batch_size {
+        cfg.write_metric_batch_size = Some(v);
+    }
+    if let Some(v) = overrides.write_metric_flush_ms {
+        cfg.write_metric_flush_ms = Some(v);
+    }
     if let Some(v) = overrides.forward_otlp_endpoint {
         cfg.forward_otlp_endpoint = Some(v);
     }
@@ -207,9 +350,115 @@ fn apply_overrides(cfg: &mut Config, overrides: ConfigOverrides, source: &str) -
             ))
         })?;
     }
+    if let Some(v) = overrides.forward_otlp_require {
+        cfg.forward_otlp_require = v;
+    }
+    if let Some(v) = overrides.tail_buffer {
+        if v == 0 {
+            return Err(OtellError::Config(format!(
+                "bad tail_buffer in {source}: must be greater than zero"
+            )));
+        }
+        cfg.tail_buffer = v;
+    }
+    if let Some(v) = overrides.ingest_reject_older_than {
+        cfg.ingest_reject_older_than = Some(humantime::parse_duration(&v).map_err(|e| {
+            OtellError::Config(format!(
+                "bad ingest_reject_older_than in {source}: {e} (value={v})"
+            ))
+        })?);
+    }
+    if let Some(v) = overrides.parse_body {
+        let normalized = v.trim().to_ascii_lowercase();
+        if !matches!(normalized.as_str(), "off" | "logfmt" | "json") {
+            return Err(OtellError::Config(format!(
+                "bad parse_body in {source}: expected off, logfmt or json (value={v})"
+            )));
+        }
+        cfg.parse_body = normalized;
+    }
+    if let Some(v) = overrides.log_time_source {
+        let normalized = v.trim().to_ascii_lowercase();
+        if !matches!(normalized.as_str(), "event" | "observed" | "min" | "max") {
+            return Err(OtellError::Config(format!(
+                "bad log_time_source in {source}: expected event, observed, min or max (value={v})"
+            )));
+        }
+        cfg.log_time_source = normalized;
+    }
+    if let Some(v) = overrides.query_audit {
+        cfg.query_audit = Some(v);
+    }
+    if let Some(v) = overrides.query_token {
+        if v.is_empty() {
+            return Err(OtellError::Config(format!(
+                "bad query_token in {source}: must not be empty"
+            )));
+        }
+        cfg.query_token = Some(v);
+    }
+    if let Some(v) = overrides.query_cors_origins {
+        cfg.query_cors_origins = v
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+    if let Some(v) = overrides.store_raw_otlp {
+        cfg.store_raw_otlp = Some(v);
+    }
+    if let Some(v) = overrides.drop_span_names {
+        let names = v
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        for name in &names {
+            glob::Pattern::new(name).map_err(|e| {
+                OtellError::Config(format!(
+                    "bad drop_span_names in {source}: {e} (value={name})"
+                ))
+            })?;
+        }
+        cfg.drop_span_names = names;
+    }
+    if let Some(v) = overrides.max_concurrent_requests {
+        if v == 0 {
+            return Err(OtellError::Config(format!(
+                "bad max_concurrent_requests in {source}: must be greater than zero"
+            )));
+        }
+        cfg.max_concurrent_requests = v;
+    }
+    if let Some(v) = overrides.query_regex_size_limit {
+        if v == 0 {
+            return Err(OtellError::Config(format!(
+                "bad query_regex_size_limit in {source}: must be greater than zero"
+            )));
+        }
+        cfg.query_regex_size_limit = v;
+    }
+    if let Some(v) = overrides.context_scan_limit {
+        if v == 0 {
+            return Err(OtellError::Config(format!(
+                "bad context_scan_limit in {source}: must be greater than zero"
+            )));
+        }
+        cfg.context_scan_limit = v;
+    }
     Ok(())
 }
 
+fn parse_bool(raw: &str) -> Option<bool> {
+    match raw.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Some(true),
+        "0" | "false" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
 fn parse_otlp_headers(raw: &str) -> Result<Vec<(String, String)>> {
     let mut out = Vec::new();
     for entry in raw.split(',') {
@@ -269,6 +518,14 @@ mod tests {
         assert!(parse_otlp_headers("=dev").is_err());
     }
 
+    #[test]
+    fn parse_bool_accepts_common_spellings() {
+        assert_eq!(parse_bool("true"), Some(true));
+        assert_eq!(parse_bool(" YES "), Some(true));
+        assert_eq!(parse_bool("0"), Some(false));
+        assert_eq!(parse_bool("maybe"), None);
+    }
+
     #[test]
     fn apply_file_overrides_updates_forwarding_fields() {
         let mut cfg = Config::default();
@@ -278,6 +535,7 @@ mod tests {
             forward_otlp_compression: Some("gzip".to_string()),
             forward_otlp_headers: Some("x-tenant=dev,authorization=Bearer token".to_string()),
             forward_otlp_timeout: Some("3s".to_string()),
+            forward_otlp_require: Some(true),
             ..ConfigOverrides::default()
         };
 
@@ -297,5 +555,128 @@ mod tests {
             ]
         );
         assert_eq!(cfg.forward_otlp_timeout, Duration::from_secs(3));
+        assert!(cfg.forward_otlp_require);
+    }
+
+    #[test]
+    fn apply_overrides_rejects_zero_tail_buffer() {
+        let mut cfg = Config::default();
+        let file = ConfigOverrides {
+            tail_buffer: Some(0),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+
+        let file = ConfigOverrides {
+            tail_buffer: Some(64),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(cfg.tail_buffer, 64);
+    }
+
+    #[test]
+    fn apply_overrides_parses_ingest_reject_older_than() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.ingest_reject_older_than, None);
+        let file = ConfigOverrides {
+            ingest_reject_older_than: Some("6h".to_string()),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(
+            cfg.ingest_reject_older_than,
+            Some(Duration::from_secs(6 * 60 * 60))
+        );
+
+        let file = ConfigOverrides {
+            ingest_reject_older_than: Some("soon".to_string()),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+    }
+
+    #[test]
+    fn apply_overrides_validates_parse_body() {
+        let mut cfg = Config::default();
+        let file = ConfigOverrides {
+            parse_body: Some("LogFmt".to_string()),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(cfg.parse_body, "logfmt");
+
+        let file = ConfigOverrides {
+            parse_body: Some("yaml".to_string()),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+    }
+
+    #[test]
+    fn apply_overrides_validates_log_time_source() {
+        let mut cfg = Config::default();
+        assert_eq!(cfg.log_time_source, "event");
+        let file = ConfigOverrides {
+            log_time_source: Some(" Observed".to_string()),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(cfg.log_time_source, "observed");
+
+        let file = ConfigOverrides {
+            log_time_source: Some("latest".to_string()),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+    }
+
+    #[test]
+    fn apply_overrides_splits_drop_span_names() {
+        let mut cfg = Config::default();
+        let file = ConfigOverrides {
+            drop_span_names: Some("GET /healthz, GET /ready*,".to_string()),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(cfg.drop_span_names, vec!["GET /healthz", "GET /ready*"]);
+
+        let file = ConfigOverrides {
+            drop_span_names: Some("GET /[".to_string()),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+    }
+
+    #[test]
+    fn apply_overrides_splits_query_cors_origins() {
+        let mut cfg = Config::default();
+        assert!(cfg.query_cors_origins.is_empty());
+        let file = ConfigOverrides {
+            query_cors_origins: Some(" http://localhost:3000 ,https://dash.local,".to_string()),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(
+            cfg.query_cors_origins,
+            vec!["http://localhost:3000", "https://dash.local"]
+        );
+    }
+
+    #[test]
+    fn apply_overrides_rejects_zero_max_concurrent_requests() {
+        let mut cfg = Config::default();
+        let file = ConfigOverrides {
+            max_concurrent_requests: Some(0),
+            ..ConfigOverrides::default()
+        };
+        assert!(apply_overrides(&mut cfg, file, "config file").is_err());
+
+        let file = ConfigOverrides {
+            max_concurrent_requests: Some(32),
+            ..ConfigOverrides::default()
+        };
+        apply_overrides(&mut cfg, file, "config file").unwrap();
+        assert_eq!(cfg.max_concurrent_requests, 32);
     }
 }
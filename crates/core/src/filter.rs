@@ -1,7 +1,9 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
-use glob::Pattern;
+use glob::{MatchOptions, Pattern};
 use serde::{Deserialize, Serialize};
 
 use crate::error::{OtellError, Result};
@@ -32,43 +34,148 @@ impl FromStr for Severity {
     }
 }
 
+/// Serialized as `ts_asc` / `ts_desc` / `duration_desc` / `relevance`; the variant names
+/// (`TsAsc`, ...) are still accepted so older handles keep decoding.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
 pub enum SortOrder {
     #[default]
+    #[serde(alias = "TsAsc")]
     TsAsc,
+    #[serde(alias = "TsDesc")]
     TsDesc,
+    #[serde(alias = "DurationDesc")]
     DurationDesc,
+    /// Log search only: severity weight decayed by age, highest first.
+    Relevance,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AttrFilter {
     pub key: String,
+    /// Glob for `=`/`!=`; the right-hand side for ordering operators.
     pub value_glob: String,
+    #[serde(default)]
+    pub op: AttrOp,
+}
+
+/// Comparison in a `--where` filter. `=`/`!=` glob-match the value's text;
+/// the ordering operators compare numerically when both sides are finite
+/// numbers and lexically otherwise.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttrOp {
+    #[default]
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl AttrOp {
+    // Two-character tokens first so `>=` is not read as `>` then `=...`.
+    const TOKENS: [(&'static str, AttrOp); 6] = [
+        ("!=", AttrOp::Ne),
+        (">=", AttrOp::Ge),
+        ("<=", AttrOp::Le),
+        (">", AttrOp::Gt),
+        ("<", AttrOp::Lt),
+        ("=", AttrOp::Eq),
+    ];
+
+    fn accepts(self, ord: Ordering) -> bool {
+        match self {
+            Self::Eq => ord.is_eq(),
+            Self::Ne => ord.is_ne(),
+            Self::Gt => ord.is_gt(),
+            Self::Ge => ord.is_ge(),
+            Self::Lt => ord.is_lt(),
+            Self::Le => ord.is_le(),
+        }
+    }
 }
 
 impl AttrFilter {
     pub fn parse(input: &str) -> Result<Self> {
-        let (key, value_glob) = input
-            .split_once('=')
-            .ok_or_else(|| OtellError::Parse(format!("invalid where filter: {input}")))?;
+        let invalid = || OtellError::Parse(format!("invalid where filter: {input}"));
+        let idx = input.find(['=', '!', '<', '>']).ok_or_else(invalid)?;
+        let (key, rest) = input.split_at(idx);
+        let (op, value_glob) = AttrOp::TOKENS
+            .iter()
+            .find_map(|(token, op)| rest.strip_prefix(token).map(|v| (*op, v)))
+            .ok_or_else(invalid)?;
 
         if key.trim().is_empty() || value_glob.trim().is_empty() {
-            return Err(OtellError::Parse(format!("invalid where filter: {input}")));
+            return Err(invalid());
         }
 
         Ok(Self {
             key: key.trim().to_string(),
             value_glob: value_glob.trim().to_string(),
+            op,
         })
     }
 
     pub fn matches(&self, value: &str) -> bool {
+        self.matches_with_case(value, false)
+    }
+
+    /// Matches a typed `attrs_json` value; numbers and bools compare by their
+    /// JSON text. A missing attribute matches `=`/`!=` as the empty string
+    /// and never satisfies an ordering operator.
+    pub fn matches_json(&self, value: Option<&serde_json::Value>, ignore_case: bool) -> bool {
+        if value.is_none() && !matches!(self.op, AttrOp::Eq | AttrOp::Ne) {
+            return false;
+        }
+        let text = value.map(attr_value_text).unwrap_or_default();
+        self.matches_with_case(&text, ignore_case)
+    }
+
+    pub fn matches_with_case(&self, value: &str, ignore_case: bool) -> bool {
+        match self.op {
+            AttrOp::Eq => return self.glob_matches(value, ignore_case),
+            AttrOp::Ne => return !self.glob_matches(value, ignore_case),
+            _ => {}
+        }
+        if let (Some(lhs), Some(rhs)) = (finite_number(value), finite_number(&self.value_glob)) {
+            return lhs
+                .partial_cmp(&rhs)
+                .is_some_and(|ord| self.op.accepts(ord));
+        }
+        if ignore_case {
+            self.op
+                .accepts(value.to_lowercase().cmp(&self.value_glob.to_lowercase()))
+        } else {
+            self.op.accepts(value.cmp(self.value_glob.as_str()))
+        }
+    }
+
+    fn glob_matches(&self, value: &str, ignore_case: bool) -> bool {
+        let options = MatchOptions {
+            case_sensitive: !ignore_case,
+            ..MatchOptions::new()
+        };
         Pattern::new(&self.value_glob)
-            .map(|p| p.matches(value))
+            .map(|p| p.matches_with(value, options))
             .unwrap_or(false)
     }
 }
 
+/// `nan`/`inf` parse as floats but don't order, so they compare as text.
+fn finite_number(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+/// Flattened text of an attribute value: strings bare, everything else as JSON.
+pub fn attr_value_text(value: &serde_json::Value) -> Cow<'_, str> {
+    match value {
+        serde_json::Value::String(s) => Cow::Borrowed(s),
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimeWindow {
     pub since: Option<DateTime<Utc>>,
@@ -100,5 +207,78 @@ mod tests {
         assert_eq!(f.key, "attrs.peer");
         assert!(f.matches("redis:6379"));
         assert!(!f.matches("postgres:5432"));
+        assert!(!f.matches("REDIS:6379"));
+        assert!(f.matches_with_case("REDIS:6379", true));
+
+        let f = AttrFilter::parse("http.status_code=5*").unwrap();
+        assert!(f.matches_json(Some(&serde_json::json!(503)), false));
+        assert!(f.matches_json(Some(&serde_json::json!("500")), false));
+        assert!(!f.matches_json(Some(&serde_json::json!(200)), false));
+        assert!(!f.matches_json(None, false));
+    }
+
+    #[test]
+    fn attr_filter_numeric_operators() {
+        let f = AttrFilter::parse("http.status_code>=500").unwrap();
+        assert_eq!(f.key, "http.status_code");
+        assert_eq!(f.op, AttrOp::Ge);
+        assert!(f.matches_json(Some(&serde_json::json!(500)), false));
+        assert!(f.matches_json(Some(&serde_json::json!(503)), false));
+        assert!(f.matches_json(Some(&serde_json::json!("504")), false));
+        assert!(!f.matches_json(Some(&serde_json::json!(404)), false));
+        assert!(!f.matches_json(None, false));
+
+        // Numeric, not lexical: "1000" sorts before "500" as text.
+        let f = AttrFilter::parse("duration_ms>500").unwrap();
+        assert!(f.matches("1000"));
+        assert!(!f.matches("500"));
+        assert!(
+            AttrFilter::parse("duration_ms<=1000")
+                .unwrap()
+                .matches("999.5")
+        );
+
+        // Equality stays textual, and non-finite operands never compare as numbers.
+        assert!(!AttrFilter::parse("retries=1").unwrap().matches("1.0"));
+        assert!(AttrFilter::parse("mode=nan").unwrap().matches("nan"));
+        assert!(!AttrFilter::parse("mode!=inf").unwrap().matches("inf"));
+        assert!(AttrFilter::parse("limit>inf").unwrap().matches("nan"));
+    }
+
+    #[test]
+    fn attr_filter_not_equal_and_lexical_operators() {
+        let f = AttrFilter::parse("peer!=redis").unwrap();
+        assert_eq!(f.op, AttrOp::Ne);
+        assert!(f.matches("postgres"));
+        assert!(!f.matches("redis"));
+        assert!(f.matches("REDIS"));
+        assert!(!f.matches_with_case("REDIS", true));
+        assert!(f.matches_json(None, false));
+
+        assert!(
+            AttrFilter::parse("peer!=redis:*")
+                .unwrap()
+                .matches("pg:5432")
+        );
+        assert!(
+            AttrFilter::parse("region<eu-west")
+                .unwrap()
+                .matches("ap-south")
+        );
+        assert!(AttrFilter::parse("q=a=b").unwrap().matches("a=b"));
+        assert!(AttrFilter::parse("peer!redis").is_err());
+        assert!(AttrFilter::parse(">=5").is_err());
+    }
+
+    #[test]
+    fn sort_order_serde_is_snake_case_with_aliases() {
+        assert_eq!(
+            serde_json::to_string(&SortOrder::DurationDesc).unwrap(),
+            r#""duration_desc""#
+        );
+        let parse = |s: &str| serde_json::from_str::<SortOrder>(s).unwrap();
+        assert_eq!(parse(r#""ts_desc""#), SortOrder::TsDesc);
+        assert_eq!(parse(r#""TsDesc""#), SortOrder::TsDesc);
+        assert_eq!(parse(r#""ts_asc""#), SortOrder::TsAsc);
     }
 }
@@ -11,4 +11,27 @@ pub struct LogRecord {
     pub body: String,
     pub attrs_json: String,
     pub attrs_text: String,
+    /// OTLP log record flags; the low byte holds W3C trace flags (bit 0 = sampled).
+    #[serde(default)]
+    pub flags: u32,
+    /// When the collection pipeline first saw the record (OTLP
+    /// `observed_time_unix_nano`). `ts` stays the event time.
+    #[serde(default)]
+    pub observed_ts: Option<DateTime<Utc>>,
+}
+
+impl LogRecord {
+    /// W3C trace-flags sampled bit.
+    pub const FLAG_SAMPLED: u32 = 0x01;
+
+    pub fn is_sampled(&self) -> bool {
+        self.flags & Self::FLAG_SAMPLED != 0
+    }
+
+    /// Event-to-observed lag; negative when the event time is ahead of the
+    /// observer's clock.
+    pub fn observed_delay_ms(&self) -> Option<i64> {
+        self.observed_ts
+            .map(|observed| (observed - self.ts).num_milliseconds())
+    }
 }
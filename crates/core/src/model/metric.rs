@@ -8,4 +8,14 @@ pub struct MetricPoint {
     pub service: String,
     pub value: f64,
     pub attrs_json: String,
+    /// Unit from the OTLP metric descriptor (e.g. `ms`, `By`); empty if unset.
+    #[serde(default)]
+    pub unit: String,
+    /// Data type: `gauge`, `sum` or `monotonic_sum`.
+    #[serde(default)]
+    pub kind: String,
+    /// `cumulative` or `delta` for sums; empty for gauges and for points
+    /// stored before it was recorded (read as cumulative).
+    #[serde(default)]
+    pub temporality: String,
 }
@@ -23,6 +23,15 @@ pub struct SearchRequest {
     pub context_seconds: Option<i64>,
     pub count_only: bool,
     pub include_stats: bool,
+    #[serde(default)]
+    pub rollup: Option<RollupUnit>,
+    /// Age at which a match's relevance score halves (`SortOrder::Relevance`).
+    /// `None` uses the store default of five minutes.
+    #[serde(default)]
+    pub relevance_half_life_secs: Option<u64>,
+    /// Keep only logs whose W3C sampled flag is set (`true`) or clear (`false`).
+    #[serde(default)]
+    pub sampled: Option<bool>,
 }
 
 impl Default for SearchRequest {
@@ -43,10 +52,40 @@ impl Default for SearchRequest {
             context_seconds: None,
             count_only: false,
             include_stats: false,
+            rollup: None,
+            relevance_half_life_secs: None,
+            sampled: None,
         }
     }
 }
 
+/// Calendar period (UTC) used to roll up search match counts.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RollupUnit {
+    Minute,
+    Hour,
+    Day,
+}
+
+impl RollupUnit {
+    /// Label of the calendar period containing `ts`, e.g. `2026-02-01T13:00`
+    /// for `Hour`. Labels sort in chronological order.
+    pub fn label(self, ts: DateTime<Utc>) -> String {
+        let fmt = match self {
+            Self::Minute => "%Y-%m-%dT%H:%M",
+            Self::Hour => "%Y-%m-%dT%H:00",
+            Self::Day => "%Y-%m-%d",
+        };
+        ts.format(fmt).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RollupEntry {
+    pub period: String,
+    pub count: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct SearchStats {
     pub by_service: Vec<(String, usize)>,
@@ -59,6 +98,18 @@ pub struct SearchResponse {
     pub returned: usize,
     pub records: Vec<LogRecord>,
     pub stats: Option<SearchStats>,
+    #[serde(default)]
+    pub rollup: Option<Vec<RollupEntry>>,
+    /// With line context, the contiguous runs of `records`; consecutive
+    /// groups were not adjacent in the store.
+    #[serde(default)]
+    pub context_groups: Option<Vec<ContextGroup>>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ContextGroup {
+    pub start: usize,
+    pub len: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +124,12 @@ pub struct TraceRequest {
     pub trace_id: String,
     pub root_span_id: Option<String>,
     pub logs: LogContextMode,
+    /// With `All` logs: skip this many logs (oldest first) before the page.
+    #[serde(default)]
+    pub logs_offset: usize,
+    /// With `All` logs: page size. `None` returns every log in one response.
+    #[serde(default)]
+    pub logs_limit: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +137,10 @@ pub struct LogsContextMeta {
     pub policy: String,
     pub limit: usize,
     pub truncated: bool,
+    /// Set when a paginated `All` request has more logs; pass it back as
+    /// `logs_offset` to fetch the next page.
+    #[serde(default)]
+    pub next_offset: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -90,6 +151,23 @@ pub struct TraceResponse {
     pub context: LogsContextMeta,
 }
 
+/// A trace's correlated logs without its spans.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceLogsRequest {
+    pub trace_id: String,
+    pub mode: LogContextMode,
+    /// Cap on returned logs. `Bounded` defaults to 50; `All` to no limit.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraceLogsResponse {
+    pub trace_id: String,
+    pub logs: Vec<LogRecord>,
+    pub context: LogsContextMeta,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpanRequest {
     pub trace_id: String,
@@ -104,6 +182,45 @@ pub struct SpanResponse {
     pub context: LogsContextMeta,
 }
 
+/// Spans across all traces whose `start_ts` falls in `window`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpansInWindowRequest {
+    pub window: TimeWindow,
+    pub service: Option<String>,
+    pub limit: usize,
+    pub sort: SortOrder,
+}
+
+/// Failing spans in `window` grouped by `(service, name)`: the "top failing
+/// operations" view across traces.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedSpansRequest {
+    pub window: TimeWindow,
+    #[serde(default)]
+    pub service: Option<String>,
+    pub limit: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FailedSpanGroup {
+    pub service: String,
+    pub name: String,
+    pub count: usize,
+    /// Most recent status message in the group, when the exporter set one.
+    #[serde(default)]
+    pub status_message: Option<String>,
+    /// Trace of the most recent failing span, for `otell trace`.
+    pub example_trace_id: String,
+    pub last_ts: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedSpansResponse {
+    pub groups: Vec<FailedSpanGroup>,
+    /// Failing spans across all groups, including ones cut by `limit`.
+    pub total_spans: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TracesRequest {
     pub service: Option<String>,
@@ -111,6 +228,11 @@ pub struct TracesRequest {
     pub window: TimeWindow,
     pub sort: SortOrder,
     pub limit: usize,
+    #[serde(default)]
+    pub count_only: bool,
+    /// Keep traces with at least one span whose attributes match every filter.
+    #[serde(default)]
+    pub attr_filters: Vec<AttrFilter>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,18 +252,39 @@ pub struct MetricsRequest {
     pub group_by: Option<String>,
     pub agg: Option<String>,
     pub limit: usize,
+    /// Also aggregate each series per time bucket of this many seconds.
+    #[serde(default)]
+    pub bucket_secs: Option<u64>,
 }
 
+/// Cap on buckets per series; wider ranges keep the newest ones.
+pub const METRIC_BUCKETS_MAX: usize = 10_000;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricSeries {
     pub group: String,
     pub value: f64,
+    /// Per-bucket values, oldest first; empty unless `bucket_secs` was set.
+    /// Every bucket in the window is present, including ones without points.
+    #[serde(default)]
+    pub points: Vec<MetricBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricBucket {
+    pub ts: DateTime<Utc>,
+    /// `None` when no point fell in the bucket.
+    pub value: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricsResponse {
     pub points: Vec<MetricPoint>,
     pub series: Vec<MetricSeries>,
+    #[serde(default)]
+    pub unit: String,
+    #[serde(default)]
+    pub kind: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -149,12 +292,40 @@ pub struct MetricsListRequest {
     pub service: Option<String>,
     pub window: TimeWindow,
     pub limit: usize,
+    #[serde(default)]
+    pub sort: MetricsListSort,
+    #[serde(default)]
+    pub count_only: bool,
+    /// Fill `MetricNameItem::services` (at most `METRIC_SERVICES_MAX` each).
+    #[serde(default)]
+    pub with_services: bool,
+}
+
+/// Cap on `MetricNameItem::services`; the `cardinality` column already tells
+/// how wide a metric is.
+pub const METRIC_SERVICES_MAX: usize = 20;
+
+/// Ordering for metric name listings. `Cardinality` ranks by the number of
+/// distinct `(service, attrs)` series, which is what drives storage cost.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsListSort {
+    #[default]
+    #[serde(alias = "Count")]
+    Count,
+    #[serde(alias = "Cardinality")]
+    Cardinality,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricNameItem {
     pub name: String,
     pub count: usize,
+    #[serde(default)]
+    pub cardinality: usize,
+    /// Distinct emitting services, sorted; only set with `with_services`.
+    #[serde(default)]
+    pub services: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -162,6 +333,48 @@ pub struct MetricsListResponse {
     pub metrics: Vec<MetricNameItem>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceGraphRequest {
+    pub window: TimeWindow,
+}
+
+/// Caller -> callee edge derived from parent/child spans whose services differ.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServiceEdge {
+    pub caller: String,
+    pub callee: String,
+    pub calls: usize,
+    pub errors: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceGraphResponse {
+    pub edges: Vec<ServiceEdge>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingStatsRequest {
+    pub window: TimeWindow,
+    #[serde(default)]
+    pub service: Option<String>,
+}
+
+/// Log counts for one service split by the W3C sampled trace flag. Logs with
+/// no trace id, or stored before flags were recorded, are `unknown`: there is
+/// no sampling decision to report.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServiceSampling {
+    pub service: String,
+    pub sampled: usize,
+    pub unsampled: usize,
+    pub unknown: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingStatsResponse {
+    pub services: Vec<ServiceSampling>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatusResponse {
     pub db_path: String,
@@ -171,6 +384,34 @@ pub struct StatusResponse {
     pub metrics_count: usize,
     pub oldest_ts: Option<DateTime<Utc>>,
     pub newest_ts: Option<DateTime<Utc>>,
+    /// Records accepted by ingest but not yet written, so not in the counts.
+    #[serde(default)]
+    pub pending_logs: u64,
+    #[serde(default)]
+    pub pending_spans: u64,
+    #[serde(default)]
+    pub pending_metrics: u64,
+    /// How long the oldest pending record has waited for a flush.
+    #[serde(default)]
+    pub max_flush_lag_ms: u64,
+}
+
+/// One validation run by `Store::integrity_check`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IntegrityCheck {
+    pub name: String,
+    /// Offending rows, or unreadable tables for `tables_readable`.
+    pub problems: u64,
+    /// Advisory checks flag data that can legitimately occur (partial
+    /// traces, skewed clocks) and do not clear `IntegrityReport::ok`.
+    pub advisory: bool,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub checks: Vec<IntegrityCheck>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
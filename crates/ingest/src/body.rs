@@ -0,0 +1,190 @@
+use otell_core::model::log::LogRecord;
+
+use crate::otlp::decode::json_to_attr_text;
+
+/// Ingest-time extraction of structured fields from log bodies.
+///
+/// Extracted keys are merged into `attrs_json`/`attrs_text` so they can be
+/// filtered with `--where`; attributes already on the record win, and the
+/// body itself is left untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BodyParser {
+    #[default]
+    Off,
+    Logfmt,
+    Json,
+}
+
+impl BodyParser {
+    pub fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "logfmt" => Self::Logfmt,
+            "json" => Self::Json,
+            _ => Self::Off,
+        }
+    }
+
+    pub fn apply(self, record: &mut LogRecord) {
+        let fields: Vec<(String, serde_json::Value)> = match self {
+            Self::Off => return,
+            Self::Logfmt => parse_logfmt(&record.body)
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect(),
+            Self::Json => parse_json_object(&record.body),
+        };
+        if fields.is_empty() {
+            return;
+        }
+
+        let mut attrs = serde_json::from_str::<serde_json::Value>(&record.attrs_json)
+            .ok()
+            .filter(|v| v.is_object())
+            .unwrap_or_else(|| serde_json::Value::Object(serde_json::Map::new()));
+        let Some(map) = attrs.as_object_mut() else {
+            return;
+        };
+        for (key, value) in fields {
+            map.entry(key).or_insert(value);
+        }
+        record.attrs_text = json_to_attr_text(&attrs);
+        record.attrs_json = attrs.to_string();
+    }
+}
+
+/// Parses `key=value` pairs, allowing double-quoted values with `\"` escapes.
+/// Tokens without `=` are skipped, so prose bodies yield nothing.
+fn parse_logfmt(body: &str) -> Vec<(String, String)> {
+    let mut out = Vec::new();
+    let mut chars = body.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            break;
+        }
+
+        let mut key = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            key.push(c);
+        }
+        if chars.next_if_eq(&'=').is_none() {
+            // Bare word (or a stray token); skip to the next whitespace.
+            while chars.next_if(|c| !c.is_whitespace()).is_some() {}
+            continue;
+        }
+
+        let mut value = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some(escaped) = chars.next() {
+                            value.push(escaped);
+                        }
+                    }
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+        } else {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(c);
+            }
+        }
+
+        if !key.is_empty() {
+            out.push((key, value));
+        }
+    }
+    out
+}
+
+/// Promotes the top-level keys of a JSON object body. Scalars keep their JSON
+/// type; nested objects and arrays are kept as their JSON text.
+fn parse_json_object(body: &str) -> Vec<(String, serde_json::Value)> {
+    let trimmed = body.trim();
+    if !trimmed.starts_with('{') {
+        return Vec::new();
+    }
+    let Ok(serde_json::Value::Object(map)) = serde_json::from_str::<serde_json::Value>(trimmed)
+    else {
+        return Vec::new();
+    };
+    map.into_iter()
+        .map(|(k, v)| {
+            let value = match v {
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    serde_json::Value::String(v.to_string())
+                }
+                scalar => scalar,
+            };
+            (k, value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn record(body: &str, attrs_json: &str) -> LogRecord {
+        LogRecord {
+            ts: Utc::now(),
+            service: "api".into(),
+            severity: 17,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: attrs_json.into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        }
+    }
+
+    #[test]
+    fn logfmt_body_becomes_attrs() {
+        let mut log = record(
+            r#"level=error msg="timeout after \"5s\"" peer=redis:6379 retrying"#,
+            r#"{"peer":"from-otlp"}"#,
+        );
+        BodyParser::Logfmt.apply(&mut log);
+
+        let attrs: serde_json::Value = serde_json::from_str(&log.attrs_json).unwrap();
+        assert_eq!(attrs["level"], "error");
+        assert_eq!(attrs["msg"], r#"timeout after "5s""#);
+        assert_eq!(attrs["peer"], "from-otlp");
+        assert!(attrs.get("retrying").is_none());
+        assert!(log.attrs_text.contains("level=error"));
+        assert!(log.body.starts_with("level=error"));
+    }
+
+    #[test]
+    fn json_body_promotes_top_level_keys() {
+        let mut log = record(
+            r#"{"msg":"timeout","attempt":3,"retry":true,"ctx":{"peer":"redis"}}"#,
+            "{}",
+        );
+        BodyParser::Json.apply(&mut log);
+
+        let attrs: serde_json::Value = serde_json::from_str(&log.attrs_json).unwrap();
+        assert_eq!(attrs["msg"], "timeout");
+        assert_eq!(attrs["attempt"], 3);
+        assert_eq!(attrs["retry"], true);
+        assert_eq!(attrs["ctx"], r#"{"peer":"redis"}"#);
+        assert!(log.attrs_text.contains("msg=timeout"));
+        assert!(log.attrs_text.contains("attempt=3"));
+    }
+
+    #[test]
+    fn unstructured_bodies_are_left_alone() {
+        let mut log = record("connection reset by peer", "{}");
+        BodyParser::Logfmt.apply(&mut log);
+        BodyParser::Json.apply(&mut log);
+        assert_eq!(log.attrs_json, "{}");
+        assert_eq!(BodyParser::parse("LOGFMT"), BodyParser::Logfmt);
+        assert_eq!(BodyParser::parse("nope"), BodyParser::Off);
+    }
+}
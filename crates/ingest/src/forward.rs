@@ -9,6 +9,7 @@ use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequ
 use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
 use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use opentelemetry_proto::tonic::collector::trace::v1::trace_service_client::TraceServiceClient;
+use otell_core::error::{OtellError, Result};
 use prost::Message;
 use reqwest::Client;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
@@ -198,6 +199,41 @@ pub fn build_forwarder(cfg: Option<ForwardConfig>) -> Option<Forwarder> {
     Some(Forwarder { tx })
 }
 
+/// Checks that the forward target accepts connections. The forwarder itself
+/// connects lazily, so this is the only way to surface a bad endpoint early.
+pub async fn probe_forward_endpoint(cfg: &ForwardConfig) -> Result<()> {
+    match cfg.protocol {
+        ForwardProtocol::Grpc => {
+            let endpoint = normalize_grpc_endpoint(&cfg.endpoint);
+            tonic::transport::Channel::from_shared(endpoint.clone())
+                .map_err(|e| OtellError::Config(format!("invalid gRPC forward endpoint: {e}")))?
+                .connect_timeout(cfg.timeout)
+                .connect()
+                .await
+                .map_err(|e| {
+                    OtellError::Ingest(format!("forward endpoint {endpoint} unreachable: {e}"))
+                })?;
+        }
+        ForwardProtocol::HttpProtobuf => {
+            let endpoint = cfg.endpoint.trim_end_matches('/').to_string();
+            let client = Client::builder()
+                .timeout(cfg.timeout)
+                .build()
+                .map_err(|e| OtellError::Internal(format!("build forward http client: {e}")))?;
+            // Any HTTP response proves reachability; only transport errors fail.
+            client
+                .head(format!("{endpoint}/v1/logs"))
+                .headers(build_http_headers(&cfg.headers))
+                .send()
+                .await
+                .map_err(|e| {
+                    OtellError::Ingest(format!("forward endpoint {endpoint} unreachable: {e}"))
+                })?;
+        }
+    }
+    Ok(())
+}
+
 impl Forwarder {
     pub async fn submit_logs(&self, req: ExportLogsServiceRequest) {
         let _ = self.tx.send(ForwardMsg::Logs(req)).await;
@@ -355,6 +391,33 @@ fn maybe_compress_http_body(
 mod tests {
     use super::*;
 
+    fn unreachable_config(protocol: ForwardProtocol) -> ForwardConfig {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+        ForwardConfig {
+            endpoint: format!("http://127.0.0.1:{port}"),
+            protocol,
+            compression: ForwardCompression::None,
+            headers: Vec::new(),
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_rejects_unreachable_targets() {
+        assert!(
+            probe_forward_endpoint(&unreachable_config(ForwardProtocol::Grpc))
+                .await
+                .is_err()
+        );
+        assert!(
+            probe_forward_endpoint(&unreachable_config(ForwardProtocol::HttpProtobuf))
+                .await
+                .is_err()
+        );
+    }
+
     #[test]
     fn forward_compression_parse_variants() {
         assert_eq!(ForwardCompression::parse("gzip"), ForwardCompression::Gzip);
@@ -1,4 +1,8 @@
+pub mod body;
 pub mod forward;
+pub mod limit;
+pub mod ndjson;
 pub mod otlp;
 pub mod pipeline;
+pub mod raw;
 pub mod server;
@@ -0,0 +1,68 @@
+use axum::Router;
+use axum::error_handling::HandleErrorLayer;
+use axum::http::StatusCode;
+use tower::ServiceBuilder;
+use tower::limit::GlobalConcurrencyLimitLayer;
+use tower::load_shed::LoadShedLayer;
+
+/// Caps in-flight requests on an HTTP router. Requests beyond `max` are shed
+/// with `503 Service Unavailable` instead of queueing without bound. The
+/// limit is global: axum layers each route separately, so a plain
+/// `ConcurrencyLimitLayer` would only cap requests per route.
+pub fn with_concurrency_limit(router: Router, max: usize) -> Router {
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(|_: tower::BoxError| async {
+                StatusCode::SERVICE_UNAVAILABLE
+            }))
+            .layer(LoadShedLayer::new())
+            .layer(GlobalConcurrencyLimitLayer::new(max)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use axum::routing::get;
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn excess_concurrent_requests_get_503() {
+        let release = Arc::new(Notify::new());
+        let handler_release = release.clone();
+        let router = Router::new().route(
+            "/slow",
+            get(move || {
+                let release = handler_release.clone();
+                async move {
+                    release.notified().await;
+                    "done"
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, with_concurrency_limit(router, 1))
+                .await
+                .unwrap();
+        });
+
+        let url = format!("http://{addr}/slow");
+        let first = tokio::spawn({
+            let url = url.clone();
+            async move { reqwest::get(url).await.unwrap().status() }
+        });
+        // Let the first request take the only slot.
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let second = reqwest::get(&url).await.unwrap().status();
+        assert_eq!(second, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        release.notify_one();
+        assert_eq!(first.await.unwrap(), reqwest::StatusCode::OK);
+    }
+}
@@ -0,0 +1,256 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use otell_core::model::metric::MetricPoint;
+use otell_core::model::span::SpanRecord;
+use serde::{Deserialize, Serialize};
+
+/// Per-line rejections reported back to the producer; the rest are only counted.
+pub const NDJSON_MAX_ERRORS: usize = 20;
+
+/// One span per line for `POST /v1/traces/ndjson`. Either `end_ts` or
+/// `duration_ms` is required.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NdjsonSpan {
+    trace_id: String,
+    span_id: String,
+    #[serde(default)]
+    parent_span_id: Option<String>,
+    service: String,
+    name: String,
+    start_ts: DateTime<Utc>,
+    #[serde(default)]
+    end_ts: Option<DateTime<Utc>>,
+    #[serde(default)]
+    duration_ms: Option<u64>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    attrs: serde_json::Map<String, serde_json::Value>,
+}
+
+/// One point per line for `POST /v1/metrics/ndjson`; `ts` defaults to now.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NdjsonMetric {
+    name: String,
+    service: String,
+    value: f64,
+    #[serde(default)]
+    ts: Option<DateTime<Utc>>,
+    #[serde(default)]
+    unit: String,
+    #[serde(default)]
+    kind: Option<String>,
+    #[serde(default)]
+    temporality: Option<String>,
+    #[serde(default)]
+    attrs: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NdjsonLineError {
+    /// 1-based line number in the request body.
+    pub line: usize,
+    pub error: String,
+}
+
+/// Reply body for the ndjson endpoints.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NdjsonSummary {
+    pub accepted: usize,
+    pub rejected: usize,
+    pub errors: Vec<NdjsonLineError>,
+}
+
+impl NdjsonSummary {
+    fn reject(&mut self, line: usize, error: String) {
+        self.rejected += 1;
+        if self.errors.len() < NDJSON_MAX_ERRORS {
+            self.errors.push(NdjsonLineError { line, error });
+        }
+    }
+}
+
+pub fn parse_spans(body: &[u8]) -> (Vec<SpanRecord>, NdjsonSummary) {
+    parse_lines(body, |raw| {
+        let span: NdjsonSpan = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        require("trace_id", &span.trace_id)?;
+        require("span_id", &span.span_id)?;
+        require("service", &span.service)?;
+        require("name", &span.name)?;
+        let end_ts = match (span.end_ts, span.duration_ms) {
+            (Some(end), _) => end,
+            (None, Some(ms)) => i64::try_from(ms)
+                .ok()
+                .and_then(TimeDelta::try_milliseconds)
+                .and_then(|d| span.start_ts.checked_add_signed(d))
+                .ok_or_else(|| format!("duration_ms {ms} is out of range"))?,
+            (None, None) => return Err("missing field `end_ts` or `duration_ms`".to_string()),
+        };
+        if end_ts < span.start_ts {
+            return Err("end_ts is before start_ts".to_string());
+        }
+        let status = match span.status.as_deref().map(str::to_ascii_uppercase) {
+            None => "OK".to_string(),
+            Some(s) if s == "OK" || s == "ERROR" => s,
+            Some(s) => return Err(format!("status must be OK or ERROR (got {s:?})")),
+        };
+        let attrs = serde_json::Value::Object(span.attrs);
+        Ok(SpanRecord {
+            trace_id: span.trace_id,
+            span_id: span.span_id,
+            parent_span_id: span.parent_span_id.filter(|p| !p.is_empty()),
+            service: span.service,
+            name: span.name,
+            start_ts: span.start_ts,
+            end_ts,
+            status,
+            attrs_json: attrs.to_string(),
+            events_json: "[]".to_string(),
+        })
+    })
+}
+
+pub fn parse_metrics(body: &[u8]) -> (Vec<MetricPoint>, NdjsonSummary) {
+    parse_lines(body, |raw| {
+        let point: NdjsonMetric = serde_json::from_str(raw).map_err(|e| e.to_string())?;
+        require("name", &point.name)?;
+        require("service", &point.service)?;
+        if !point.value.is_finite() {
+            return Err("value must be a finite number".to_string());
+        }
+        let kind = point.kind.unwrap_or_else(|| "gauge".to_string());
+        if !matches!(kind.as_str(), "gauge" | "sum" | "monotonic_sum") {
+            return Err(format!(
+                "kind must be gauge, sum or monotonic_sum (got {kind:?})"
+            ));
+        }
+        let temporality = match (kind.as_str(), point.temporality) {
+            ("gauge", None) => String::new(),
+            ("gauge", Some(_)) => return Err("temporality only applies to sums".to_string()),
+            (_, None) => "cumulative".to_string(),
+            (_, Some(t)) if t == "cumulative" || t == "delta" => t,
+            (_, Some(t)) => {
+                return Err(format!(
+                    "temporality must be cumulative or delta (got {t:?})"
+                ));
+            }
+        };
+        let attrs = serde_json::Value::Object(point.attrs);
+        Ok(MetricPoint {
+            ts: point.ts.unwrap_or_else(Utc::now),
+            name: point.name,
+            service: point.service,
+            value: point.value,
+            attrs_json: attrs.to_string(),
+            unit: point.unit,
+            kind,
+            temporality,
+        })
+    })
+}
+
+/// Blank lines are skipped; every other line is decoded on its own so one
+/// bad record doesn't reject the batch.
+fn parse_lines<T>(
+    body: &[u8],
+    decode: impl Fn(&str) -> Result<T, String>,
+) -> (Vec<T>, NdjsonSummary) {
+    let mut records = Vec::new();
+    let mut summary = NdjsonSummary::default();
+    for (idx, line) in body.split(|b| *b == b'\n').enumerate() {
+        let Ok(line) = std::str::from_utf8(line) else {
+            summary.reject(idx + 1, "line is not valid UTF-8".to_string());
+            continue;
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match decode(line) {
+            Ok(record) => records.push(record),
+            Err(error) => summary.reject(idx + 1, error),
+        }
+    }
+    summary.accepted = records.len();
+    (records, summary)
+}
+
+fn require(field: &str, value: &str) -> Result<(), String> {
+    if value.trim().is_empty() {
+        return Err(format!("field `{field}` must not be empty"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_decode_per_line_and_count_rejections() {
+        let body = br#"{"trace_id":"t1","span_id":"root","service":"api","name":"GET /","start_ts":"2026-02-01T00:00:00Z","duration_ms":250,"status":"error","attrs":{"http.status_code":500}}
+{"trace_id":"t1","span_id":"child","parent_span_id":"root","service":"db","name":"query","start_ts":"2026-02-01T00:00:00.050Z","end_ts":"2026-02-01T00:00:00.150Z"}
+
+{"trace_id":"t1","span_id":"","service":"api","name":"x","start_ts":"2026-02-01T00:00:00Z","duration_ms":1}
+{"trace_id":"t1","span_id":"s3","service":"api","name":"x","start_ts":"2026-02-01T00:00:00Z"}
+not json
+"#;
+        let (spans, summary) = parse_spans(body);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(summary.accepted, 2);
+        assert_eq!(summary.rejected, 3);
+        assert_eq!(
+            summary.errors.iter().map(|e| e.line).collect::<Vec<_>>(),
+            vec![4, 5, 6]
+        );
+        assert!(summary.errors[0].error.contains("span_id"));
+        assert!(summary.errors[1].error.contains("duration_ms"));
+
+        assert_eq!(spans[0].status, "ERROR");
+        assert_eq!(spans[0].duration_ms(), 250);
+        assert_eq!(spans[0].attrs_json, r#"{"http.status_code":500}"#);
+        assert_eq!(spans[1].parent_span_id.as_deref(), Some("root"));
+        assert_eq!(spans[1].status, "OK");
+    }
+
+    #[test]
+    fn out_of_range_duration_rejects_the_line() {
+        let line = |ms: u64| {
+            format!(
+                r#"{{"trace_id":"t1","span_id":"s","service":"api","name":"x","start_ts":"2026-02-01T00:00:00Z","duration_ms":{ms}}}"#
+            )
+        };
+        for ms in [u64::MAX, 1 << 63, 10_000_000_000_000_000] {
+            let (spans, summary) = parse_spans(line(ms).as_bytes());
+            assert!(spans.is_empty());
+            assert_eq!(summary.rejected, 1);
+            assert!(summary.errors[0].error.contains("out of range"), "{ms}");
+        }
+    }
+
+    #[test]
+    fn metrics_validate_value_and_kind() {
+        let body = br#"{"name":"queue.depth","service":"worker","value":12,"ts":"2026-02-01T00:00:00Z","attrs":{"queue":"emails"}}
+{"name":"requests","service":"api","value":3,"kind":"monotonic_sum","unit":"1"}
+{"name":"requests","service":"api","value":"3"}
+{"name":"requests","service":"api","value":3,"kind":"histogram"}
+{"service":"api","value":3}
+{"name":"requests","service":"api","value":3,"kind":"sum","temporality":"delta"}
+{"name":"queue.depth","service":"worker","value":3,"temporality":"delta"}
+"#;
+        let (points, summary) = parse_metrics(body);
+        assert_eq!(points.len(), 3);
+        assert_eq!(summary.rejected, 4);
+        assert_eq!(points[0].kind, "gauge");
+        assert_eq!(points[0].temporality, "");
+        assert_eq!(points[0].attrs_json, r#"{"queue":"emails"}"#);
+        assert_eq!(points[1].kind, "monotonic_sum");
+        assert_eq!(points[1].temporality, "cumulative");
+        assert_eq!(points[1].unit, "1");
+        assert_eq!(points[2].temporality, "delta");
+        assert!(summary.errors[1].error.contains("kind"));
+        assert!(summary.errors[2].error.contains("name"));
+        assert!(summary.errors[3].error.contains("temporality"));
+    }
+}
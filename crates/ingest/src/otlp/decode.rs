@@ -1,19 +1,74 @@
 use chrono::{TimeZone, Utc};
-use opentelemetry_proto::tonic::common::v1::{AnyValue, InstrumentationScope, KeyValue};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use opentelemetry_proto::tonic::common::v1::{AnyValue, EntityRef, InstrumentationScope, KeyValue};
 use opentelemetry_proto::tonic::logs::v1::LogRecord as OtlpLogRecord;
-use opentelemetry_proto::tonic::metrics::v1::{Metric, NumberDataPoint};
+use opentelemetry_proto::tonic::metrics::v1::metric::Data;
+use opentelemetry_proto::tonic::metrics::v1::{AggregationTemporality, Metric, NumberDataPoint};
 use opentelemetry_proto::tonic::resource::v1::Resource;
 use opentelemetry_proto::tonic::trace::v1::Span as OtlpSpan;
+use opentelemetry_proto::tonic::trace::v1::status::StatusCode;
+use otell_core::filter::attr_value_text;
 use otell_core::model::log::LogRecord;
 use otell_core::model::metric::MetricPoint;
 use otell_core::model::span::SpanRecord;
 
+pub fn decode_logs_request(req: ExportLogsServiceRequest) -> Vec<LogRecord> {
+    let mut logs = Vec::new();
+    for rl in req.resource_logs {
+        let resource = rl.resource.as_ref();
+        for sl in rl.scope_logs {
+            let scope = sl.scope.as_ref();
+            for log in sl.log_records {
+                logs.push(decode_log(resource, scope, &log));
+            }
+        }
+    }
+    logs
+}
+
+pub fn decode_traces_request(req: ExportTraceServiceRequest) -> Vec<SpanRecord> {
+    let mut spans = Vec::new();
+    for rs in req.resource_spans {
+        let resource = rs.resource.as_ref();
+        for ss in rs.scope_spans {
+            for span in ss.spans {
+                spans.push(decode_span(resource, &span));
+            }
+        }
+    }
+    spans
+}
+
+/// Decodes gauge and sum data points; other metric types are skipped.
+pub fn decode_metrics_request(req: ExportMetricsServiceRequest) -> Vec<MetricPoint> {
+    let mut points = Vec::new();
+    for rm in req.resource_metrics {
+        let resource = rm.resource.as_ref();
+        for sm in rm.scope_metrics {
+            for metric in sm.metrics {
+                let data_points = match &metric.data {
+                    Some(Data::Gauge(g)) => &g.data_points,
+                    Some(Data::Sum(s)) => &s.data_points,
+                    _ => continue,
+                };
+                for point in data_points {
+                    points.push(decode_metric(resource, &metric, point));
+                }
+            }
+        }
+    }
+    points
+}
+
 pub fn decode_log(
     resource: Option<&Resource>,
     _scope: Option<&InstrumentationScope>,
     record: &OtlpLogRecord,
 ) -> LogRecord {
-    let attrs = kv_to_json(&record.attributes);
+    let mut attrs = kv_to_json(&record.attributes);
+    merge_entity_attrs(&mut attrs, resource);
     let attrs_text = json_to_attr_text(&attrs);
     let service = service_name(resource);
     let ts_nanos = if record.time_unix_nano == 0 {
@@ -22,20 +77,111 @@ pub fn decode_log(
         record.time_unix_nano
     };
 
+    let mut trace_id = bytes_to_hex(&record.trace_id);
+    let mut span_id = bytes_to_hex(&record.span_id);
+    let mut flags = record.flags;
+    // Some libraries only log the propagation header; recover correlation from it.
+    if trace_id.is_none()
+        && let Some(parent) = attrs
+            .get("traceparent")
+            .and_then(|v| v.as_str())
+            .and_then(parse_traceparent)
+    {
+        trace_id = Some(parent.trace_id);
+        span_id = span_id.or(Some(parent.span_id));
+        if flags == 0 {
+            flags = parent.flags;
+        }
+    }
+
     LogRecord {
         ts: nanos_to_dt(ts_nanos),
         service,
         severity: record.severity_number,
-        trace_id: bytes_to_hex(&record.trace_id),
-        span_id: bytes_to_hex(&record.span_id),
+        trace_id,
+        span_id,
         body: any_value_to_string(record.body.as_ref()),
         attrs_json: attrs.to_string(),
         attrs_text,
+        flags,
+        observed_ts: (record.observed_time_unix_nano != 0)
+            .then(|| nanos_to_dt(record.observed_time_unix_nano)),
     }
 }
 
+/// Which OTLP timestamp becomes a log's `ts` when both are set
+/// (`OTELL_LOG_TIME_SOURCE`). `decode_log` always yields the event time,
+/// falling back to the observed time; this re-picks from the decoded record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogTimeSource {
+    #[default]
+    Event,
+    Observed,
+    Min,
+    Max,
+}
+
+impl LogTimeSource {
+    pub fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "observed" => Self::Observed,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            _ => Self::Event,
+        }
+    }
+
+    pub fn apply(self, record: &mut LogRecord) {
+        let Some(observed) = record.observed_ts else {
+            return;
+        };
+        record.ts = match self {
+            Self::Event => return,
+            Self::Observed => observed,
+            Self::Min => record.ts.min(observed),
+            Self::Max => record.ts.max(observed),
+        };
+    }
+}
+
+struct TraceParent {
+    trace_id: String,
+    span_id: String,
+    flags: u32,
+}
+
+/// Parses a W3C `traceparent` header value (`00-<32 hex>-<16 hex>-<2 hex>`).
+/// All-zero ids and the invalid `ff` version are rejected, as the spec requires.
+fn parse_traceparent(raw: &str) -> Option<TraceParent> {
+    let mut parts = raw.trim().split('-');
+    let version = parts.next()?;
+    let trace_id = parts.next()?;
+    let span_id = parts.next()?;
+    let flags = parts.next()?;
+    // Version 00 has exactly four fields; later versions may append more.
+    if version == "00" && parts.next().is_some() {
+        return None;
+    }
+    let is_hex = |s: &str, len: usize| s.len() == len && s.bytes().all(|b| b.is_ascii_hexdigit());
+    if !is_hex(version, 2) || version.eq_ignore_ascii_case("ff") {
+        return None;
+    }
+    if !is_hex(trace_id, 32) || !is_hex(span_id, 16) || !is_hex(flags, 2) {
+        return None;
+    }
+    if trace_id.bytes().all(|b| b == b'0') || span_id.bytes().all(|b| b == b'0') {
+        return None;
+    }
+    Some(TraceParent {
+        trace_id: trace_id.to_ascii_lowercase(),
+        span_id: span_id.to_ascii_lowercase(),
+        flags: u32::from_str_radix(flags, 16).ok()?,
+    })
+}
+
 pub fn decode_span(resource: Option<&Resource>, span: &OtlpSpan) -> SpanRecord {
-    let attrs = kv_to_json(&span.attributes);
+    let mut attrs = kv_to_json(&span.attributes);
+    merge_entity_attrs(&mut attrs, resource);
     let events = serde_json::Value::Array(
         span.events
             .iter()
@@ -49,18 +195,13 @@ pub fn decode_span(resource: Option<&Resource>, span: &OtlpSpan) -> SpanRecord {
             .collect(),
     );
 
-    let status = span
-        .status
-        .as_ref()
-        .map(|s| s.message.clone())
-        .filter(|s| !s.is_empty())
-        .unwrap_or_else(|| {
-            if span.status.as_ref().map(|s| s.code).unwrap_or_default() == 2 {
-                "ERROR".to_string()
-            } else {
-                "OK".to_string()
-            }
-        });
+    // The spec gives the status message meaning only on errors, so an error
+    // keeps its message in place of `ERROR` and every other span is `OK`.
+    let status = match &span.status {
+        Some(s) if s.code == StatusCode::Error as i32 && !s.message.is_empty() => s.message.clone(),
+        Some(s) if s.code == StatusCode::Error as i32 => "ERROR".to_string(),
+        _ => "OK".to_string(),
+    };
 
     SpanRecord {
         trace_id: bytes_to_hex(&span.trace_id).unwrap_or_default(),
@@ -91,13 +232,39 @@ pub fn decode_metric(
             }
         })
         .unwrap_or(0.0);
+    let mut attrs = kv_to_json(&point.attributes);
+    merge_entity_attrs(&mut attrs, resource);
 
     MetricPoint {
         ts: nanos_to_dt(point.time_unix_nano),
         name: metric.name.clone(),
         service: service_name(resource),
         value,
-        attrs_json: kv_to_json(&point.attributes).to_string(),
+        attrs_json: attrs.to_string(),
+        unit: metric.unit.clone(),
+        kind: metric_kind(metric).to_string(),
+        temporality: metric_temporality(metric).to_string(),
+    }
+}
+
+fn metric_kind(metric: &Metric) -> &'static str {
+    use opentelemetry_proto::tonic::metrics::v1::metric::Data;
+    match &metric.data {
+        Some(Data::Sum(sum)) if sum.is_monotonic => "monotonic_sum",
+        Some(Data::Sum(_)) => "sum",
+        _ => "gauge",
+    }
+}
+
+fn metric_temporality(metric: &Metric) -> &'static str {
+    match &metric.data {
+        Some(Data::Sum(sum))
+            if sum.aggregation_temporality == AggregationTemporality::Delta as i32 =>
+        {
+            "delta"
+        }
+        Some(Data::Sum(_)) => "cumulative",
+        _ => "",
     }
 }
 
@@ -108,21 +275,67 @@ fn service_name(resource: Option<&Resource>) -> String {
                 return any_value_to_string(kv.value.as_ref());
             }
         }
+        // Entity-modeled resources may identify the service only through a
+        // `service` entity ref; fall back to its first resolvable id attribute.
+        if let Some(entity) = resource.entity_refs.iter().find(|e| e.r#type == "service")
+            && let Some(kv) = resolve_entity_ids(resource, entity).next()
+        {
+            return any_value_to_string(kv.value.as_ref());
+        }
     }
     "unknown".to_string()
 }
 
+/// Copies the primary entity's identifying resource attributes into the
+/// record attrs so they can be filtered on. Record attributes win on conflict.
+fn merge_entity_attrs(attrs: &mut serde_json::Value, resource: Option<&Resource>) {
+    let Some(resource) = resource else {
+        return;
+    };
+    let Some(entity) = resource.entity_refs.first() else {
+        return;
+    };
+    let Some(map) = attrs.as_object_mut() else {
+        return;
+    };
+    for kv in resolve_entity_ids(resource, entity) {
+        map.entry(kv.key.clone())
+            .or_insert_with(|| any_value_to_json(kv.value.as_ref()));
+    }
+}
+
+fn resolve_entity_ids<'a>(
+    resource: &'a Resource,
+    entity: &'a EntityRef,
+) -> impl Iterator<Item = &'a KeyValue> {
+    entity
+        .id_keys
+        .iter()
+        .filter_map(|key| resource.attributes.iter().find(|kv| &kv.key == key))
+}
+
 fn kv_to_json(attrs: &[KeyValue]) -> serde_json::Value {
     let mut map = serde_json::Map::new();
     for kv in attrs {
-        map.insert(
-            kv.key.clone(),
-            serde_json::Value::String(any_value_to_string(kv.value.as_ref())),
-        );
+        map.insert(kv.key.clone(), any_value_to_json(kv.value.as_ref()));
     }
     serde_json::Value::Object(map)
 }
 
+/// Keeps ints, doubles and bools as native JSON so `attrs_json` stays typed;
+/// everything else uses the `any_value_to_string` form.
+fn any_value_to_json(value: Option<&AnyValue>) -> serde_json::Value {
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    match value.and_then(|v| v.value.as_ref()) {
+        Some(Value::BoolValue(b)) => serde_json::Value::Bool(*b),
+        Some(Value::IntValue(i)) => serde_json::Value::from(*i),
+        Some(Value::DoubleValue(d)) => serde_json::Number::from_f64(*d)
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|| serde_json::Value::String(d.to_string())),
+        _ => serde_json::Value::String(any_value_to_string(value)),
+    }
+}
+
 fn any_value_to_string(value: Option<&AnyValue>) -> String {
     value
         .and_then(|v| v.value.as_ref())
@@ -141,12 +354,12 @@ fn any_value_to_string(value: Option<&AnyValue>) -> String {
         .unwrap_or_default()
 }
 
-fn json_to_attr_text(value: &serde_json::Value) -> String {
+pub(crate) fn json_to_attr_text(value: &serde_json::Value) -> String {
     value
         .as_object()
         .map(|map| {
             map.iter()
-                .map(|(k, v)| format!("{k}={}", v.as_str().unwrap_or_default()))
+                .map(|(k, v)| format!("{k}={}", attr_value_text(v)))
                 .collect::<Vec<_>>()
                 .join(" ")
         })
@@ -171,12 +384,21 @@ fn nanos_to_dt(nanos: u64) -> chrono::DateTime<Utc> {
 #[cfg(test)]
 mod tests {
     use opentelemetry_proto::tonic::common::v1::any_value::Value;
-    use opentelemetry_proto::tonic::common::v1::{AnyValue, KeyValue};
+    use opentelemetry_proto::tonic::common::v1::{AnyValue, EntityRef, KeyValue};
     use opentelemetry_proto::tonic::logs::v1::LogRecord as OtlpLogRecord;
     use opentelemetry_proto::tonic::resource::v1::Resource;
     use opentelemetry_proto::tonic::trace::v1::Span as OtlpSpan;
 
-    use super::{decode_log, decode_span};
+    use super::{LogTimeSource, decode_log, decode_metric, decode_span, nanos_to_dt};
+
+    fn string_kv(key: &str, value: &str) -> KeyValue {
+        KeyValue {
+            key: key.into(),
+            value: Some(AnyValue {
+                value: Some(Value::StringValue(value.into())),
+            }),
+        }
+    }
 
     #[test]
     fn decodes_log_and_service() {
@@ -221,6 +443,142 @@ mod tests {
         );
     }
 
+    #[test]
+    fn observed_time_is_kept_apart_from_event_time() {
+        let log = |time: u64, observed: u64| OtlpLogRecord {
+            time_unix_nano: time,
+            observed_time_unix_nano: observed,
+            ..Default::default()
+        };
+
+        let out = decode_log(
+            None,
+            None,
+            &log(1_700_000_000_000_000_000, 1_700_000_002_500_000_000),
+        );
+        assert_eq!(out.ts, nanos_to_dt(1_700_000_000_000_000_000));
+        assert_eq!(
+            out.observed_ts,
+            Some(nanos_to_dt(1_700_000_002_500_000_000))
+        );
+        assert_eq!(out.observed_delay_ms(), Some(2500));
+
+        // Without an event time, ts falls back to the observed time.
+        let out = decode_log(None, None, &log(0, 1_700_000_002_500_000_000));
+        assert_eq!(Some(out.ts), out.observed_ts);
+        assert_eq!(decode_log(None, None, &log(1, 0)).observed_ts, None);
+    }
+
+    #[test]
+    fn log_time_source_picks_between_event_and_observed() {
+        let event = 1_700_000_000_000_000_000;
+        let observed = 1_700_000_002_500_000_000;
+        let pick = |source: &str, time: u64, observed: u64| {
+            let mut out = decode_log(
+                None,
+                None,
+                &OtlpLogRecord {
+                    time_unix_nano: time,
+                    observed_time_unix_nano: observed,
+                    ..Default::default()
+                },
+            );
+            LogTimeSource::parse(source).apply(&mut out);
+            out.ts
+        };
+
+        assert_eq!(pick("event", event, observed), nanos_to_dt(event));
+        assert_eq!(pick("observed", event, observed), nanos_to_dt(observed));
+        assert_eq!(pick("min", event, observed), nanos_to_dt(event));
+        assert_eq!(pick("max", event, observed), nanos_to_dt(observed));
+        // A skewed SDK clock stamping events after they were observed.
+        assert_eq!(pick("min", observed, event), nanos_to_dt(event));
+        assert_eq!(pick("max", observed, event), nanos_to_dt(observed));
+
+        // Only one timestamp set: every mode keeps it.
+        for source in ["event", "observed", "min", "max"] {
+            assert_eq!(pick(source, event, 0), nanos_to_dt(event));
+            assert_eq!(pick(source, 0, observed), nanos_to_dt(observed));
+        }
+    }
+
+    #[test]
+    fn traceparent_attr_fills_missing_trace_context() {
+        let log = |traceparent: &str| OtlpLogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            attributes: vec![string_kv("traceparent", traceparent)],
+            ..Default::default()
+        };
+
+        let out = decode_log(
+            None,
+            None,
+            &log("00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01"),
+        );
+        assert_eq!(
+            out.trace_id.as_deref(),
+            Some("4bf92f3577b34da6a3ce929d0e0e4736")
+        );
+        assert_eq!(out.span_id.as_deref(), Some("00f067aa0ba902b7"));
+        assert!(out.is_sampled());
+
+        for bad in [
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "not a header",
+        ] {
+            let out = decode_log(None, None, &log(bad));
+            assert_eq!(out.trace_id, None, "{bad}");
+            assert_eq!(out.span_id, None, "{bad}");
+        }
+
+        let mut with_ids = log("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+        with_ids.trace_id = vec![1; 16];
+        let out = decode_log(None, None, &with_ids);
+        assert_eq!(
+            out.trace_id.as_deref(),
+            Some("01010101010101010101010101010101")
+        );
+    }
+
+    #[test]
+    fn sampled_flag_is_stored_and_filterable() {
+        let log = |body: &str, flags: u32| OtlpLogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            severity_number: 9,
+            body: Some(AnyValue {
+                value: Some(Value::StringValue(body.into())),
+            }),
+            flags,
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            ..Default::default()
+        };
+        let sampled = decode_log(None, None, &log("sampled", 0x01));
+        assert!(sampled.is_sampled());
+
+        let store = otell_store::Store::open_in_memory().unwrap();
+        store
+            .insert_logs(&[sampled, decode_log(None, None, &log("unsampled", 0x00))])
+            .unwrap();
+        let bodies = |filter| {
+            store
+                .search_logs(&otell_core::query::SearchRequest {
+                    sampled: filter,
+                    ..Default::default()
+                })
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|r| r.body)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bodies(Some(true)), vec!["sampled"]);
+        assert_eq!(bodies(Some(false)), vec!["unsampled"]);
+        assert_eq!(bodies(None).len(), 2);
+    }
+
     #[test]
     fn decodes_span_defaults_status() {
         let span = OtlpSpan {
@@ -238,4 +596,130 @@ mod tests {
         assert_eq!(out.status, "OK");
         assert_eq!(out.name, "call");
     }
+
+    #[test]
+    fn decode_span_keeps_status_message_only_for_errors() {
+        use opentelemetry_proto::tonic::trace::v1::Status;
+
+        let with_status = |code, message: &str| OtlpSpan {
+            trace_id: vec![1; 16],
+            span_id: vec![2; 8],
+            status: Some(Status {
+                message: message.into(),
+                code,
+            }),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode_span(None, &with_status(2, "db timeout")).status,
+            "db timeout"
+        );
+        assert_eq!(decode_span(None, &with_status(2, "")).status, "ERROR");
+        assert_eq!(decode_span(None, &with_status(1, "all good")).status, "OK");
+        assert_eq!(decode_span(None, &with_status(0, "retried")).status, "OK");
+    }
+
+    #[test]
+    fn numeric_attrs_keep_their_json_type() {
+        let kv = |key: &str, value: Value| KeyValue {
+            key: key.into(),
+            value: Some(AnyValue { value: Some(value) }),
+        };
+        let log = OtlpLogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            attributes: vec![
+                kv("http.status_code", Value::IntValue(500)),
+                kv("retry", Value::BoolValue(true)),
+                kv("ratio", Value::DoubleValue(0.25)),
+                kv("peer", Value::StringValue("redis".into())),
+            ],
+            ..Default::default()
+        };
+
+        let out = decode_log(None, None, &log);
+        let attrs: serde_json::Value = serde_json::from_str(&out.attrs_json).unwrap();
+        assert_eq!(attrs["http.status_code"], serde_json::json!(500));
+        assert!(attrs["http.status_code"].is_i64());
+        assert_eq!(attrs["retry"], serde_json::json!(true));
+        assert_eq!(attrs["ratio"], serde_json::json!(0.25));
+        assert_eq!(attrs["peer"], "redis");
+        assert!(out.attrs_text.contains("http.status_code=500"));
+        assert!(out.attrs_text.contains("retry=true"));
+    }
+
+    #[test]
+    fn entity_ref_ids_become_attrs() {
+        let resource = Resource {
+            attributes: vec![
+                string_kv("service.name", "api"),
+                string_kv("k8s.pod.uid", "pod-123"),
+                string_kv("k8s.pod.label", "ignored"),
+            ],
+            dropped_attributes_count: 0,
+            entity_refs: vec![EntityRef {
+                r#type: "k8s.pod".into(),
+                id_keys: vec!["k8s.pod.uid".into()],
+                description_keys: vec!["k8s.pod.label".into()],
+                ..Default::default()
+            }],
+        };
+        let log = OtlpLogRecord {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            attributes: vec![string_kv("peer", "redis:6379")],
+            ..Default::default()
+        };
+
+        let out = decode_log(Some(&resource), None, &log);
+        let attrs: serde_json::Value = serde_json::from_str(&out.attrs_json).unwrap();
+        assert_eq!(attrs["k8s.pod.uid"], "pod-123");
+        assert_eq!(attrs["peer"], "redis:6379");
+        assert!(attrs.get("k8s.pod.label").is_none());
+        assert!(out.attrs_text.contains("k8s.pod.uid=pod-123"));
+    }
+
+    #[test]
+    fn service_entity_ref_names_service() {
+        let resource = Resource {
+            attributes: vec![string_kv("service.instance.id", "checkout-7")],
+            dropped_attributes_count: 0,
+            entity_refs: vec![EntityRef {
+                r#type: "service".into(),
+                id_keys: vec!["service.instance.id".into()],
+                ..Default::default()
+            }],
+        };
+
+        let out = decode_span(Some(&resource), &OtlpSpan::default());
+        assert_eq!(out.service, "checkout-7");
+    }
+
+    #[test]
+    fn decodes_sum_metric_unit_and_kind() {
+        use opentelemetry_proto::tonic::metrics::v1::{
+            Metric, NumberDataPoint, Sum, metric, number_data_point,
+        };
+
+        let point = NumberDataPoint {
+            time_unix_nano: 1_700_000_000_000_000_000,
+            value: Some(number_data_point::Value::AsInt(42)),
+            ..Default::default()
+        };
+        let metric = Metric {
+            name: "http.server.requests".into(),
+            unit: "{request}".into(),
+            data: Some(metric::Data::Sum(Sum {
+                data_points: vec![point.clone()],
+                aggregation_temporality: 2,
+                is_monotonic: true,
+            })),
+            ..Default::default()
+        };
+
+        let out = decode_metric(None, &metric, &point);
+        assert_eq!(out.unit, "{request}");
+        assert_eq!(out.kind, "monotonic_sum");
+        assert_eq!(out.temporality, "cumulative");
+        assert_eq!(out.value, 42.0);
+    }
 }
@@ -18,11 +18,13 @@ use opentelemetry_proto::tonic::collector::trace::v1::trace_service_server::{
 use opentelemetry_proto::tonic::collector::trace::v1::{
     ExportTraceServiceRequest, ExportTraceServiceResponse,
 };
+use prost::Message;
 use tonic::{Request, Response, Status};
 
 use crate::forward::Forwarder;
-use crate::otlp::decode::{decode_log, decode_metric, decode_span};
+use crate::otlp::decode::{decode_logs_request, decode_metrics_request, decode_traces_request};
 use crate::pipeline::Pipeline;
+use crate::raw::{GRPC_CONTENT_TYPE, RawSignal};
 
 #[derive(Clone)]
 pub struct GrpcIngest {
@@ -49,6 +51,16 @@ impl GrpcIngest {
     pub fn metrics_service(&self) -> MetricsServiceServer<Self> {
         MetricsServiceServer::new(self.clone())
     }
+
+    /// tonic has already decoded the request, so re-encode it for the raw
+    /// capture; protobuf round-trips to the same message.
+    async fn capture(&self, signal: RawSignal, req: &impl Message) {
+        let raw = self.pipeline.raw_capture();
+        if raw.is_enabled() {
+            raw.record(signal, GRPC_CONTENT_TYPE, &req.encode_to_vec())
+                .await;
+        }
+    }
 }
 
 #[tonic::async_trait]
@@ -58,19 +70,11 @@ impl LogsService for GrpcIngest {
         request: Request<ExportLogsServiceRequest>,
     ) -> std::result::Result<Response<ExportLogsServiceResponse>, Status> {
         let req = request.into_inner();
+        self.capture(RawSignal::Logs, &req).await;
         if let Some(forwarder) = &self.forwarder {
             forwarder.submit_logs(req.clone()).await;
         }
-        let mut logs = Vec::new();
-        for rl in req.resource_logs {
-            let resource = rl.resource.as_ref();
-            for sl in rl.scope_logs {
-                let scope = sl.scope.as_ref();
-                for log in sl.log_records {
-                    logs.push(decode_log(resource, scope, &log));
-                }
-            }
-        }
+        let logs = decode_logs_request(req);
         tracing::debug!(count = logs.len(), "otlp grpc logs accepted");
         self.pipeline.submit_logs(logs).await;
         Ok(Response::new(ExportLogsServiceResponse::default()))
@@ -83,19 +87,13 @@ impl TraceService for GrpcIngest {
         &self,
         request: Request<ExportTraceServiceRequest>,
     ) -> std::result::Result<Response<ExportTraceServiceResponse>, Status> {
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+        self.capture(RawSignal::Traces, &req).await;
+        self.pipeline.drop_ignored_spans(&mut req);
         if let Some(forwarder) = &self.forwarder {
             forwarder.submit_traces(req.clone()).await;
         }
-        let mut spans = Vec::new();
-        for rs in req.resource_spans {
-            let resource = rs.resource.as_ref();
-            for ss in rs.scope_spans {
-                for span in ss.spans {
-                    spans.push(decode_span(resource, &span));
-                }
-            }
-        }
+        let spans = decode_traces_request(req);
         tracing::debug!(count = spans.len(), "otlp grpc traces accepted");
         self.pipeline.submit_spans(spans).await;
         Ok(Response::new(ExportTraceServiceResponse::default()))
@@ -109,32 +107,11 @@ impl MetricsService for GrpcIngest {
         request: Request<ExportMetricsServiceRequest>,
     ) -> std::result::Result<Response<ExportMetricsServiceResponse>, Status> {
         let req = request.into_inner();
+        self.capture(RawSignal::Metrics, &req).await;
         if let Some(forwarder) = &self.forwarder {
             forwarder.submit_metrics(req.clone()).await;
         }
-        let mut points = Vec::new();
-        for rm in req.resource_metrics {
-            let resource = rm.resource.as_ref();
-            for sm in rm.scope_metrics {
-                for metric in sm.metrics {
-                    if let Some(data) = &metric.data {
-                        if let opentelemetry_proto::tonic::metrics::v1::metric::Data::Gauge(g) =
-                            data
-                        {
-                            for point in &g.data_points {
-                                points.push(decode_metric(resource, &metric, point));
-                            }
-                        }
-                        if let opentelemetry_proto::tonic::metrics::v1::metric::Data::Sum(s) = data
-                        {
-                            for point in &s.data_points {
-                                points.push(decode_metric(resource, &metric, point));
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        let points = decode_metrics_request(req);
         tracing::debug!(count = points.len(), "otlp grpc metrics accepted");
         self.pipeline.submit_metrics(points).await;
         Ok(Response::new(ExportMetricsServiceResponse::default()))
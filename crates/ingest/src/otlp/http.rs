@@ -1,3 +1,4 @@
+use axum::Json;
 use axum::extract::State;
 use axum::http::header::CONTENT_TYPE;
 use axum::http::{HeaderMap, Method, StatusCode};
@@ -13,8 +14,10 @@ use tower_http::trace::TraceLayer;
 use tracing::Level;
 
 use crate::forward::Forwarder;
-use crate::otlp::decode::{decode_log, decode_metric, decode_span};
+use crate::ndjson::{NdjsonSummary, parse_metrics, parse_spans};
+use crate::otlp::decode::{decode_logs_request, decode_metrics_request, decode_traces_request};
 use crate::pipeline::Pipeline;
+use crate::raw::RawSignal;
 
 #[derive(Clone)]
 pub struct HttpIngestState {
@@ -35,6 +38,8 @@ pub fn router(pipeline: Pipeline, forwarder: Option<Forwarder>) -> Router {
         .route("/v1/logs", post(export_logs))
         .route("/v1/traces", post(export_traces))
         .route("/v1/metrics", post(export_metrics))
+        .route("/v1/traces/ndjson", post(export_traces_ndjson))
+        .route("/v1/metrics/ndjson", post(export_metrics_ndjson))
         .layer(cors)
         .layer(
             TraceLayer::new_for_http()
@@ -49,6 +54,11 @@ async fn export_logs(
     headers: HeaderMap,
     body: Bytes,
 ) -> StatusCode {
+    state
+        .pipeline
+        .raw_capture()
+        .record(RawSignal::Logs, content_type(&headers), &body)
+        .await;
     let Ok(req) = decode_otlp_http_payload::<ExportLogsServiceRequest>("logs", &headers, &body)
     else {
         return StatusCode::BAD_REQUEST;
@@ -57,16 +67,7 @@ async fn export_logs(
         forwarder.submit_logs(req.clone()).await;
     }
 
-    let mut logs = Vec::new();
-    for rl in req.resource_logs {
-        let resource = rl.resource.as_ref();
-        for sl in rl.scope_logs {
-            let scope = sl.scope.as_ref();
-            for log in sl.log_records {
-                logs.push(decode_log(resource, scope, &log));
-            }
-        }
-    }
+    let logs = decode_logs_request(req);
     tracing::debug!(count = logs.len(), "otlp http logs accepted");
     state.pipeline.submit_logs(logs).await;
     StatusCode::OK
@@ -77,23 +78,22 @@ async fn export_traces(
     headers: HeaderMap,
     body: Bytes,
 ) -> StatusCode {
-    let Ok(req) = decode_otlp_http_payload::<ExportTraceServiceRequest>("traces", &headers, &body)
+    state
+        .pipeline
+        .raw_capture()
+        .record(RawSignal::Traces, content_type(&headers), &body)
+        .await;
+    let Ok(mut req) =
+        decode_otlp_http_payload::<ExportTraceServiceRequest>("traces", &headers, &body)
     else {
         return StatusCode::BAD_REQUEST;
     };
+    state.pipeline.drop_ignored_spans(&mut req);
     if let Some(forwarder) = &state.forwarder {
         forwarder.submit_traces(req.clone()).await;
     }
 
-    let mut spans = Vec::new();
-    for rs in req.resource_spans {
-        let resource = rs.resource.as_ref();
-        for ss in rs.scope_spans {
-            for span in ss.spans {
-                spans.push(decode_span(resource, &span));
-            }
-        }
-    }
+    let spans = decode_traces_request(req);
     tracing::debug!(count = spans.len(), "otlp http traces accepted");
     state.pipeline.submit_spans(spans).await;
     StatusCode::OK
@@ -104,6 +104,11 @@ async fn export_metrics(
     headers: HeaderMap,
     body: Bytes,
 ) -> StatusCode {
+    state
+        .pipeline
+        .raw_capture()
+        .record(RawSignal::Metrics, content_type(&headers), &body)
+        .await;
     let Ok(req) =
         decode_otlp_http_payload::<ExportMetricsServiceRequest>("metrics", &headers, &body)
     else {
@@ -113,39 +118,57 @@ async fn export_metrics(
         forwarder.submit_metrics(req.clone()).await;
     }
 
-    let mut points = Vec::new();
-    for rm in req.resource_metrics {
-        let resource = rm.resource.as_ref();
-        for sm in rm.scope_metrics {
-            for metric in sm.metrics {
-                if let Some(data) = &metric.data {
-                    if let opentelemetry_proto::tonic::metrics::v1::metric::Data::Gauge(g) = data {
-                        for point in &g.data_points {
-                            points.push(decode_metric(resource, &metric, point));
-                        }
-                    }
-                    if let opentelemetry_proto::tonic::metrics::v1::metric::Data::Sum(s) = data {
-                        for point in &s.data_points {
-                            points.push(decode_metric(resource, &metric, point));
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let points = decode_metrics_request(req);
     tracing::debug!(count = points.len(), "otlp http metrics accepted");
     state.pipeline.submit_metrics(points).await;
     StatusCode::OK
 }
 
-fn is_json_content_type(headers: &HeaderMap) -> bool {
-    let Some(content_type) = headers
+/// Simplified span ingest: one JSON span per line, decoded straight into
+/// `SpanRecord`. Not forwarded upstream since there is no OTLP request.
+async fn export_traces_ndjson(
+    State(state): State<HttpIngestState>,
+    body: Bytes,
+) -> (StatusCode, Json<NdjsonSummary>) {
+    let (mut spans, summary) = parse_spans(&body);
+    state.pipeline.drop_ignored_span_records(&mut spans);
+    tracing::debug!(
+        accepted = summary.accepted,
+        rejected = summary.rejected,
+        "ndjson traces accepted"
+    );
+    state.pipeline.submit_spans(spans).await;
+    (ndjson_status(&summary), Json(summary))
+}
+
+async fn export_metrics_ndjson(
+    State(state): State<HttpIngestState>,
+    body: Bytes,
+) -> (StatusCode, Json<NdjsonSummary>) {
+    let (points, summary) = parse_metrics(&body);
+    tracing::debug!(
+        accepted = summary.accepted,
+        rejected = summary.rejected,
+        "ndjson metrics accepted"
+    );
+    state.pipeline.submit_metrics(points).await;
+    (ndjson_status(&summary), Json(summary))
+}
+
+/// Partial batches still succeed; only a batch with nothing usable is a 400.
+fn ndjson_status(summary: &NdjsonSummary) -> StatusCode {
+    if summary.accepted == 0 && summary.rejected > 0 {
+        StatusCode::BAD_REQUEST
+    } else {
+        StatusCode::OK
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> &str {
+    headers
         .get(CONTENT_TYPE)
         .and_then(|value| value.to_str().ok())
-    else {
-        return false;
-    };
-    content_type.to_ascii_lowercase().contains("json")
+        .unwrap_or("<missing>")
 }
 
 fn decode_otlp_http_payload<T>(
@@ -156,12 +179,20 @@ fn decode_otlp_http_payload<T>(
 where
     T: Message + Default + DeserializeOwned,
 {
-    let content_type = headers
-        .get(CONTENT_TYPE)
-        .and_then(|value| value.to_str().ok())
-        .unwrap_or("<missing>");
+    decode_otlp_payload(signal, content_type(headers), body).map_err(|_| StatusCode::BAD_REQUEST)
+}
 
-    if is_json_content_type(headers) {
+/// Decodes an OTLP export body as JSON or protobuf, guided by (but not
+/// trusting) `content_type`. On failure the error names both decode attempts.
+pub(crate) fn decode_otlp_payload<T>(
+    signal: &'static str,
+    content_type: &str,
+    body: &[u8],
+) -> Result<T, String>
+where
+    T: Message + Default + DeserializeOwned,
+{
+    if content_type.to_ascii_lowercase().contains("json") {
         match serde_json::from_slice::<T>(body) {
             Ok(req) => return Ok(req),
             Err(json_err) => match T::decode(body) {
@@ -182,7 +213,7 @@ where
                         protobuf_error = %proto_err,
                         "otlp http payload decode failed",
                     );
-                    return Err(StatusCode::BAD_REQUEST);
+                    return Err(format!("json: {json_err}; protobuf: {proto_err}"));
                 }
             },
         }
@@ -208,7 +239,7 @@ where
                     json_error = %json_err,
                     "otlp http payload decode failed",
                 );
-                Err(StatusCode::BAD_REQUEST)
+                Err(format!("protobuf: {proto_err}; json: {json_err}"))
             }
         },
     }
@@ -1,23 +1,52 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
+use chrono::{DateTime, Utc};
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use otell_core::model::log::LogRecord;
 use otell_core::model::metric::MetricPoint;
 use otell_core::model::span::SpanRecord;
 use otell_store::Store;
+use otell_store::pending::{PendingSignal, PendingWrites};
 use tokio::sync::mpsc;
-use tracing::warn;
+use tracing::{debug, warn};
+
+use crate::body::BodyParser;
+use crate::otlp::decode::LogTimeSource;
+use crate::raw::RawCapture;
 
 #[derive(Clone)]
 pub struct Pipeline {
     logs_tx: mpsc::Sender<Vec<LogRecord>>,
     spans_tx: mpsc::Sender<Vec<SpanRecord>>,
     metrics_tx: mpsc::Sender<Vec<MetricPoint>>,
+    reject_older_than: Option<Duration>,
+    rejected_too_old: Arc<AtomicU64>,
+    body_parser: BodyParser,
+    log_time_source: LogTimeSource,
+    drop_span_names: Arc<Vec<glob::Pattern>>,
+    pending: Arc<PendingWrites>,
+    raw_capture: RawCapture,
 }
 
 pub struct PipelineConfig {
     pub channel_capacity: usize,
     pub flush_interval: Duration,
     pub batch_size: usize,
+    /// Per-signal overrides of `batch_size`/`flush_interval`, so bursty logs
+    /// can flush on count while sparse spans flush on time.
+    pub signal_flush: SignalFlush,
+    /// Drop records whose timestamp is older than this at submit time.
+    pub reject_older_than: Option<Duration>,
+    /// Extract structured fields from log bodies into attributes.
+    pub body_parser: BodyParser,
+    /// Which of a log's event/observed timestamps becomes its `ts`.
+    pub log_time_source: LogTimeSource,
+    /// Span name globs (e.g. `GET /healthz`) dropped before forwarding and storage.
+    pub drop_span_names: Vec<String>,
+    /// Copy of every inbound export request, written before decoding.
+    pub raw_capture: RawCapture,
 }
 
 impl Default for PipelineConfig {
@@ -26,56 +55,205 @@ impl Default for PipelineConfig {
             channel_capacity: 256,
             flush_interval: Duration::from_millis(200),
             batch_size: 2048,
+            signal_flush: SignalFlush::default(),
+            reject_older_than: None,
+            body_parser: BodyParser::Off,
+            log_time_source: LogTimeSource::Event,
+            drop_span_names: Vec::new(),
+            raw_capture: RawCapture::disabled(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SignalFlush {
+    pub logs: FlushOverride,
+    pub spans: FlushOverride,
+    pub metrics: FlushOverride,
+}
+
+/// Unset fields fall back to the shared `PipelineConfig` values.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlushOverride {
+    pub batch_size: Option<usize>,
+    pub flush_interval: Option<Duration>,
+}
+
+impl FlushOverride {
+    fn resolve(self, batch_size: usize, flush_interval: Duration) -> (usize, Duration) {
+        (
+            self.batch_size.unwrap_or(batch_size),
+            self.flush_interval.unwrap_or(flush_interval),
+        )
+    }
+}
+
 impl Pipeline {
     pub fn new(store: Store, cfg: PipelineConfig) -> Self {
+        let drop_span_names = cfg
+            .drop_span_names
+            .iter()
+            .filter_map(|p| match glob::Pattern::new(p) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    warn!(pattern = %p, error = %e, "ignoring invalid drop span name pattern");
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        let pending = store.pending_writes();
         let (logs_tx, logs_rx) = mpsc::channel(cfg.channel_capacity);
         let (spans_tx, spans_rx) = mpsc::channel(cfg.channel_capacity);
         let (metrics_tx, metrics_rx) = mpsc::channel(cfg.channel_capacity);
 
-        tokio::spawn(run_log_writer(
-            store.clone(),
-            logs_rx,
-            cfg.batch_size,
-            cfg.flush_interval,
-        ));
+        let (log_batch, log_flush) = cfg
+            .signal_flush
+            .logs
+            .resolve(cfg.batch_size, cfg.flush_interval);
+        let (span_batch, span_flush) = cfg
+            .signal_flush
+            .spans
+            .resolve(cfg.batch_size, cfg.flush_interval);
+        let (metric_batch, metric_flush) = cfg
+            .signal_flush
+            .metrics
+            .resolve(cfg.batch_size, cfg.flush_interval);
+        tokio::spawn(run_log_writer(store.clone(), logs_rx, log_batch, log_flush));
         tokio::spawn(run_span_writer(
             store.clone(),
             spans_rx,
-            cfg.batch_size,
-            cfg.flush_interval,
+            span_batch,
+            span_flush,
         ));
         tokio::spawn(run_metric_writer(
             store,
             metrics_rx,
-            cfg.batch_size,
-            cfg.flush_interval,
+            metric_batch,
+            metric_flush,
         ));
 
         Self {
             logs_tx,
             spans_tx,
             metrics_tx,
+            reject_older_than: cfg.reject_older_than,
+            rejected_too_old: Arc::new(AtomicU64::new(0)),
+            body_parser: cfg.body_parser,
+            log_time_source: cfg.log_time_source,
+            drop_span_names: Arc::new(drop_span_names),
+            pending,
+            raw_capture: cfg.raw_capture,
+        }
+    }
+
+    /// Removes spans whose name matches a drop pattern from an inbound
+    /// request, so they are neither forwarded nor stored.
+    pub fn drop_ignored_spans(&self, req: &mut ExportTraceServiceRequest) {
+        if self.drop_span_names.is_empty() {
+            return;
+        }
+        let mut dropped = 0;
+        for rs in &mut req.resource_spans {
+            for ss in &mut rs.scope_spans {
+                let before = ss.spans.len();
+                ss.spans.retain(|span| !self.drops_span_name(&span.name));
+                dropped += before - ss.spans.len();
+            }
+        }
+        if dropped > 0 {
+            debug!(dropped, "dropped spans matching drop_span_names");
+        }
+    }
+
+    /// Same as [`Self::drop_ignored_spans`] for spans that arrive already
+    /// decoded (ndjson ingest).
+    pub fn drop_ignored_span_records(&self, spans: &mut Vec<SpanRecord>) {
+        if self.drop_span_names.is_empty() {
+            return;
+        }
+        let before = spans.len();
+        spans.retain(|span| !self.drops_span_name(&span.name));
+        let dropped = before - spans.len();
+        if dropped > 0 {
+            debug!(dropped, "dropped spans matching drop_span_names");
         }
     }
 
-    pub async fn submit_logs(&self, logs: Vec<LogRecord>) {
+    fn drops_span_name(&self, name: &str) -> bool {
+        self.drop_span_names.iter().any(|p| p.matches(name))
+    }
+
+    pub fn raw_capture(&self) -> &RawCapture {
+        &self.raw_capture
+    }
+
+    /// Number of records dropped so far for being older than `reject_older_than`.
+    pub fn rejected_too_old(&self) -> u64 {
+        self.rejected_too_old.load(Ordering::Relaxed)
+    }
+
+    fn retain_recent<T>(&self, records: &mut Vec<T>, ts: impl Fn(&T) -> DateTime<Utc>) {
+        let Some(max_age) = self.reject_older_than else {
+            return;
+        };
+        let Ok(max_age) = chrono::Duration::from_std(max_age) else {
+            return;
+        };
+        let cutoff = Utc::now() - max_age;
+        let before = records.len();
+        records.retain(|r| ts(r) >= cutoff);
+        let dropped = (before - records.len()) as u64;
+        if dropped > 0 {
+            self.rejected_too_old.fetch_add(dropped, Ordering::Relaxed);
+            debug!(dropped, %cutoff, "rejected records older than ingest window");
+        }
+    }
+
+    pub async fn submit_logs(&self, mut logs: Vec<LogRecord>) {
+        if self.log_time_source != LogTimeSource::Event {
+            for log in &mut logs {
+                self.log_time_source.apply(log);
+            }
+        }
+        self.retain_recent(&mut logs, |l| l.ts);
+        if logs.is_empty() {
+            return;
+        }
+        if self.body_parser != BodyParser::Off {
+            for log in &mut logs {
+                self.body_parser.apply(log);
+            }
+        }
+        let n = logs.len();
+        self.pending.logs.accepted(n);
         if self.logs_tx.send(logs).await.is_err() {
+            self.pending.logs.flushed(n);
             warn!("log pipeline dropped batch: receiver closed");
         }
     }
 
-    pub async fn submit_spans(&self, spans: Vec<SpanRecord>) {
+    pub async fn submit_spans(&self, mut spans: Vec<SpanRecord>) {
+        self.retain_recent(&mut spans, |s| s.end_ts);
+        if spans.is_empty() {
+            return;
+        }
+        let n = spans.len();
+        self.pending.spans.accepted(n);
         if self.spans_tx.send(spans).await.is_err() {
+            self.pending.spans.flushed(n);
             warn!("span pipeline dropped batch: receiver closed");
         }
     }
 
-    pub async fn submit_metrics(&self, metrics: Vec<MetricPoint>) {
+    pub async fn submit_metrics(&self, mut metrics: Vec<MetricPoint>) {
+        self.retain_recent(&mut metrics, |m| m.ts);
+        if metrics.is_empty() {
+            return;
+        }
+        let n = metrics.len();
+        self.pending.metrics.accepted(n);
         if self.metrics_tx.send(metrics).await.is_err() {
+            self.pending.metrics.flushed(n);
             warn!("metric pipeline dropped batch: receiver closed");
         }
     }
@@ -89,17 +267,18 @@ async fn run_log_writer(
 ) {
     let mut ticker = tokio::time::interval(flush_interval);
     let mut buffer = Vec::new();
+    let mut failed_flushes = 0;
     loop {
         tokio::select! {
             Some(batch) = rx.recv() => {
                 buffer.extend(batch);
                 if buffer.len() >= batch_size {
-                    flush_logs(&store, &mut buffer);
+                    flush_logs(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             _ = ticker.tick() => {
                 if !buffer.is_empty() {
-                    flush_logs(&store, &mut buffer);
+                    flush_logs(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             else => break,
@@ -115,17 +294,18 @@ async fn run_span_writer(
 ) {
     let mut ticker = tokio::time::interval(flush_interval);
     let mut buffer = Vec::new();
+    let mut failed_flushes = 0;
     loop {
         tokio::select! {
             Some(batch) = rx.recv() => {
                 buffer.extend(batch);
                 if buffer.len() >= batch_size {
-                    flush_spans(&store, &mut buffer);
+                    flush_spans(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             _ = ticker.tick() => {
                 if !buffer.is_empty() {
-                    flush_spans(&store, &mut buffer);
+                    flush_spans(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             else => break,
@@ -141,17 +321,18 @@ async fn run_metric_writer(
 ) {
     let mut ticker = tokio::time::interval(flush_interval);
     let mut buffer = Vec::new();
+    let mut failed_flushes = 0;
     loop {
         tokio::select! {
             Some(batch) = rx.recv() => {
                 buffer.extend(batch);
                 if buffer.len() >= batch_size {
-                    flush_metrics(&store, &mut buffer);
+                    flush_metrics(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             _ = ticker.tick() => {
                 if !buffer.is_empty() {
-                    flush_metrics(&store, &mut buffer);
+                    flush_metrics(&store, &mut buffer, &mut failed_flushes).await;
                 }
             }
             else => break,
@@ -159,25 +340,156 @@ async fn run_metric_writer(
     }
 }
 
-fn flush_logs(store: &Store, buffer: &mut Vec<LogRecord>) {
-    if let Err(e) = store.insert_logs(buffer) {
-        warn!(error = ?e, "failed to write log batch");
+/// Insert attempts per flush before the batch is carried over to the next one.
+const FLUSH_ATTEMPTS: u32 = 3;
+const FLUSH_RETRY_DELAY: Duration = Duration::from_millis(50);
+/// Records kept across failed flushes; older records are dropped beyond this.
+const MAX_RETAINED_RECORDS: usize = 65_536;
+/// Consecutive failed flushes after which the retained batch is dropped.
+const MAX_FAILED_FLUSHES: u32 = 5;
+
+async fn flush_logs(store: &Store, buffer: &mut Vec<LogRecord>, failed_flushes: &mut u32) {
+    let pending = store.pending_writes();
+    flush_with_retry(buffer, "log", &pending.logs, failed_flushes, |b| {
+        store.insert_logs(b)
+    })
+    .await;
+}
+
+async fn flush_spans(store: &Store, buffer: &mut Vec<SpanRecord>, failed_flushes: &mut u32) {
+    let pending = store.pending_writes();
+    flush_with_retry(buffer, "span", &pending.spans, failed_flushes, |b| {
+        store.insert_spans(b)
+    })
+    .await;
+}
+
+async fn flush_metrics(store: &Store, buffer: &mut Vec<MetricPoint>, failed_flushes: &mut u32) {
+    let pending = store.pending_writes();
+    flush_with_retry(buffer, "metric", &pending.metrics, failed_flushes, |b| {
+        store.insert_metrics(b)
+    })
+    .await;
+}
+
+/// Writes `buffer`, retrying transient failures.
+///
+/// When the whole batch keeps failing it is written in halves. If any part
+/// lands, the store is healthy and the records that still fail on their own
+/// are dropped as bad data. If nothing lands, the batch stays buffered for
+/// the next flush: at most `MAX_RETAINED_RECORDS` records, and for at most
+/// `MAX_FAILED_FLUSHES` flushes in a row.
+async fn flush_with_retry<T>(
+    buffer: &mut Vec<T>,
+    kind: &str,
+    pending: &PendingSignal,
+    failed_flushes: &mut u32,
+    insert: impl Fn(&[T]) -> otell_core::Result<()>,
+) {
+    let mut last_error = None;
+    for attempt in 1..=FLUSH_ATTEMPTS {
+        match insert(buffer) {
+            Ok(()) => {
+                pending.flushed(buffer.len());
+                buffer.clear();
+                *failed_flushes = 0;
+                return;
+            }
+            Err(e) if attempt < FLUSH_ATTEMPTS => {
+                debug!(error = ?e, attempt, "retrying {kind} batch write");
+                tokio::time::sleep(FLUSH_RETRY_DELAY * attempt).await;
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    if let Some((written, rejected)) = write_isolating_bad_records(buffer, &insert) {
+        pending.flushed(buffer.len());
+        buffer.clear();
+        *failed_flushes = 0;
+        warn!(
+            error = ?last_error,
+            written,
+            dropped = rejected.len(),
+            first_dropped_index = rejected.first().copied(),
+            "dropped {kind} records that fail to write on their own"
+        );
+        return;
+    }
+
+    *failed_flushes += 1;
+    if *failed_flushes >= MAX_FAILED_FLUSHES {
+        warn!(
+            error = ?last_error,
+            dropped = buffer.len(),
+            failed_flushes = *failed_flushes,
+            "dropped buffered {kind} records after repeated write failures"
+        );
+        pending.flushed(buffer.len());
+        buffer.clear();
+        *failed_flushes = 0;
+        return;
+    }
+    warn!(error = ?last_error, records = buffer.len(), "failed to write {kind} batch; keeping it for the next flush");
+    if buffer.len() > MAX_RETAINED_RECORDS {
+        let dropped = buffer.len() - MAX_RETAINED_RECORDS;
+        buffer.drain(..dropped);
+        pending.flushed(dropped);
+        warn!(
+            dropped,
+            "dropped oldest buffered {kind} records after repeated write failures"
+        );
     }
-    buffer.clear();
 }
 
-fn flush_spans(store: &Store, buffer: &mut Vec<SpanRecord>) {
-    if let Err(e) = store.insert_spans(buffer) {
-        warn!(error = ?e, "failed to write span batch");
+/// Writes the two halves of a failed batch separately. `None` when neither
+/// half lands (the store itself is failing); otherwise recurses into failing
+/// halves and returns the number written plus the indices that were rejected.
+fn write_isolating_bad_records<T>(
+    records: &[T],
+    insert: &impl Fn(&[T]) -> otell_core::Result<()>,
+) -> Option<(usize, Vec<usize>)> {
+    if records.len() < 2 {
+        return None;
+    }
+    let (left, right) = records.split_at(records.len() / 2);
+    let left_ok = insert(left).is_ok();
+    let right_ok = insert(right).is_ok();
+    if !left_ok && !right_ok {
+        return None;
     }
-    buffer.clear();
+    let mut written = 0;
+    let mut rejected = Vec::new();
+    for (half, ok, offset) in [(left, left_ok, 0), (right, right_ok, left.len())] {
+        if ok {
+            written += half.len();
+        } else {
+            written += bisect(half, offset, insert, &mut rejected);
+        }
+    }
+    Some((written, rejected))
 }
 
-fn flush_metrics(store: &Store, buffer: &mut Vec<MetricPoint>) {
-    if let Err(e) = store.insert_metrics(buffer) {
-        warn!(error = ?e, "failed to write metric batch");
+fn bisect<T>(
+    records: &[T],
+    offset: usize,
+    insert: &impl Fn(&[T]) -> otell_core::Result<()>,
+    rejected: &mut Vec<usize>,
+) -> usize {
+    if records.len() == 1 {
+        rejected.push(offset);
+        return 0;
+    }
+    let (left, right) = records.split_at(records.len() / 2);
+    let mut written = 0;
+    for (half, half_offset) in [(left, offset), (right, offset + left.len())] {
+        written += if insert(half).is_ok() {
+            half.len()
+        } else {
+            bisect(half, half_offset, insert, rejected)
+        };
     }
-    buffer.clear();
+    written
 }
 
 #[cfg(test)]
@@ -197,6 +509,12 @@ mod tests {
                 channel_capacity: 8,
                 flush_interval: std::time::Duration::from_millis(10),
                 batch_size: 4,
+                signal_flush: SignalFlush::default(),
+                reject_older_than: None,
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: Vec::new(),
+                raw_capture: RawCapture::disabled(),
             },
         );
 
@@ -211,6 +529,8 @@ mod tests {
                 body: "error".into(),
                 attrs_json: "{}".into(),
                 attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
             }])
             .await;
 
@@ -229,6 +549,12 @@ mod tests {
                 channel_capacity: 8,
                 flush_interval: std::time::Duration::from_secs(5),
                 batch_size: 2,
+                signal_flush: SignalFlush::default(),
+                reject_older_than: None,
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: Vec::new(),
+                raw_capture: RawCapture::disabled(),
             },
         );
 
@@ -244,6 +570,8 @@ mod tests {
                     body: format!("line{i}"),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 }])
                 .await;
         }
@@ -252,4 +580,365 @@ mod tests {
         let res = store.search_logs(&SearchRequest::default()).unwrap();
         assert_eq!(res.total_matches, 2);
     }
+
+    #[tokio::test]
+    async fn pipeline_rejects_records_older_than_window() {
+        let store = Store::open_in_memory().unwrap();
+        let pipeline = Pipeline::new(
+            store.clone(),
+            PipelineConfig {
+                channel_capacity: 8,
+                flush_interval: std::time::Duration::from_millis(10),
+                batch_size: 4,
+                signal_flush: SignalFlush::default(),
+                reject_older_than: Some(std::time::Duration::from_secs(3600)),
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: Vec::new(),
+                raw_capture: RawCapture::disabled(),
+            },
+        );
+
+        let log = |ts, body: &str| LogRecord {
+            ts,
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+        pipeline
+            .submit_logs(vec![
+                log(
+                    Utc.with_ymd_and_hms(2001, 1, 1, 0, 0, 0).unwrap(),
+                    "ancient",
+                ),
+                log(Utc::now(), "fresh"),
+            ])
+            .await;
+
+        tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+        assert_eq!(pipeline.rejected_too_old(), 1);
+        let res = store.search_logs(&SearchRequest::default()).unwrap();
+        assert_eq!(res.total_matches, 1);
+        assert_eq!(res.records[0].body, "fresh");
+    }
+
+    #[tokio::test]
+    async fn pipeline_drops_spans_matching_drop_list() {
+        use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
+
+        use crate::otlp::decode::decode_span;
+
+        let store = Store::open_in_memory().unwrap();
+        let pipeline = Pipeline::new(
+            store.clone(),
+            PipelineConfig {
+                channel_capacity: 8,
+                flush_interval: std::time::Duration::from_millis(10),
+                batch_size: 4,
+                signal_flush: SignalFlush::default(),
+                reject_older_than: None,
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: vec!["GET /healthz".into(), "GET /ready*".into()],
+                raw_capture: RawCapture::disabled(),
+            },
+        );
+
+        let now = Utc::now().timestamp_nanos_opt().unwrap() as u64;
+        let span = |name: &str, span_id: u8| Span {
+            trace_id: vec![span_id; 16],
+            span_id: vec![span_id; 8],
+            name: name.into(),
+            start_time_unix_nano: now,
+            end_time_unix_nano: now + 1_000,
+            ..Default::default()
+        };
+        let mut req = ExportTraceServiceRequest {
+            resource_spans: vec![ResourceSpans {
+                scope_spans: vec![ScopeSpans {
+                    spans: vec![
+                        span("GET /healthz", 1),
+                        span("GET /readyz", 2),
+                        span("GET /checkout", 3),
+                    ],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        };
+        pipeline.drop_ignored_spans(&mut req);
+
+        let spans = req
+            .resource_spans
+            .iter()
+            .flat_map(|rs| {
+                rs.scope_spans.iter().flat_map(move |ss| {
+                    ss.spans
+                        .iter()
+                        .map(move |s| decode_span(rs.resource.as_ref(), s))
+                })
+            })
+            .collect::<Vec<_>>();
+        pipeline.submit_spans(spans).await;
+
+        tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+        let traces = store
+            .list_traces(&otell_core::query::TracesRequest {
+                service: None,
+                status: None,
+                window: otell_core::filter::TimeWindow::all(),
+                sort: otell_core::filter::SortOrder::TsAsc,
+                limit: 10,
+                count_only: false,
+                attr_filters: Vec::new(),
+            })
+            .unwrap();
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].root_name, "GET /checkout");
+    }
+
+    #[tokio::test]
+    async fn flush_retries_transient_failures_and_keeps_batch() {
+        let store = Store::open_in_memory().unwrap();
+        let failures = std::cell::Cell::new(FLUSH_ATTEMPTS + 1);
+        let insert = |b: &[LogRecord]| {
+            if failures.get() > 0 {
+                failures.set(failures.get() - 1);
+                return Err(otell_core::OtellError::Store("database is locked".into()));
+            }
+            store.insert_logs(b)
+        };
+        let mut buffer = vec![LogRecord {
+            ts: Utc::now(),
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: "survivor".into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        }];
+
+        let pending = PendingSignal::default();
+        pending.accepted(1);
+        let mut failed_flushes = 0;
+        flush_with_retry(&mut buffer, "log", &pending, &mut failed_flushes, insert).await;
+        assert_eq!(buffer.len(), 1, "batch kept after exhausting retries");
+        assert_eq!(failed_flushes, 1);
+        assert_eq!(pending.count(), 1);
+        assert_eq!(failures.get(), 1);
+
+        flush_with_retry(&mut buffer, "log", &pending, &mut failed_flushes, insert).await;
+        assert!(buffer.is_empty());
+        assert_eq!(failed_flushes, 0);
+        assert_eq!(pending.count(), 0);
+        let res = store.search_logs(&SearchRequest::default()).unwrap();
+        assert_eq!(res.total_matches, 1);
+        assert_eq!(res.records[0].body, "survivor");
+    }
+
+    fn log(body: &str) -> LogRecord {
+        LogRecord {
+            ts: Utc::now(),
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn flush_drops_only_records_that_fail_on_their_own() {
+        let store = Store::open_in_memory().unwrap();
+        let insert = |b: &[LogRecord]| {
+            if b.iter().any(|l| l.body == "poison") {
+                return Err(otell_core::OtellError::Store("conversion error".into()));
+            }
+            store.insert_logs(b)
+        };
+        let mut buffer = ["a", "b", "poison", "c", "d"].map(log).to_vec();
+        let pending = PendingSignal::default();
+        pending.accepted(buffer.len());
+        let mut failed_flushes = 0;
+
+        flush_with_retry(&mut buffer, "log", &pending, &mut failed_flushes, insert).await;
+        assert!(buffer.is_empty());
+        assert_eq!(failed_flushes, 0);
+        assert_eq!(pending.count(), 0);
+        let res = store.search_logs(&SearchRequest::default()).unwrap();
+        let mut bodies = res
+            .records
+            .iter()
+            .map(|l| l.body.as_str())
+            .collect::<Vec<_>>();
+        bodies.sort();
+        assert_eq!(bodies, vec!["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn flush_gives_up_after_repeated_store_failures() {
+        let insert = |_: &[LogRecord]| -> otell_core::Result<()> {
+            Err(otell_core::OtellError::Store("disk full".into()))
+        };
+        let mut buffer = ["a", "b"].map(log).to_vec();
+        let pending = PendingSignal::default();
+        pending.accepted(buffer.len());
+        let mut failed_flushes = 0;
+
+        for _ in 1..MAX_FAILED_FLUSHES {
+            flush_with_retry(&mut buffer, "log", &pending, &mut failed_flushes, insert).await;
+            assert_eq!(buffer.len(), 2, "nothing landed, so the batch is kept");
+        }
+        flush_with_retry(&mut buffer, "log", &pending, &mut failed_flushes, insert).await;
+        assert!(buffer.is_empty());
+        assert_eq!(failed_flushes, 0);
+        assert_eq!(pending.count(), 0);
+    }
+
+    // Paused time: each sleep runs the writer tasks until they are idle
+    // before the clock moves, so the waits don't depend on machine speed.
+    #[tokio::test(start_paused = true)]
+    async fn pending_counts_drop_to_zero_after_flush() {
+        let store = Store::open_in_memory().unwrap();
+        let pipeline = Pipeline::new(
+            store.clone(),
+            PipelineConfig {
+                channel_capacity: 8,
+                flush_interval: std::time::Duration::from_secs(5),
+                batch_size: 3,
+                signal_flush: SignalFlush::default(),
+                reject_older_than: None,
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: Vec::new(),
+                raw_capture: RawCapture::disabled(),
+            },
+        );
+        let log = |body: &str| LogRecord {
+            ts: Utc::now(),
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+
+        // Let the writer consume the interval's immediate first tick.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        pipeline.submit_logs(vec![log("a"), log("b")]).await;
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        let status = store.status().unwrap();
+        assert_eq!(status.pending_logs, 2);
+        assert_eq!(status.logs_count, 0);
+
+        // Reaching batch_size flushes everything buffered.
+        pipeline.submit_logs(vec![log("c")]).await;
+        tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+        let status = store.status().unwrap();
+        assert_eq!(status.logs_count, 3);
+        assert_eq!(status.pending_logs, 0);
+        assert_eq!(status.max_flush_lag_ms, 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn spans_flush_on_their_own_batch_size() {
+        let store = Store::open_in_memory().unwrap();
+        let pipeline = Pipeline::new(
+            store.clone(),
+            PipelineConfig {
+                channel_capacity: 8,
+                flush_interval: std::time::Duration::from_secs(60),
+                batch_size: 100,
+                signal_flush: SignalFlush {
+                    spans: FlushOverride {
+                        batch_size: Some(2),
+                        flush_interval: None,
+                    },
+                    metrics: FlushOverride {
+                        batch_size: None,
+                        flush_interval: Some(std::time::Duration::from_secs(1)),
+                    },
+                    ..SignalFlush::default()
+                },
+                reject_older_than: None,
+                body_parser: BodyParser::Off,
+                log_time_source: LogTimeSource::Event,
+                drop_span_names: Vec::new(),
+                raw_capture: RawCapture::disabled(),
+            },
+        );
+        let now = Utc::now();
+        let span = |span_id: &str| SpanRecord {
+            trace_id: "t1".into(),
+            span_id: span_id.into(),
+            parent_span_id: None,
+            service: "api".into(),
+            name: "GET /".into(),
+            start_ts: now,
+            end_ts: now,
+            status: "OK".into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        };
+        let log = |body: &str| LogRecord {
+            ts: now,
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+
+        // Let the writers consume the interval's immediate first tick.
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        pipeline.submit_logs(vec![log("a"), log("b")]).await;
+        pipeline.submit_spans(vec![span("s1"), span("s2")]).await;
+        tokio::time::sleep(std::time::Duration::from_millis(40)).await;
+
+        let status = store.status().unwrap();
+        assert_eq!(status.spans_count, 2);
+        assert_eq!(status.pending_spans, 0);
+        assert_eq!(status.logs_count, 0);
+        assert_eq!(status.pending_logs, 2);
+
+        // Metrics flush on their own interval, well before the shared one.
+        pipeline
+            .submit_metrics(vec![MetricPoint {
+                ts: now,
+                name: "requests".into(),
+                service: "api".into(),
+                value: 1.0,
+                attrs_json: "{}".into(),
+                unit: String::new(),
+                kind: "gauge".into(),
+                temporality: String::new(),
+            }])
+            .await;
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        let status = store.status().unwrap();
+        assert_eq!(status.metrics_count, 1);
+        assert_eq!(status.pending_metrics, 0);
+        assert_eq!(status.pending_logs, 2);
+    }
 }
@@ -0,0 +1,363 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
+use opentelemetry_proto::tonic::collector::metrics::v1::ExportMetricsServiceRequest;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
+use otell_core::error::{OtellError, Result};
+use otell_core::model::log::LogRecord;
+use otell_core::model::metric::MetricPoint;
+use otell_core::model::span::SpanRecord;
+use serde::{Deserialize, Serialize};
+
+use crate::otlp::decode::{decode_logs_request, decode_metrics_request, decode_traces_request};
+use crate::otlp::http::decode_otlp_payload;
+
+/// Size at which the capture file is rotated to `<path>.1`, and the largest
+/// payload a frame may carry.
+pub const RAW_OTLP_MAX_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Content type recorded for gRPC requests, which arrive as protobuf.
+pub const GRPC_CONTENT_TYPE: &str = "application/grpc+proto";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RawSignal {
+    Logs,
+    Traces,
+    Metrics,
+}
+
+impl RawSignal {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Logs => "logs",
+            Self::Traces => "traces",
+            Self::Metrics => "metrics",
+        }
+    }
+}
+
+/// Opt-in capture of inbound OTLP export requests (`OTELL_STORE_RAW_OTLP`),
+/// written before decoding so `otell replay` can re-run the decoder on the
+/// exact bytes an SDK sent.
+///
+/// Each frame is a JSON header line (`ts`, `signal`, `content_type`, `len`)
+/// followed by `len` payload bytes and a newline. When the file would grow
+/// past `max_bytes` it is renamed to `<path>.1`, replacing the previous one.
+#[derive(Clone, Default)]
+pub struct RawCapture {
+    inner: Option<Arc<Mutex<RawFile>>>,
+}
+
+struct RawFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    max_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FrameHeader {
+    ts: DateTime<Utc>,
+    signal: RawSignal,
+    content_type: String,
+    len: usize,
+}
+
+impl RawCapture {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn open(path: &Path, max_bytes: u64) -> Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                OtellError::Config(format!("create raw OTLP dir {}: {e}", parent.display()))
+            })?;
+        }
+        let file = open_append(path)?;
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            inner: Some(Arc::new(Mutex::new(RawFile {
+                path: path.to_path_buf(),
+                file,
+                len,
+                max_bytes,
+            }))),
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Appends one frame. The file write runs on the blocking pool so a slow
+    /// disk doesn't stall the handler that received the request.
+    pub async fn record(&self, signal: RawSignal, content_type: &str, payload: &[u8]) {
+        let Some(inner) = &self.inner else {
+            return;
+        };
+        if payload.len() as u64 > RAW_OTLP_MAX_BYTES {
+            tracing::warn!(
+                len = payload.len(),
+                "raw OTLP payload too large to capture; skipped"
+            );
+            return;
+        }
+        let header = FrameHeader {
+            ts: Utc::now(),
+            signal,
+            content_type: content_type.to_string(),
+            len: payload.len(),
+        };
+        let Ok(mut frame) = serde_json::to_vec(&header) else {
+            return;
+        };
+        frame.push(b'\n');
+        frame.extend_from_slice(payload);
+        frame.push(b'\n');
+
+        let inner = Arc::clone(inner);
+        let written = tokio::task::spawn_blocking(move || {
+            inner
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .write_frame(&frame)
+        })
+        .await;
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => tracing::warn!(error = %err, "failed to write raw OTLP capture"),
+            Err(err) => tracing::warn!(error = %err, "raw OTLP capture task failed"),
+        }
+    }
+}
+
+impl RawFile {
+    fn write_frame(&mut self, frame: &[u8]) -> Result<()> {
+        if self.len > 0 && self.len + frame.len() as u64 > self.max_bytes {
+            let mut rotated = self.path.clone().into_os_string();
+            rotated.push(".1");
+            std::fs::rename(&self.path, &rotated)
+                .map_err(|e| OtellError::Ingest(format!("rotate raw OTLP capture: {e}")))?;
+            self.file = open_append(&self.path)?;
+            self.len = 0;
+        }
+        self.file
+            .write_all(frame)
+            .map_err(|e| OtellError::Ingest(format!("write raw OTLP capture: {e}")))?;
+        self.len += frame.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| OtellError::Config(format!("open raw OTLP capture {}: {e}", path.display())))
+}
+
+/// One captured export request, as read back by `otell replay`.
+#[derive(Debug, Clone)]
+pub struct RawFrame {
+    pub ts: DateTime<Utc>,
+    pub signal: RawSignal,
+    pub content_type: String,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecodedFrame {
+    Logs(Vec<LogRecord>),
+    Spans(Vec<SpanRecord>),
+    Metrics(Vec<MetricPoint>),
+}
+
+impl DecodedFrame {
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Logs(v) => v.len(),
+            Self::Spans(v) => v.len(),
+            Self::Metrics(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RawFrame {
+    /// Runs the ingest decoder on the captured bytes. Ingest-time filters
+    /// (span drops, body parsing, age limits) are not applied.
+    pub fn decode(&self) -> std::result::Result<DecodedFrame, String> {
+        let ct = self.content_type.as_str();
+        let payload = self.payload.as_slice();
+        Ok(match self.signal {
+            RawSignal::Logs => {
+                let req: ExportLogsServiceRequest = decode_otlp_payload("logs", ct, payload)?;
+                DecodedFrame::Logs(decode_logs_request(req))
+            }
+            RawSignal::Traces => {
+                let req: ExportTraceServiceRequest = decode_otlp_payload("traces", ct, payload)?;
+                DecodedFrame::Spans(decode_traces_request(req))
+            }
+            RawSignal::Metrics => {
+                let req: ExportMetricsServiceRequest = decode_otlp_payload("metrics", ct, payload)?;
+                DecodedFrame::Metrics(decode_metrics_request(req))
+            }
+        })
+    }
+}
+
+pub fn read_raw_frames(path: &Path) -> Result<Vec<RawFrame>> {
+    let file = File::open(path)
+        .map_err(|e| OtellError::Config(format!("open {}: {e}", path.display())))?;
+    let mut reader = BufReader::new(file);
+    let mut frames = Vec::new();
+    let mut header = String::new();
+    loop {
+        header.clear();
+        let read = reader
+            .read_line(&mut header)
+            .map_err(|e| OtellError::Parse(format!("read frame header: {e}")))?;
+        if read == 0 {
+            break;
+        }
+        let parsed: FrameHeader = serde_json::from_str(header.trim_end()).map_err(|e| {
+            OtellError::Parse(format!("bad frame header #{}: {e}", frames.len() + 1))
+        })?;
+        // Checked before allocating: the header is just bytes from a file.
+        if parsed.len as u64 > RAW_OTLP_MAX_BYTES {
+            return Err(OtellError::Parse(format!(
+                "frame #{} claims {} bytes, more than the {RAW_OTLP_MAX_BYTES}-byte limit",
+                frames.len() + 1,
+                parsed.len
+            )));
+        }
+        let mut payload = vec![0; parsed.len + 1];
+        reader.read_exact(&mut payload).map_err(|e| {
+            OtellError::Parse(format!("truncated frame #{}: {e}", frames.len() + 1))
+        })?;
+        payload.pop();
+        frames.push(RawFrame {
+            ts: parsed.ts,
+            signal: parsed.signal,
+            content_type: parsed.content_type,
+            payload,
+        });
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry_proto::tonic::common::v1::AnyValue;
+    use opentelemetry_proto::tonic::common::v1::any_value::Value;
+    use opentelemetry_proto::tonic::logs::v1::{
+        LogRecord as OtlpLogRecord, ResourceLogs, ScopeLogs,
+    };
+    use prost::Message;
+
+    use super::*;
+
+    fn logs_request(body: &str) -> ExportLogsServiceRequest {
+        ExportLogsServiceRequest {
+            resource_logs: vec![ResourceLogs {
+                scope_logs: vec![ScopeLogs {
+                    log_records: vec![OtlpLogRecord {
+                        time_unix_nano: 1_700_000_000_000_000_000,
+                        severity_number: 17,
+                        body: Some(AnyValue {
+                            value: Some(Value::StringValue(body.into())),
+                        }),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[tokio::test]
+    async fn captured_payloads_replay_through_decoder() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("raw/otlp.bin");
+        let capture = RawCapture::open(&path, RAW_OTLP_MAX_BYTES).unwrap();
+
+        capture
+            .record(
+                RawSignal::Logs,
+                GRPC_CONTENT_TYPE,
+                &logs_request("from grpc").encode_to_vec(),
+            )
+            .await;
+        capture
+            .record(
+                RawSignal::Logs,
+                "application/json",
+                &serde_json::to_vec(&logs_request("from json")).unwrap(),
+            )
+            .await;
+        capture
+            .record(RawSignal::Traces, "application/x-protobuf", b"\xff\xff")
+            .await;
+
+        let frames = read_raw_frames(&path).unwrap();
+        assert_eq!(frames.len(), 3);
+        let bodies = frames[..2]
+            .iter()
+            .map(|f| match f.decode().unwrap() {
+                DecodedFrame::Logs(logs) => logs[0].body.clone(),
+                other => panic!("unexpected {other:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["from grpc", "from json"]);
+        assert!(frames[2].decode().is_err());
+    }
+
+    #[tokio::test]
+    async fn capture_file_rotates_at_max_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.bin");
+        let capture = RawCapture::open(&path, 100).unwrap();
+        capture
+            .record(RawSignal::Logs, GRPC_CONTENT_TYPE, &[0; 40])
+            .await;
+        capture
+            .record(RawSignal::Logs, GRPC_CONTENT_TYPE, &[0; 40])
+            .await;
+
+        assert_eq!(read_raw_frames(&path).unwrap().len(), 1);
+        assert_eq!(
+            read_raw_frames(&dir.path().join("otlp.bin.1"))
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn oversized_frame_header_is_rejected_before_reading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otlp.bin");
+        let header = format!(
+            r#"{{"ts":"2026-02-01T00:00:00Z","signal":"logs","content_type":"application/json","len":{}}}"#,
+            usize::MAX
+        );
+        std::fs::write(&path, format!("{header}\n{{}}\n")).unwrap();
+
+        let err = read_raw_frames(&path).unwrap_err().to_string();
+        assert!(err.contains("more than the"), "{err}");
+    }
+}
@@ -2,8 +2,11 @@ use std::net::SocketAddr;
 
 use otell_core::error::{OtellError, Result};
 use tonic::transport::Server;
+use tower::ServiceBuilder;
+use tower::limit::GlobalConcurrencyLimitLayer;
 
 use crate::forward::{ForwardConfig, build_forwarder};
+use crate::limit::with_concurrency_limit;
 use crate::otlp::grpc::GrpcIngest;
 use crate::otlp::http;
 use crate::pipeline::{Pipeline, PipelineConfig};
@@ -14,6 +17,7 @@ pub async fn run_ingest_servers(
     http_addr: SocketAddr,
     cfg: PipelineConfig,
     forward_cfg: Option<ForwardConfig>,
+    max_concurrent_requests: usize,
 ) -> Result<()> {
     tracing::info!(addr = %grpc_addr, "otlp gRPC ingest server listening");
     tracing::info!(addr = %http_addr, "otlp HTTP ingest server listening");
@@ -21,10 +25,16 @@ pub async fn run_ingest_servers(
     let pipeline = Pipeline::new(store, cfg);
     let forwarder = build_forwarder(forward_cfg);
     let grpc = GrpcIngest::new(pipeline.clone(), forwarder.clone());
-    let http_router = http::router(pipeline, forwarder);
+    let http_router =
+        with_concurrency_limit(http::router(pipeline, forwarder), max_concurrent_requests);
 
     let grpc_task = tokio::spawn(async move {
         Server::builder()
+            .layer(
+                ServiceBuilder::new()
+                    .load_shed()
+                    .layer(GlobalConcurrencyLimitLayer::new(max_concurrent_requests)),
+            )
             .add_service(grpc.logs_service())
             .add_service(grpc.traces_service())
             .add_service(grpc.metrics_service())
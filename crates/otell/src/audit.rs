@@ -0,0 +1,78 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+
+use crate::protocol::ApiRequest;
+
+/// Opt-in append-only record of handled queries (`OTELL_QUERY_AUDIT`).
+///
+/// Each line is a JSON object with `ts`, `transport`, `kind` and the full
+/// `request`. Tail streams are recorded once when opened, not per record.
+#[derive(Clone, Default)]
+pub struct QueryAudit {
+    file: Option<Arc<Mutex<File>>>,
+}
+
+impl QueryAudit {
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent).context("create query audit dir")?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open query audit log {}", path.display()))?;
+        Ok(Self {
+            file: Some(Arc::new(Mutex::new(file))),
+        })
+    }
+
+    pub fn record(&self, transport: &str, req: &ApiRequest) {
+        if self.file.is_none() {
+            return;
+        }
+        let request = serde_json::to_value(req).unwrap_or_default();
+        let kind = match &request {
+            serde_json::Value::String(kind) => kind.clone(),
+            serde_json::Value::Object(map) => map.keys().next().cloned().unwrap_or_default(),
+            _ => String::new(),
+        };
+        self.write(transport, &kind, &request);
+    }
+
+    pub fn record_tail(&self, transport: &str, query: &impl Serialize) {
+        if self.file.is_none() {
+            return;
+        }
+        let request = serde_json::to_value(query).unwrap_or_default();
+        self.write(transport, "Tail", &request);
+    }
+
+    fn write(&self, transport: &str, kind: &str, request: &serde_json::Value) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        let line = serde_json::json!({
+            "ts": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "transport": transport,
+            "kind": kind,
+            "request": request,
+        });
+        let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = writeln!(file, "{line}") {
+            tracing::warn!(error = ?err, "failed to write query audit line");
+        }
+    }
+}
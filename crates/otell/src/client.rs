@@ -4,7 +4,7 @@ use anyhow::Context;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpStream, UnixStream};
 
-use crate::protocol::{ApiRequest, ApiResponse};
+use crate::protocol::{ApiRequest, ApiResponse, AuthPreamble};
 
 pub enum QueryClient {
     Uds(BufReader<UnixStream>),
@@ -29,9 +29,15 @@ impl QueryClient {
         let addr = addr
             .or_else(|| std::env::var("OTELL_QUERY_TCP_ADDR").ok())
             .unwrap_or_else(|| "127.0.0.1:1777".to_string());
-        let stream = TcpStream::connect(&addr)
+        let mut stream = TcpStream::connect(&addr)
             .await
             .with_context(|| format!("connect query server TCP {addr}"))?;
+        // A rejected token comes back as an Error reply to the first request.
+        if let Ok(token) = std::env::var("OTELL_QUERY_TOKEN") {
+            let mut preamble = serde_json::to_vec(&AuthPreamble { token })?;
+            preamble.push(b'\n');
+            stream.write_all(&preamble).await?;
+        }
         Ok(Self::Tcp(BufReader::new(stream)))
     }
 
@@ -1,35 +1,50 @@
+mod audit;
 mod client;
 mod output;
 mod protocol;
 mod query_server;
 mod telemetry;
 
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 use anyhow::Context;
 use base64::Engine;
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use otell_core::config::Config;
 use otell_core::filter::{AttrFilter, Severity, SortOrder, TimeWindow};
 use otell_core::query::{
-    LogContextMode, MetricsListRequest, MetricsRequest, QueryHandle, SearchRequest, SpanRequest,
-    TraceRequest, TracesRequest,
+    FailedSpansRequest, LogContextMode, MetricsListRequest, MetricsListSort, MetricsRequest,
+    QueryHandle, RollupUnit, SamplingStatsRequest, SearchRequest, ServiceGraphRequest, SpanRequest,
+    SpansInWindowRequest, StatusResponse, TraceListItem, TraceLogsRequest, TraceRequest,
+    TraceResponse, TracesRequest,
 };
 use otell_core::time::{parse_duration_str, parse_time_or_relative};
-use otell_ingest::forward::{ForwardCompression, ForwardConfig, ForwardProtocol};
-use otell_ingest::pipeline::PipelineConfig;
-use serde::Serialize;
+use otell_ingest::body::BodyParser;
+use otell_ingest::forward::{
+    ForwardCompression, ForwardConfig, ForwardProtocol, probe_forward_endpoint,
+};
+use otell_ingest::otlp::decode::LogTimeSource;
+use otell_ingest::pipeline::{FlushOverride, PipelineConfig, SignalFlush};
+use otell_ingest::raw::{RAW_OTLP_MAX_BYTES, RawCapture, read_raw_frames};
+use serde::{Deserialize, Serialize};
 use tokio::io::{AsyncBufReadExt, BufReader};
 
+use crate::audit::QueryAudit;
 use crate::client::QueryClient;
 use crate::output::{
-    print_metrics_human, print_metrics_list_human, print_search_human, print_span_human,
-    print_status_human, print_trace_human, print_traces_human,
+    COMPACT_TRACE_MAX_LINES, TRACES_TREE_MAX, compact_trace_lines, print_check_human,
+    print_failed_spans_human, print_metrics_human, print_metrics_list_human,
+    print_metrics_sparklines, print_replay_frame_human, print_sampling_human, print_search_human,
+    print_service_graph_human, print_span_human, print_spans_human, print_status_human,
+    print_trace_human, print_trace_logs_human, print_traces_human, print_traces_tree_human,
+    trace_command,
 };
 use crate::protocol::{ApiRequest, ApiResponse};
 use crate::telemetry::{
-    SelfObserveMode, TelemetryConfig, init_cli_tracing, init_run_tracing, shutdown_tracing,
+    SelfObserveMode, TelemetryConfig, init_cli_tracing, init_run_tracing,
+    self_observe_level_from_env, self_observe_service_from_env, shutdown_tracing,
 };
 
 #[derive(Parser, Debug)]
@@ -48,6 +63,21 @@ struct Cli {
 
     #[arg(long, global = true)]
     addr: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Skip the trailing handle= line (or set OTELL_NO_HANDLE=1)"
+    )]
+    no_handle: bool,
+
+    #[arg(
+        long,
+        global = true,
+        conflicts_with = "no_handle",
+        help = "With --json, add the handle as a top-level \"handle\" field"
+    )]
+    embed_handle: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -68,36 +98,16 @@ enum Commands {
         query_uds_path: Option<PathBuf>,
     },
     #[command(about = "Search logs with deterministic filters")]
-    Search {
-        pattern: String,
-        #[arg(long)]
-        fixed: bool,
-        #[arg(short = 'i', long)]
-        ignore_case: bool,
-        #[arg(long)]
-        since: Option<String>,
-        #[arg(long)]
-        until: Option<String>,
-        #[arg(long)]
-        service: Option<String>,
-        #[arg(long)]
-        trace: Option<String>,
-        #[arg(long)]
-        span: Option<String>,
-        #[arg(long)]
-        severity: Option<String>,
-        #[arg(long = "where")]
-        where_filters: Vec<String>,
-        #[arg(short = 'C', help = "Context lines (e.g. 20) or time (e.g. 2s)")]
-        context: Option<String>,
-        #[arg(long, help = "Only return total match count")]
-        count: bool,
-        #[arg(long, help = "Include grouped stats in response")]
-        stats: bool,
-        #[arg(long, default_value_t = 100)]
-        limit: usize,
-        #[arg(long, default_value = "ts_asc")]
-        sort: String,
+    Search(SearchArgs),
+    #[command(about = "Save a search under a name for `otell run-saved`")]
+    Save {
+        name: String,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    #[command(about = "Run a search saved with `otell save`")]
+    RunSaved {
+        name: String,
     },
     #[command(about = "Inspect a trace and related logs")]
     Trace {
@@ -106,6 +116,30 @@ enum Commands {
         root: Option<String>,
         #[arg(long, default_value = "bounded")]
         logs: String,
+        #[arg(long, help = "Page --logs all output instead of returning every log")]
+        paginate: bool,
+        #[arg(long, default_value_t = 500)]
+        page_size: usize,
+        #[arg(
+            long,
+            default_value_t = 0,
+            help = "Skip this many logs (use next_offset)"
+        )]
+        offset: usize,
+        #[arg(
+            long,
+            conflicts_with = "paginate",
+            help = "Print a short summary of error and slow spans only"
+        )]
+        compact: bool,
+    },
+    #[command(about = "Fetch only the logs correlated with a trace")]
+    TraceLogs {
+        trace_id: String,
+        #[arg(long, default_value = "bounded")]
+        logs: String,
+        #[arg(long, help = "Maximum logs to return (bounded defaults to 50)")]
+        limit: Option<usize>,
     },
     #[command(about = "Inspect a specific span")]
     Span {
@@ -128,6 +162,51 @@ enum Commands {
         limit: usize,
         #[arg(long, default_value = "duration_desc")]
         sort: String,
+        #[arg(long, help = "Only return the number of matching traces")]
+        count: bool,
+        #[arg(
+            long = "where",
+            help = "Keep traces with a span whose attribute matches (key=glob, key>=500, key!=x)"
+        )]
+        where_filters: Vec<String>,
+        #[arg(long, help = "Print a ready-to-run `otell trace` command per trace")]
+        open: bool,
+        #[arg(
+            long,
+            conflicts_with = "count",
+            help = "Print the span tree of each listed trace (first 10 only)"
+        )]
+        tree: bool,
+        #[arg(
+            long,
+            conflicts_with_all = ["count", "tree", "open"],
+            help = "List failing spans grouped by service and name (same as `otell failures`)"
+        )]
+        failed_spans: bool,
+    },
+    #[command(about = "List spans across all traces in a time window")]
+    Spans {
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long)]
+        service: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long, default_value = "ts_asc")]
+        sort: String,
+    },
+    #[command(about = "List the most frequent failing operations (service, span name)")]
+    Failures {
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long)]
+        service: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
     },
     #[command(about = "Query metric points or list metric names")]
     Metrics {
@@ -144,6 +223,29 @@ enum Commands {
         agg: Option<String>,
         #[arg(long, default_value_t = 50)]
         limit: usize,
+        #[arg(
+            long,
+            default_value = "count",
+            help = "Order for `metrics list`: count|cardinality"
+        )]
+        sort: String,
+        #[arg(
+            long,
+            help = "For `metrics list`: only return the number of metric names"
+        )]
+        count: bool,
+        #[arg(
+            long,
+            help = "For `metrics list`: show which services emit each metric"
+        )]
+        with_services: bool,
+        #[arg(long, help = "Also aggregate each series per time bucket (e.g. 1m)")]
+        bucket: Option<String>,
+        #[arg(
+            long,
+            help = "Render each series' buckets as a sparkline (default bucket 1m)"
+        )]
+        sparkline: bool,
     },
     #[command(about = "Stream matching logs in real time")]
     Tail {
@@ -160,13 +262,70 @@ enum Commands {
         span: Option<String>,
         #[arg(long)]
         severity: Option<String>,
+        #[arg(
+            long,
+            help = "Replay matching stored logs from this time before following"
+        )]
+        since: Option<String>,
+        #[arg(
+            long,
+            help = "Stop at this time; a past value replays the backlog and exits"
+        )]
+        until: Option<String>,
+        #[arg(
+            long,
+            value_name = "ATTR",
+            help = "Tag and color each line by this attribute's value (e.g. pod)"
+        )]
+        color_by: Option<String>,
         #[arg(long)]
         http_addr: Option<String>,
     },
-    Status,
+    #[command(about = "Show store health and counts")]
+    Status {
+        #[arg(long, help = "Keep polling and show ingest rates between polls")]
+        watch: bool,
+        #[arg(long, default_value = "2s", help = "Poll interval for --watch")]
+        interval: String,
+    },
+    #[command(about = "Show caller -> callee service edges from span parentage")]
+    Graph {
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+    },
+    #[command(about = "Show per-service counts of sampled, unsampled and untraced logs")]
+    Sampling {
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long)]
+        service: Option<String>,
+    },
+    #[command(about = "Validate the database and report suspicious data")]
+    Check,
+    #[command(about = "Re-decode OTLP requests captured with OTELL_STORE_RAW_OTLP")]
+    Replay {
+        file: PathBuf,
+    },
+    #[command(about = "Send a raw JSON ApiRequest from a file or stdin")]
+    Query {
+        #[arg(
+            long,
+            help = "Path to the request JSON; reads stdin when omitted or '-'"
+        )]
+        file: Option<PathBuf>,
+    },
     #[command(about = "Execute a previously emitted handle")]
     Handle {
         handle: String,
+        #[arg(
+            long,
+            help = "Print the decoded request as JSON instead of executing it"
+        )]
+        explain: bool,
     },
     #[command(about = "Learn otell quickly via live probes")]
     Intro {
@@ -178,9 +337,87 @@ enum Commands {
     Mcp,
 }
 
+#[derive(Args, Debug)]
+struct SearchArgs {
+    pattern: String,
+    #[arg(long)]
+    fixed: bool,
+    #[arg(
+        short = 'i',
+        long,
+        help = "Case-insensitive pattern, --service and --where matching"
+    )]
+    ignore_case: bool,
+    #[arg(long)]
+    since: Option<String>,
+    #[arg(long)]
+    until: Option<String>,
+    #[arg(long)]
+    service: Option<String>,
+    #[arg(long)]
+    trace: Option<String>,
+    #[arg(long)]
+    span: Option<String>,
+    #[arg(long)]
+    severity: Option<String>,
+    #[arg(long = "where")]
+    where_filters: Vec<String>,
+    #[arg(long, help = "Only logs with the W3C sampled trace flag set")]
+    sampled: bool,
+    #[arg(
+        long,
+        conflicts_with = "sampled",
+        help = "Only logs with the W3C sampled trace flag clear"
+    )]
+    unsampled: bool,
+    #[arg(short = 'C', help = "Context lines (e.g. 20) or time (e.g. 2s)")]
+    context: Option<String>,
+    #[arg(long, help = "Only return total match count")]
+    count: bool,
+    #[arg(
+        long,
+        requires = "count",
+        help = "With --count: also count the preceding window of equal length"
+    )]
+    compare: bool,
+    #[arg(long, help = "Include grouped stats in response")]
+    stats: bool,
+    #[arg(long, help = "Print only the matched records as a bare JSON array")]
+    records_only: bool,
+    #[arg(long, help = "Exit non-zero when nothing matches")]
+    fail_on_empty: bool,
+    #[arg(
+        long,
+        conflicts_with = "records_only",
+        help = "Print attributes as sorted key=value lines under each record"
+    )]
+    merge_attrs: bool,
+    #[arg(
+        long,
+        conflicts_with = "records_only",
+        help = "Show each record's event-to-observed delay"
+    )]
+    show_delay: bool,
+    #[arg(
+        long,
+        help = "Roll up match counts per calendar period: minute|hour|day"
+    )]
+    group_window: Option<String>,
+    #[arg(long, default_value_t = 100)]
+    limit: usize,
+    #[arg(long, default_value = "ts_asc")]
+    sort: String,
+    #[arg(
+        long,
+        help = "Age at which --sort relevance halves a match's score (default 5m)"
+    )]
+    half_life: Option<String>,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
+    let handles = HandleOutput::from_cli(&cli);
 
     match cli.command {
         Commands::Run {
@@ -193,6 +430,8 @@ async fn main() -> anyhow::Result<()> {
         } => {
             let telemetry_cfg = TelemetryConfig {
                 self_observe: SelfObserveMode::from_env(),
+                self_observe_min_level: self_observe_level_from_env()?,
+                self_observe_service: self_observe_service_from_env(),
             };
             run_server(
                 db_path,
@@ -205,76 +444,152 @@ async fn main() -> anyhow::Result<()> {
             )
             .await
         }
-        Commands::Search {
-            pattern,
-            fixed,
-            ignore_case,
-            since,
-            until,
-            service,
-            trace,
-            span,
-            severity,
-            where_filters,
-            context,
-            count,
-            stats,
-            limit,
-            sort,
-        } => {
+        Commands::Search(args) if args.compare => {
             init_cli_tracing();
+            let mut req = search_request(&args)?;
+            let previous_window = pin_compare_window(&mut req.window, chrono::Utc::now())?;
+            let previous_req = SearchRequest {
+                window: previous_window,
+                ..req.clone()
+            };
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
-            let (context_lines, context_seconds) = parse_context(context)?;
-            let req = SearchRequest {
-                pattern: Some(pattern),
-                fixed,
-                ignore_case,
-                service,
-                trace_id: trace,
-                span_id: span,
-                severity_gte: severity.map(|s| Severity::from_str(&s)).transpose()?,
-                attr_filters: where_filters
-                    .into_iter()
-                    .map(|f| AttrFilter::parse(&f))
-                    .collect::<otell_core::Result<Vec<_>>>()?,
-                window: parse_window(since, until)?,
-                sort: parse_sort(&sort),
-                limit,
-                context_lines,
-                context_seconds,
-                count_only: count,
-                include_stats: stats,
+            let api_req = ApiRequest::Search(req);
+            let handle = encode_handle(&api_req)?;
+            let current = search_total(client.request(api_req.clone()).await?)?;
+            let previous = search_total(client.request(ApiRequest::Search(previous_req)).await?)?;
+            let ApiRequest::Search(req) = api_req else {
+                unreachable!("built as a search");
             };
+            let cmp = SearchComparison::new(current, previous, req.window);
+            if cli.json {
+                let mut value = serde_json::to_value(&cmp)?;
+                handles.embed_into(&mut value, &handle);
+                println!("{}", serde_json::to_string_pretty(&value)?);
+            } else {
+                println!("{}", cmp.human_line());
+                handles.print_line(&handle);
+            }
+            Ok(())
+        }
+        Commands::Search(args) => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let req = search_request(&args)?;
+            let window = req.window.clone();
+            let SearchArgs {
+                records_only,
+                fail_on_empty,
+                merge_attrs,
+                show_delay,
+                ..
+            } = args;
             let api_req = ApiRequest::Search(req);
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
-            if !cli.json {
-                println!("handle={handle}");
+            let empty = matches!(&response, ApiResponse::Search(resp) if resp.total_matches == 0);
+            if records_only {
+                match response {
+                    ApiResponse::Search(resp) => {
+                        println!("{}", serde_json::to_string(&resp.records)?);
+                    }
+                    ApiResponse::Error(e) => return Err(anyhow::anyhow!(e)),
+                    other => return Err(anyhow::anyhow!("unexpected response: {other:?}")),
+                }
+            } else if (merge_attrs || show_delay) && !cli.json {
+                match response {
+                    ApiResponse::Search(resp) => print_search_human(&resp, merge_attrs, show_delay),
+                    other => print_response(other, false)?,
+                }
+                handles.print_line(&handle);
+            } else {
+                print_query_response(response, &handle, cli.json, handles)?;
+            }
+            if empty {
+                if let Ok(ApiResponse::Status(status)) = client.request(ApiRequest::Status).await {
+                    eprintln!("{}", empty_search_hint(&window, &status));
+                }
+                if fail_on_empty {
+                    anyhow::bail!("no matches");
+                }
             }
             Ok(())
         }
+        Commands::Save { name, search } => {
+            let saved = SavedQuery::from_search(&search)?;
+            let path = saved_queries_path();
+            save_query(&path, &name, saved)?;
+            println!("saved {name} to {}", path.display());
+            Ok(())
+        }
+        Commands::RunSaved { name } => {
+            init_cli_tracing();
+            let api_req = load_saved_query(&saved_queries_path(), &name)?.request()?;
+            let handle = encode_handle(&api_req)?;
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
         Commands::Trace {
             trace_id,
             root,
             logs,
+            paginate,
+            page_size,
+            offset,
+            compact,
         } => {
             init_cli_tracing();
+            let logs = if compact {
+                LogContextMode::None
+            } else {
+                parse_logs_mode(&logs)?
+            };
+            if paginate && !matches!(logs, LogContextMode::All) {
+                anyhow::bail!("--paginate requires --logs all");
+            }
+            if paginate && page_size == 0 {
+                anyhow::bail!("--page-size must be at least 1");
+            }
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
             let req = TraceRequest {
                 trace_id,
                 root_span_id: root,
-                logs: parse_logs_mode(&logs)?,
+                logs,
+                logs_offset: if paginate { offset } else { 0 },
+                logs_limit: paginate.then_some(page_size),
             };
             let api_req = ApiRequest::Trace(req);
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
-            if !cli.json {
-                println!("handle={handle}");
+            match response {
+                ApiResponse::Trace(trace) if compact && !cli.json => {
+                    for line in compact_trace_lines(&trace, COMPACT_TRACE_MAX_LINES) {
+                        println!("{line}");
+                    }
+                    handles.print_line(&handle);
+                }
+                other => print_query_response(other, &handle, cli.json, handles)?,
             }
             Ok(())
         }
+        Commands::TraceLogs {
+            trace_id,
+            logs,
+            limit,
+        } => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = ApiRequest::TraceLogs(TraceLogsRequest {
+                trace_id,
+                mode: parse_logs_mode(&logs)?,
+                limit,
+            });
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
         Commands::Span {
             trace_id,
             span_id,
@@ -290,10 +605,7 @@ async fn main() -> anyhow::Result<()> {
             let api_req = ApiRequest::Span(req);
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
-            if !cli.json {
-                println!("handle={handle}");
-            }
+            print_query_response(response, &handle, cli.json, handles)?;
             Ok(())
         }
         Commands::Traces {
@@ -303,25 +615,97 @@ async fn main() -> anyhow::Result<()> {
             status,
             limit,
             sort,
+            count,
+            where_filters,
+            open,
+            tree,
+            failed_spans,
         } => {
             init_cli_tracing();
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            if failed_spans {
+                let api_req = failed_spans_request(since, until, service, limit)?;
+                let handle = encode_handle(&api_req)?;
+                let response = client.request(api_req).await?;
+                print_query_response(response, &handle, cli.json, handles)?;
+                return Ok(());
+            }
             let req = TracesRequest {
                 service,
                 status,
                 window: parse_window(since, until)?,
                 sort: parse_sort(&sort),
                 limit,
+                count_only: count,
+                attr_filters: parse_where_filters(where_filters)?,
             };
             let api_req = ApiRequest::Traces(req);
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
-            if !cli.json {
-                println!("handle={handle}");
+            match response {
+                ApiResponse::Traces(items) if tree => {
+                    let trees = fetch_trace_trees(&mut client, &items).await?;
+                    if cli.json {
+                        let mut value = if open {
+                            traces_with_commands(&items)?
+                        } else {
+                            serde_json::to_value(ApiResponse::Traces(items.clone()))?
+                        };
+                        embed_trace_spans(&mut value, &trees)?;
+                        handles.embed_into(&mut value, &handle);
+                        println!("{}", serde_json::to_string_pretty(&value)?);
+                    } else {
+                        print_traces_tree_human(&items, &trees, open);
+                        handles.print_line(&handle);
+                    }
+                }
+                ApiResponse::Traces(items) if open && cli.json => {
+                    let mut value = traces_with_commands(&items)?;
+                    handles.embed_into(&mut value, &handle);
+                    println!("{}", serde_json::to_string_pretty(&value)?);
+                }
+                ApiResponse::Traces(items) if open => {
+                    print_traces_human(&items, true);
+                    handles.print_line(&handle);
+                }
+                response => print_query_response(response, &handle, cli.json, handles)?,
             }
             Ok(())
         }
+        Commands::Spans {
+            since,
+            until,
+            service,
+            limit,
+            sort,
+        } => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = ApiRequest::SpansInWindow(SpansInWindowRequest {
+                window: parse_window(since, until)?,
+                service,
+                limit,
+                sort: parse_sort(&sort),
+            });
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
+        Commands::Failures {
+            since,
+            until,
+            service,
+            limit,
+        } => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = failed_spans_request(since, until, service, limit)?;
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
         Commands::Metrics {
             name,
             since,
@@ -330,14 +714,30 @@ async fn main() -> anyhow::Result<()> {
             group_by,
             agg,
             limit,
+            sort,
+            count,
+            with_services,
+            bucket,
+            sparkline,
         } => {
             init_cli_tracing();
+            let bucket = match bucket {
+                Some(raw) => Some(
+                    humantime::parse_duration(&raw)
+                        .with_context(|| format!("invalid --bucket: {raw}"))?,
+                ),
+                None => sparkline.then(|| std::time::Duration::from_secs(60)),
+            };
+            let bucket_secs = bucket.map(|b| b.as_secs().max(1));
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
             let api_req = if matches!(name.as_deref(), None | Some("list")) {
                 ApiRequest::MetricsList(MetricsListRequest {
                     service,
                     window: parse_window(since, until)?,
                     limit,
+                    sort: parse_metrics_list_sort(&sort),
+                    count_only: count,
+                    with_services,
                 })
             } else {
                 ApiRequest::Metrics(MetricsRequest {
@@ -347,13 +747,17 @@ async fn main() -> anyhow::Result<()> {
                     group_by,
                     agg,
                     limit,
+                    bucket_secs,
                 })
             };
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
-            if !cli.json {
-                println!("handle={handle}");
+            match response {
+                ApiResponse::Metrics(metrics) if sparkline && !cli.json => {
+                    print_metrics_sparklines(&metrics);
+                    handles.print_line(&handle);
+                }
+                other => print_query_response(other, &handle, cli.json, handles)?,
             }
             Ok(())
         }
@@ -365,9 +769,13 @@ async fn main() -> anyhow::Result<()> {
             trace,
             span,
             severity,
+            since,
+            until,
+            color_by,
             http_addr,
         } => {
             init_cli_tracing();
+            let window = parse_window(since, until)?;
             run_tail(TailQueryParams {
                 pattern,
                 fixed,
@@ -376,6 +784,9 @@ async fn main() -> anyhow::Result<()> {
                 trace_id: trace,
                 span_id: span,
                 severity,
+                since: window.since.map(|ts| ts.to_rfc3339()),
+                until: window.until.map(|ts| ts.to_rfc3339()),
+                color_by,
                 addr: http_addr
                     .or(cli.addr)
                     .or_else(|| std::env::var("OTELL_QUERY_HTTP_ADDR").ok())
@@ -383,20 +794,103 @@ async fn main() -> anyhow::Result<()> {
             })
             .await
         }
-        Commands::Status => {
+        Commands::Status { watch, interval } => {
             init_cli_tracing();
+            if watch {
+                let interval = humantime::parse_duration(&interval)
+                    .with_context(|| format!("invalid --interval: {interval}"))?;
+                return run_status_watch(cli.uds, cli.addr, interval, cli.json).await;
+            }
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
             let api_req = ApiRequest::Status;
             let handle = encode_handle(&api_req)?;
             let response = client.request(api_req).await?;
-            print_response(response, cli.json)?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
+        Commands::Graph { since, until } => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = ApiRequest::ServiceGraph(ServiceGraphRequest {
+                window: parse_window(since, until)?,
+            });
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
+        Commands::Sampling {
+            since,
+            until,
+            service,
+        } => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = ApiRequest::SamplingStats(SamplingStatsRequest {
+                window: parse_window(since, until)?,
+                service,
+            });
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            print_query_response(response, &handle, cli.json, handles)?;
+            Ok(())
+        }
+        Commands::Check => {
+            init_cli_tracing();
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let api_req = ApiRequest::Check;
+            let handle = encode_handle(&api_req)?;
+            let response = client.request(api_req).await?;
+            let failed = matches!(&response, ApiResponse::Check(report) if !report.ok);
+            print_query_response(response, &handle, cli.json, handles)?;
+            if failed {
+                anyhow::bail!("integrity check failed");
+            }
+            Ok(())
+        }
+        Commands::Replay { file } => {
+            init_cli_tracing();
+            let frames = read_raw_frames(&file)?;
+            let mut failed = 0;
+            for (idx, frame) in frames.iter().enumerate() {
+                let decoded = frame.decode();
+                failed += usize::from(decoded.is_err());
+                if cli.json {
+                    let mut line = serde_json::json!({
+                        "frame": idx + 1,
+                        "ts": frame.ts,
+                        "signal": frame.signal,
+                        "content_type": frame.content_type,
+                        "bytes": frame.payload.len(),
+                    });
+                    match decoded {
+                        Ok(records) => line["records"] = serde_json::to_value(records)?,
+                        Err(err) => line["error"] = serde_json::Value::String(err),
+                    }
+                    println!("{line}");
+                } else {
+                    print_replay_frame_human(idx + 1, frame, &decoded);
+                }
+            }
             if !cli.json {
-                println!("handle={handle}");
+                println!("-- {} frames ({failed} failed to decode) --", frames.len());
             }
             Ok(())
         }
-        Commands::Handle { handle } => {
+        Commands::Query { file } => {
+            init_cli_tracing();
+            let req = read_query_request(file.as_deref())?;
+            let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
+            let response = client.request(req).await?;
+            print_response(response, cli.json)?;
+            Ok(())
+        }
+        Commands::Handle { handle, explain } => {
             init_cli_tracing();
+            if explain {
+                println!("{}", explain_handle(&handle)?);
+                return Ok(());
+            }
             let mut client = QueryClient::connect(cli.uds, cli.addr).await?;
             let req = decode_handle(&handle)?;
             let response = client.request(req).await?;
@@ -437,6 +931,10 @@ struct TailQueryParams {
     trace_id: Option<String>,
     span_id: Option<String>,
     severity: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
+    #[serde(skip_serializing)]
+    color_by: Option<String>,
     #[serde(skip_serializing)]
     addr: String,
 }
@@ -452,10 +950,9 @@ async fn run_tail(params: TailQueryParams) -> anyhow::Result<()> {
         .context("open tail stream")?;
 
     if !response.status().is_success() {
-        anyhow::bail!(
-            "tail stream request failed with status {}",
-            response.status()
-        );
+        let status = response.status();
+        let detail = response.text().await.unwrap_or_default();
+        anyhow::bail!("tail stream request failed with status {status}: {detail}");
     }
 
     let mut buffer = String::new();
@@ -467,12 +964,21 @@ async fn run_tail(params: TailQueryParams) -> anyhow::Result<()> {
             let frame = buffer[..frame_end].to_string();
             buffer.drain(..frame_end + 2);
 
+            let is_gap = frame.lines().any(|line| line == "event: gap");
             for line in frame.lines() {
-                if let Some(data) = line.strip_prefix("data: ")
+                if is_gap {
+                    if let Some(data) = line.strip_prefix("data: ") {
+                        let skipped = serde_json::from_str::<serde_json::Value>(data)
+                            .ok()
+                            .and_then(|v| v["skipped"].as_u64())
+                            .unwrap_or_default();
+                        eprintln!("-- tail fell behind; {skipped} records skipped --");
+                    }
+                } else if let Some(data) = line.strip_prefix("data: ")
                     && let Ok(record) =
                         serde_json::from_str::<otell_core::model::log::LogRecord>(data)
                 {
-                    print_tail_record(&record);
+                    print_tail_record(&record, params.color_by.as_deref());
                 }
             }
         }
@@ -481,25 +987,83 @@ async fn run_tail(params: TailQueryParams) -> anyhow::Result<()> {
     Ok(())
 }
 
-fn print_tail_record(record: &otell_core::model::log::LogRecord) {
+fn print_tail_record(record: &otell_core::model::log::LogRecord, color_by: Option<&str>) {
+    println!("{}", render_tail_record(record, color_by, true));
+}
+
+/// Palette for `--color-by`; service names already use cyan.
+const TAIL_PALETTE: [owo_colors::AnsiColors; 8] = [
+    owo_colors::AnsiColors::Green,
+    owo_colors::AnsiColors::Yellow,
+    owo_colors::AnsiColors::Blue,
+    owo_colors::AnsiColors::Magenta,
+    owo_colors::AnsiColors::BrightGreen,
+    owo_colors::AnsiColors::BrightYellow,
+    owo_colors::AnsiColors::BrightBlue,
+    owo_colors::AnsiColors::BrightMagenta,
+];
+
+fn render_tail_record(
+    record: &otell_core::model::log::LogRecord,
+    color_by: Option<&str>,
+    color: bool,
+) -> String {
     use owo_colors::OwoColorize;
 
-    let sev = match record.severity {
-        1..=4 => "TRACE".blue().to_string(),
-        5..=8 => "DEBUG".bright_black().to_string(),
-        9..=12 => "INFO".green().to_string(),
-        13..=16 => "WARN".yellow().to_string(),
-        17..=20 => "ERROR".red().to_string(),
-        _ => "FATAL".magenta().to_string(),
+    let label = match record.severity {
+        1..=4 => "TRACE",
+        5..=8 => "DEBUG",
+        9..=12 => "INFO",
+        13..=16 => "WARN",
+        17..=20 => "ERROR",
+        _ => "FATAL",
+    };
+    let (service, sev) = if color {
+        let sev = match label {
+            "TRACE" => label.blue().to_string(),
+            "DEBUG" => label.bright_black().to_string(),
+            "INFO" => label.green().to_string(),
+            "WARN" => label.yellow().to_string(),
+            "ERROR" => label.red().to_string(),
+            _ => label.magenta().to_string(),
+        };
+        (record.service.cyan().to_string(), sev)
+    } else {
+        (record.service.clone(), label.to_string())
     };
 
-    println!(
-        "{} {} {} | {}",
+    let group = color_by.map(|key| {
+        let key = key.trim_start_matches("attrs.");
+        let value = serde_json::from_str::<serde_json::Value>(&record.attrs_json)
+            .ok()
+            .and_then(|attrs| {
+                attrs.get(key).map(|v| match v {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                })
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let tag = format!("[{key}={value}]");
+        if color {
+            // FNV-1a, so a value keeps its color across runs.
+            let hash = value.bytes().fold(0xcbf29ce484222325u64, |h, b| {
+                (h ^ u64::from(b)).wrapping_mul(0x100000001b3)
+            });
+            let palette_color = TAIL_PALETTE[(hash % TAIL_PALETTE.len() as u64) as usize];
+            format!("{} ", tag.color(palette_color))
+        } else {
+            format!("{tag} ")
+        }
+    });
+
+    format!(
+        "{} {} {} {}| {}",
         record.ts.to_rfc3339(),
-        record.service.cyan(),
+        service,
         sev,
+        group.unwrap_or_default(),
         record.body
-    );
+    )
 }
 
 async fn run_intro(
@@ -528,6 +1092,9 @@ async fn run_intro(
                 service: None,
                 window: TimeWindow::all(),
                 limit: 5,
+                sort: MetricsListSort::Count,
+                count_only: false,
+                with_services: false,
             }))
             .await
             .ok();
@@ -646,18 +1213,32 @@ fn render_intro_markdown(input: IntroDocInput<'_>) -> anyhow::Result<String> {
     out.push_str("| command | usage | key flags |\n");
     out.push_str("|---|---|---|\n");
     out.push_str("| `run` | `otell run` | `--db-path`, `--otlp-grpc-addr`, `--otlp-http-addr`, `--query-tcp-addr`, `--query-http-addr`, `--query-uds-path` |\n");
-    out.push_str("| `search` | `otell search <pattern>` | `--fixed`, `-i/--ignore-case`, `--since`, `--until`, `--service`, `--trace`, `--span`, `--severity <LEVEL>`, `--where key=glob` (repeat), `-C <N\\|DURATION>`, `--count`, `--stats`, `--sort ts_asc\\|ts_desc`, `--limit` |\n");
-    out.push_str("| `traces` | `otell traces` | `--since`, `--until`, `--service`, `--status`, `--sort`, `--limit` |\n");
+    out.push_str("| `search` | `otell search <pattern>` | `--fixed`, `-i/--ignore-case`, `--since`, `--until`, `--service`, `--trace`, `--span`, `--severity <LEVEL>`, `--where key=glob\\|key>=N\\|key!=v` (repeat), `--sampled\\|--unsampled`, `-C <N\\|DURATION>`, `--count [--compare]`, `--stats`, `--records-only`, `--merge-attrs`, `--show-delay`, `--sort ts_asc\\|ts_desc\\|relevance`, `--half-life`, `--limit`, `--group-window minute\\|hour\\|day`, `--fail-on-empty` |\n");
+    out.push_str("| `traces` | `otell traces` | `--since`, `--until`, `--service`, `--status`, `--where`, `--sort`, `--limit`, `--count`, `--open`, `--tree`, `--failed-spans` |\n");
+    out.push_str("| `spans` | `otell spans` | `--since`, `--until`, `--service`, `--sort ts_asc\\|ts_desc\\|duration_desc`, `--limit` |\n");
     out.push_str(
-        "| `trace` | `otell trace <trace_id>` | `--root <span_id>`, `--logs none\\|bounded\\|all` |\n",
+        "| `failures` | `otell failures` | `--since`, `--until`, `--service`, `--limit` |\n",
     );
+    out.push_str(
+        "| `trace` | `otell trace <trace_id>` | `--root <span_id>`, `--logs none\\|bounded\\|all`, `--paginate --page-size --offset`, `--compact` |\n",
+    );
+    out.push_str("| `trace-logs` | `otell trace-logs <trace_id>` | `--logs none\\|bounded\\|all`, `--limit` |\n");
     out.push_str(
         "| `span` | `otell span <trace_id> <span_id>` | `--logs none\\|bounded\\|all` |\n",
     );
-    out.push_str("| `metrics` | `otell metrics [<name>\\|list]` | `--since`, `--until`, `--service`, `--group-by`, `--agg`, `--limit` |\n");
-    out.push_str("| `tail` | `otell tail [pattern]` | `--fixed`, `-i/--ignore-case`, `--service`, `--trace`, `--span`, `--severity`, `--http-addr` |\n");
-    out.push_str("| `status` | `otell status` | _(no command-specific flags)_ |\n");
-    out.push_str("| `handle` | `otell handle <base64>` | _(no command-specific flags)_ |\n");
+    out.push_str("| `metrics` | `otell metrics [<name>\\|list]` | `--since`, `--until`, `--service`, `--group-by`, `--agg`, `--limit`, `--sort count\\|cardinality`, `--count`, `--with-services`, `--bucket`, `--sparkline` |\n");
+    out.push_str("| `tail` | `otell tail [pattern]` | `--fixed`, `-i/--ignore-case`, `--service`, `--trace`, `--span`, `--severity`, `--since`, `--until`, `--color-by <attr>`, `--http-addr` |\n");
+    out.push_str("| `status` | `otell status` | `--watch`, `--interval` |\n");
+    out.push_str("| `check` | `otell check` | exits non-zero when a hard check fails |\n");
+    out.push_str("| `graph` | `otell graph` | `--since`, `--until` |\n");
+    out.push_str("| `sampling` | `otell sampling` | `--since`, `--until`, `--service` |\n");
+    out.push_str("| `handle` | `otell handle <base64>` | `--explain` |\n");
+    out.push_str("| `save` | `otell save <name> <pattern>` | same flags as `search` |\n");
+    out.push_str("| `run-saved` | `otell run-saved <name>` | runs a query stored by `save` |\n");
+    out.push_str(
+        "| `replay` | `otell replay <file>` | re-decodes an `OTELL_STORE_RAW_OTLP` capture |\n",
+    );
+    out.push_str("| `query` | `otell query --file req.json` | reads stdin without `--file` |\n");
     out.push_str("| `intro` | `otell intro` | `--human` |\n");
     out.push_str("| `version` | `otell version` | _(no command-specific flags)_ |\n");
     out.push_str("| `mcp` | `otell mcp` | stdio JSON-RPC mode (`initialize`, `tools/list`, `tools/call`) |\n\n");
@@ -667,7 +1248,8 @@ fn render_intro_markdown(input: IntroDocInput<'_>) -> anyhow::Result<String> {
     out.push_str("|---|---|---|\n");
     out.push_str("| `search <pattern>` / `tail [pattern]` (default mode) | Rust `regex` syntax over log body text, with `-i/--ignore-case` for case-insensitive matching | Not full `ripgrep` query language; no PCRE-only features such as look-around assertions or backreferences |\n");
     out.push_str("| `--fixed` | Literal substring match (no regex parsing) | Regex operators are treated as plain text |\n");
-    out.push_str("| `--where key=glob` | Attribute value glob matching (for example `attrs.peer=redis:*`) | Not regex; no regex capture groups or regex operators |\n\n");
+    out.push_str("| `--where key=glob` | Attribute value glob matching (for example `attrs.peer=redis:*`); `!=` negates | Not regex; no regex capture groups or regex operators |\n");
+    out.push_str("| `--where key>N` | `>`, `>=`, `<`, `<=` compare numerically when both sides are numbers (for example `http.status_code>=500`), else lexically | No arithmetic; a missing attribute never matches |\n\n");
     out.push_str("Pattern matching applies to log body text only. Structured filters (`--service`, `--trace`, `--span`, `--severity`, `--where`) are applied separately.\n\n");
 
     if let Some(status) = input.status {
@@ -684,6 +1266,11 @@ fn render_intro_markdown(input: IntroDocInput<'_>) -> anyhow::Result<String> {
                 out.push_str(&format!("| logs_count | {} |\n", st.logs_count));
                 out.push_str(&format!("| spans_count | {} |\n", st.spans_count));
                 out.push_str(&format!("| metrics_count | {} |\n", st.metrics_count));
+                out.push_str(&format!(
+                    "| pending (logs/spans/metrics) | {}/{}/{} |\n",
+                    st.pending_logs, st.pending_spans, st.pending_metrics
+                ));
+                out.push_str(&format!("| max_flush_lag_ms | {} |\n", st.max_flush_lag_ms));
                 let oldest_ts = st
                     .oldest_ts
                     .map(|ts| ts.to_rfc3339())
@@ -814,29 +1401,157 @@ async fn connect_with_retry(
     Err(last_err.unwrap_or_else(|| anyhow::anyhow!("failed to connect to otell query server")))
 }
 
-async fn run_mcp(uds: Option<PathBuf>, addr: Option<String>) -> anyhow::Result<()> {
-    #[derive(serde::Deserialize)]
-    struct McpReq {
-        id: Option<serde_json::Value>,
-        method: Option<String>,
-        params: Option<serde_json::Value>,
+#[derive(serde::Deserialize)]
+struct McpReq {
+    id: Option<serde_json::Value>,
+    method: Option<String>,
+    params: Option<serde_json::Value>,
+}
+
+fn mcp_ok(id: Option<serde_json::Value>, result: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({"jsonrpc":"2.0","id":id,"result":result})
+}
+
+fn mcp_err(id: Option<serde_json::Value>, message: String) -> serde_json::Value {
+    serde_json::json!({"jsonrpc":"2.0","id":id,"error":{"message":message}})
+}
+
+async fn mcp_dispatch(
+    message: serde_json::Value,
+    client: &mut Option<QueryClient>,
+    uds: &Option<PathBuf>,
+    addr: &Option<String>,
+) -> anyhow::Result<Option<serde_json::Value>> {
+    // A request with a method but no `id` member is a notification and gets no
+    // reply; malformed messages still get an error.
+    let notification = message
+        .as_object()
+        .is_some_and(|m| m.contains_key("method") && !m.contains_key("id"));
+    let response = mcp_handle(message, client, uds, addr).await?;
+    Ok((!notification).then_some(response))
+}
+
+async fn mcp_handle(
+    message: serde_json::Value,
+    client: &mut Option<QueryClient>,
+    uds: &Option<PathBuf>,
+    addr: &Option<String>,
+) -> anyhow::Result<serde_json::Value> {
+    let input = match serde_json::from_value::<McpReq>(message) {
+        Ok(v) => v,
+        Err(e) => return Ok(mcp_err(None, e.to_string())),
+    };
+
+    if matches!(input.method.as_deref(), Some("initialize")) {
+        let result = serde_json::json!({
+            "protocolVersion": "0.1.0",
+            "serverInfo": {"name": "otell", "version": env!("CARGO_PKG_VERSION")},
+            "capabilities": {
+                "tools": {"listChanged": false}
+            }
+        });
+        return Ok(mcp_ok(input.id, result));
     }
 
-    fn mcp_ok(id: Option<serde_json::Value>, result: serde_json::Value) -> serde_json::Value {
-        serde_json::json!({"jsonrpc":"2.0","id":id,"result":result})
+    if matches!(input.method.as_deref(), Some("tools/list")) {
+        let result = serde_json::json!({"tools": [
+            {"name":"search"},
+            {"name":"trace"},
+            {"name":"trace_logs"},
+            {"name":"span"},
+            {"name":"traces"},
+            {"name":"spans"},
+            {"name":"failures"},
+            {"name":"metrics"},
+            {"name":"metrics.list"},
+            {"name":"status"},
+            {"name":"graph"},
+            {"name":"sampling"},
+            {"name":"check"},
+            {"name":"resolve_handle"}
+        ]});
+        return Ok(mcp_ok(input.id, result));
     }
 
-    fn mcp_err(id: Option<serde_json::Value>, message: String) -> serde_json::Value {
-        serde_json::json!({"jsonrpc":"2.0","id":id,"error":{"message":message}})
+    if !matches!(input.method.as_deref(), Some("tools/call")) {
+        return Ok(mcp_err(
+            input.id,
+            "unsupported method (expected initialize, tools/list, tools/call)".to_string(),
+        ));
     }
 
+    let tool_name = input
+        .params
+        .as_ref()
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(|s| s.to_string());
+    let Some(tool_name) = tool_name else {
+        return Ok(mcp_err(input.id, "missing tool name".to_string()));
+    };
+
+    let method_args = input
+        .params
+        .as_ref()
+        .and_then(|p| p.get("arguments"))
+        .cloned()
+        .unwrap_or_else(|| serde_json::json!({}));
+
+    let request = match tool_name.as_str() {
+        "search" => serde_json::from_value::<SearchRequest>(method_args).map(ApiRequest::Search),
+        "trace" => serde_json::from_value::<TraceRequest>(method_args).map(ApiRequest::Trace),
+        "trace_logs" => {
+            serde_json::from_value::<TraceLogsRequest>(method_args).map(ApiRequest::TraceLogs)
+        }
+        "span" => serde_json::from_value::<SpanRequest>(method_args).map(ApiRequest::Span),
+        "spans" => serde_json::from_value::<SpansInWindowRequest>(method_args)
+            .map(ApiRequest::SpansInWindow),
+        "failures" => {
+            serde_json::from_value::<FailedSpansRequest>(method_args).map(ApiRequest::FailedSpans)
+        }
+        "traces" => serde_json::from_value::<TracesRequest>(method_args).map(ApiRequest::Traces),
+        "metrics" => serde_json::from_value::<MetricsRequest>(method_args).map(ApiRequest::Metrics),
+        "metrics.list" => {
+            serde_json::from_value::<MetricsListRequest>(method_args).map(ApiRequest::MetricsList)
+        }
+        "resolve_handle" => {
+            serde_json::from_value::<QueryHandle>(method_args).map(ApiRequest::ResolveHandle)
+        }
+        "status" => Ok(ApiRequest::Status),
+        "check" => Ok(ApiRequest::Check),
+        "graph" => {
+            serde_json::from_value::<ServiceGraphRequest>(method_args).map(ApiRequest::ServiceGraph)
+        }
+        "sampling" => serde_json::from_value::<SamplingStatsRequest>(method_args)
+            .map(ApiRequest::SamplingStats),
+        _ => return Ok(mcp_err(input.id, "unknown mcp tool".to_string())),
+    };
+
+    let response = match request {
+        Ok(req) => {
+            if client.is_none() {
+                *client = Some(QueryClient::connect(uds.clone(), addr.clone()).await?);
+            }
+            client
+                .as_mut()
+                .expect("client initialized")
+                .request(req)
+                .await
+                .unwrap_or_else(|e| ApiResponse::Error(e.to_string()))
+        }
+        Err(e) => ApiResponse::Error(format!("invalid tool arguments: {e}")),
+    };
+
+    Ok(mcp_ok(input.id, serde_json::to_value(response)?))
+}
+
+async fn run_mcp(uds: Option<PathBuf>, addr: Option<String>) -> anyhow::Result<()> {
     let mut client: Option<QueryClient> = None;
     let stdin = tokio::io::stdin();
     let mut lines = BufReader::new(stdin).lines();
 
     while let Some(line) = lines.next_line().await? {
-        let input: Result<McpReq, _> = serde_json::from_str(&line);
-        let input = match input {
+        let message = match serde_json::from_str::<serde_json::Value>(&line) {
             Ok(v) => v,
             Err(e) => {
                 println!("{}", serde_json::to_string(&mcp_err(None, e.to_string()))?);
@@ -844,111 +1559,26 @@ async fn run_mcp(uds: Option<PathBuf>, addr: Option<String>) -> anyhow::Result<(
             }
         };
 
-        if matches!(input.method.as_deref(), Some("initialize")) {
-            let result = serde_json::json!({
-                "protocolVersion": "0.1.0",
-                "serverInfo": {"name": "otell", "version": env!("CARGO_PKG_VERSION")},
-                "capabilities": {
-                    "tools": {"listChanged": false}
-                }
-            });
-            println!("{}", serde_json::to_string(&mcp_ok(input.id, result))?);
-            continue;
-        }
-
-        if matches!(input.method.as_deref(), Some("tools/list")) {
-            let result = serde_json::json!({"tools": [
-                {"name":"search"},
-                {"name":"trace"},
-                {"name":"span"},
-                {"name":"traces"},
-                {"name":"metrics"},
-                {"name":"metrics.list"},
-                {"name":"status"},
-                {"name":"resolve_handle"}
-            ]});
-            println!("{}", serde_json::to_string(&mcp_ok(input.id, result))?);
-            continue;
-        }
-
-        if !matches!(input.method.as_deref(), Some("tools/call")) {
-            println!(
-                "{}",
-                serde_json::to_string(&mcp_err(
-                    input.id,
-                    "unsupported method (expected initialize, tools/list, tools/call)".to_string()
-                ))?
-            );
-            continue;
-        }
-
-        let tool_name = input
-            .params
-            .as_ref()
-            .and_then(|p| p.get("name"))
-            .and_then(|n| n.as_str())
-            .map(|s| s.to_string());
-        let Some(tool_name) = tool_name else {
-            println!(
-                "{}",
-                serde_json::to_string(&mcp_err(input.id, "missing tool name".to_string()))?
-            );
-            continue;
-        };
-
-        let method_args = input
-            .params
-            .as_ref()
-            .and_then(|p| p.get("arguments"))
-            .cloned()
-            .unwrap_or_else(|| serde_json::json!({}));
-
-        let request = match tool_name.as_str() {
-            "search" => {
-                serde_json::from_value::<SearchRequest>(method_args).map(ApiRequest::Search)
-            }
-            "trace" => serde_json::from_value::<TraceRequest>(method_args).map(ApiRequest::Trace),
-            "span" => serde_json::from_value::<SpanRequest>(method_args).map(ApiRequest::Span),
-            "traces" => {
-                serde_json::from_value::<TracesRequest>(method_args).map(ApiRequest::Traces)
-            }
-            "metrics" => {
-                serde_json::from_value::<MetricsRequest>(method_args).map(ApiRequest::Metrics)
+        // JSON-RPC batch: an array of requests gets an array of responses,
+        // leaving out notifications; nothing is written if all were notifications.
+        let output = match message {
+            serde_json::Value::Array(batch) if batch.is_empty() => {
+                Some(mcp_err(None, "empty batch".to_string()))
             }
-            "metrics.list" => serde_json::from_value::<MetricsListRequest>(method_args)
-                .map(ApiRequest::MetricsList),
-            "resolve_handle" => {
-                serde_json::from_value::<QueryHandle>(method_args).map(ApiRequest::ResolveHandle)
-            }
-            "status" => Ok(ApiRequest::Status),
-            _ => {
-                println!(
-                    "{}",
-                    serde_json::to_string(&mcp_err(input.id, "unknown mcp tool".to_string()))?
-                );
-                continue;
-            }
-        };
-
-        let response = match request {
-            Ok(req) => {
-                if client.is_none() {
-                    client = Some(QueryClient::connect(uds.clone(), addr.clone()).await?);
+            serde_json::Value::Array(batch) => {
+                let mut responses = Vec::with_capacity(batch.len());
+                for item in batch {
+                    if let Some(response) = mcp_dispatch(item, &mut client, &uds, &addr).await? {
+                        responses.push(response);
+                    }
                 }
-                client
-                    .as_mut()
-                    .expect("client initialized")
-                    .request(req)
-                    .await
-                    .unwrap_or_else(|e| ApiResponse::Error(e.to_string()))
+                (!responses.is_empty()).then_some(serde_json::Value::Array(responses))
             }
-            Err(e) => ApiResponse::Error(format!("invalid tool arguments: {e}")),
+            message => mcp_dispatch(message, &mut client, &uds, &addr).await?,
         };
-
-        println!(
-            "{}",
-            serde_json::to_string(&mcp_ok(input.id, serde_json::to_value(response)?))?
-        );
+        if let Some(output) = output {
+            println!("{}", serde_json::to_string(&output)?);
+        }
     }
 
     Ok(())
@@ -983,7 +1613,10 @@ async fn run_server(
         cfg.uds_path = v;
     }
 
-    let store = otell_store::Store::open(&cfg.db_path)?;
+    let store = otell_store::Store::open(&cfg.db_path)?
+        .with_tail_buffer(cfg.tail_buffer)
+        .with_regex_size_limit(cfg.query_regex_size_limit)
+        .with_context_scan_limit(cfg.context_scan_limit);
     init_run_tracing(telemetry_cfg, Some(store.clone()));
 
     eprintln!("otell run");
@@ -993,11 +1626,44 @@ async fn run_server(
     eprintln!("  query uds: {}", cfg.uds_path.display());
     eprintln!("  query tcp: {}", cfg.query_tcp_addr);
     eprintln!("  query http: {}", cfg.query_http_addr);
+    if let Some(max_age) = cfg.ingest_reject_older_than {
+        eprintln!(
+            "  ingest window: rejecting records older than {}",
+            humantime::format_duration(max_age)
+        );
+    }
+    if let Some(path) = &cfg.query_audit {
+        eprintln!("  query audit: {}", path.display());
+    }
     eprintln!("  tip: run `otell intro` in another shell");
 
     let grpc_addr = cfg.otlp_grpc_addr.parse()?;
     let http_addr = cfg.otlp_http_addr.parse()?;
 
+    let forward_cfg = cfg
+        .forward_otlp_endpoint
+        .clone()
+        .map(|endpoint| ForwardConfig {
+            endpoint,
+            protocol: ForwardProtocol::parse(&cfg.forward_otlp_protocol),
+            compression: ForwardCompression::parse(&cfg.forward_otlp_compression),
+            headers: cfg.forward_otlp_headers.clone(),
+            timeout: cfg.forward_otlp_timeout,
+        });
+    if cfg.forward_otlp_require
+        && let Some(forward) = &forward_cfg
+    {
+        probe_forward_endpoint(forward).await.context(
+            "forwarding is required (OTELL_FORWARD_REQUIRE) but the target is unreachable",
+        )?;
+        eprintln!("  forward: {} (reachable)", forward.endpoint);
+    }
+
+    let raw_capture = match &cfg.store_raw_otlp {
+        Some(path) => RawCapture::open(path, RAW_OTLP_MAX_BYTES)?,
+        None => RawCapture::disabled(),
+    };
+
     let ingest_task = tokio::spawn(otell_ingest::server::run_ingest_servers(
         store.clone(),
         grpc_addr,
@@ -1006,27 +1672,41 @@ async fn run_server(
             channel_capacity: 512,
             flush_interval: std::time::Duration::from_millis(cfg.write_flush_ms),
             batch_size: cfg.write_batch_size,
+            signal_flush: SignalFlush {
+                logs: flush_override(cfg.write_log_batch_size, cfg.write_log_flush_ms),
+                spans: flush_override(cfg.write_span_batch_size, cfg.write_span_flush_ms),
+                metrics: flush_override(cfg.write_metric_batch_size, cfg.write_metric_flush_ms),
+            },
+            reject_older_than: cfg.ingest_reject_older_than,
+            body_parser: BodyParser::parse(&cfg.parse_body),
+            log_time_source: LogTimeSource::parse(&cfg.log_time_source),
+            drop_span_names: cfg.drop_span_names.clone(),
+            raw_capture,
         },
-        cfg.forward_otlp_endpoint
-            .clone()
-            .map(|endpoint| ForwardConfig {
-                endpoint,
-                protocol: ForwardProtocol::parse(&cfg.forward_otlp_protocol),
-                compression: ForwardCompression::parse(&cfg.forward_otlp_compression),
-                headers: cfg.forward_otlp_headers.clone(),
-                timeout: cfg.forward_otlp_timeout,
-            }),
+        forward_cfg,
+        cfg.max_concurrent_requests,
     ));
 
+    let audit = match &cfg.query_audit {
+        Some(path) => QueryAudit::open(path)?,
+        None => QueryAudit::disabled(),
+    };
+
     let query_task = tokio::spawn(query_server::run_query_server(
         store.clone(),
+        audit.clone(),
         cfg.uds_path.clone(),
         cfg.query_tcp_addr.parse()?,
+        cfg.max_concurrent_requests,
+        cfg.query_token.clone(),
     ));
 
     let query_http_task = tokio::spawn(query_server::run_query_http_server(
         store.clone(),
+        audit,
         cfg.query_http_addr.parse()?,
+        cfg.max_concurrent_requests,
+        cfg.query_cors_origins.clone(),
     ));
 
     let retention_task = tokio::spawn({
@@ -1064,20 +1744,233 @@ async fn run_server(
     Ok(())
 }
 
+fn flush_override(batch_size: Option<usize>, flush_ms: Option<u64>) -> FlushOverride {
+    FlushOverride {
+        batch_size,
+        flush_interval: flush_ms.map(std::time::Duration::from_millis),
+    }
+}
+
 fn parse_window(since: Option<String>, until: Option<String>) -> anyhow::Result<TimeWindow> {
     let since = since.map(|v| parse_time_or_relative(&v)).transpose()?;
     let until = until.map(|v| parse_time_or_relative(&v)).transpose()?;
     Ok(TimeWindow { since, until })
 }
 
+fn failed_spans_request(
+    since: Option<String>,
+    until: Option<String>,
+    service: Option<String>,
+    limit: usize,
+) -> anyhow::Result<ApiRequest> {
+    Ok(ApiRequest::FailedSpans(FailedSpansRequest {
+        window: parse_window(since, until)?,
+        service,
+        limit,
+    }))
+}
+
+fn search_request(args: &SearchArgs) -> anyhow::Result<SearchRequest> {
+    let (context_lines, context_seconds) = parse_context(args.context.clone())?;
+    Ok(SearchRequest {
+        pattern: Some(args.pattern.clone()),
+        fixed: args.fixed,
+        ignore_case: args.ignore_case,
+        service: args.service.clone(),
+        trace_id: args.trace.clone(),
+        span_id: args.span.clone(),
+        severity_gte: args
+            .severity
+            .as_deref()
+            .map(Severity::from_str)
+            .transpose()?,
+        attr_filters: parse_where_filters(args.where_filters.clone())?,
+        window: parse_window(args.since.clone(), args.until.clone())?,
+        sort: parse_sort(&args.sort),
+        limit: args.limit,
+        context_lines,
+        context_seconds,
+        count_only: args.count,
+        include_stats: args.stats,
+        rollup: args.group_window.as_deref().map(parse_rollup).transpose()?,
+        sampled: match (args.sampled, args.unsampled) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+        relevance_half_life_secs: args
+            .half_life
+            .as_deref()
+            .map(|h| parse_duration_str(h).map(|d| d.as_secs()))
+            .transpose()?,
+    })
+}
+
+/// Fixes an open-ended `until` at `now` so both `--compare` windows have
+/// the same length, and returns the window just before `window`. Bounds are
+/// inclusive, so the previous window ends one microsecond before `since`.
+fn pin_compare_window(
+    window: &mut TimeWindow,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<TimeWindow> {
+    let Some(since) = window.since else {
+        anyhow::bail!("--compare needs --since to size the window");
+    };
+    let until = *window.until.get_or_insert(now);
+    if until <= since {
+        anyhow::bail!("--compare needs --until after --since");
+    }
+    Ok(TimeWindow {
+        since: Some(since - (until - since)),
+        until: Some(since - chrono::Duration::microseconds(1)),
+    })
+}
+
+fn search_total(response: ApiResponse) -> anyhow::Result<usize> {
+    match response {
+        ApiResponse::Search(resp) => Ok(resp.total_matches),
+        ApiResponse::Error(e) => Err(anyhow::anyhow!(e)),
+        other => Err(anyhow::anyhow!("unexpected response: {other:?}")),
+    }
+}
+
+/// `search --count --compare` result. `change_pct` is `None` when the
+/// previous window had no matches.
+#[derive(Debug, Clone, Serialize)]
+struct SearchComparison {
+    count: usize,
+    previous: usize,
+    delta: i64,
+    change_pct: Option<f64>,
+    window: TimeWindow,
+}
+
+impl SearchComparison {
+    fn new(count: usize, previous: usize, window: TimeWindow) -> Self {
+        let delta = count as i64 - previous as i64;
+        Self {
+            count,
+            previous,
+            delta,
+            change_pct: (previous > 0).then(|| delta as f64 * 100.0 / previous as f64),
+            window,
+        }
+    }
+
+    fn human_line(&self) -> String {
+        let change = self
+            .change_pct
+            .map(|pct| format!("{pct:+.1}%"))
+            .unwrap_or_else(|| "n/a".to_string());
+        format!(
+            "count={} previous={} delta={:+} change={change}",
+            self.count, self.previous, self.delta
+        )
+    }
+}
+
+/// A named search kept in `queries.json` next to the config file. The
+/// request is stored as its handle; `since`/`until` keep the flags as typed
+/// so relative windows like `15m` are re-resolved on every run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SavedQuery {
+    handle: String,
+    #[serde(default)]
+    since: Option<String>,
+    #[serde(default)]
+    until: Option<String>,
+}
+
+impl SavedQuery {
+    /// Only the request and its window are saved, so flags that change how
+    /// `search` prints or exits are rejected rather than silently dropped.
+    fn from_search(search: &SearchArgs) -> anyhow::Result<Self> {
+        let output_flags = [
+            ("--compare", search.compare),
+            ("--records-only", search.records_only),
+            ("--fail-on-empty", search.fail_on_empty),
+            ("--merge-attrs", search.merge_attrs),
+            ("--show-delay", search.show_delay),
+        ]
+        .into_iter()
+        .filter_map(|(flag, set)| set.then_some(flag))
+        .collect::<Vec<_>>();
+        if !output_flags.is_empty() {
+            anyhow::bail!(
+                "{} only affect how `otell search` prints its result and can't be saved",
+                output_flags.join(", ")
+            );
+        }
+        let req = ApiRequest::Search(search_request(search)?);
+        Ok(Self {
+            handle: encode_handle(&req)?,
+            since: search.since.clone(),
+            until: search.until.clone(),
+        })
+    }
+
+    fn request(&self) -> anyhow::Result<ApiRequest> {
+        let mut req = decode_handle(&self.handle).context("decode saved query")?;
+        if let ApiRequest::Search(search) = &mut req {
+            search.window = parse_window(self.since.clone(), self.until.clone())?;
+        }
+        Ok(req)
+    }
+}
+
+fn saved_queries_path() -> PathBuf {
+    otell_core::config::config_file_path().with_file_name("queries.json")
+}
+
+fn read_saved_queries(
+    path: &std::path::Path,
+) -> anyhow::Result<std::collections::BTreeMap<String, SavedQuery>> {
+    match std::fs::read_to_string(path) {
+        Ok(raw) => serde_json::from_str(&raw).with_context(|| format!("parse {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Default::default()),
+        Err(e) => Err(e).with_context(|| format!("read {}", path.display())),
+    }
+}
+
+fn save_query(path: &std::path::Path, name: &str, query: SavedQuery) -> anyhow::Result<()> {
+    let mut queries = read_saved_queries(path)?;
+    queries.insert(name.to_string(), query);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(&queries)?)
+        .with_context(|| format!("write {}", path.display()))
+}
+
+fn load_saved_query(path: &std::path::Path, name: &str) -> anyhow::Result<SavedQuery> {
+    read_saved_queries(path)?
+        .remove(name)
+        .ok_or_else(|| anyhow::anyhow!("no saved query named {name:?} in {}", path.display()))
+}
+
+fn parse_where_filters(filters: Vec<String>) -> anyhow::Result<Vec<AttrFilter>> {
+    Ok(filters
+        .iter()
+        .map(|f| AttrFilter::parse(f))
+        .collect::<otell_core::Result<Vec<_>>>()?)
+}
+
 fn parse_sort(sort: &str) -> SortOrder {
     match sort {
         "ts_desc" => SortOrder::TsDesc,
         "duration_desc" => SortOrder::DurationDesc,
+        "relevance" => SortOrder::Relevance,
         _ => SortOrder::TsAsc,
     }
 }
 
+fn parse_metrics_list_sort(sort: &str) -> MetricsListSort {
+    match sort {
+        "cardinality" => MetricsListSort::Cardinality,
+        _ => MetricsListSort::Count,
+    }
+}
+
 fn parse_logs_mode(s: &str) -> anyhow::Result<LogContextMode> {
     match s {
         "none" => Ok(LogContextMode::None),
@@ -1087,6 +1980,15 @@ fn parse_logs_mode(s: &str) -> anyhow::Result<LogContextMode> {
     }
 }
 
+fn parse_rollup(s: &str) -> anyhow::Result<RollupUnit> {
+    match s {
+        "minute" => Ok(RollupUnit::Minute),
+        "hour" => Ok(RollupUnit::Hour),
+        "day" => Ok(RollupUnit::Day),
+        other => anyhow::bail!("invalid group window: {other} (expected minute|hour|day)"),
+    }
+}
+
 fn parse_context(context: Option<String>) -> anyhow::Result<(usize, Option<i64>)> {
     let Some(c) = context else {
         return Ok((0, None));
@@ -1110,6 +2012,243 @@ fn decode_handle(handle: &str) -> anyhow::Result<ApiRequest> {
     Ok(serde_json::from_slice(&bytes)?)
 }
 
+fn read_query_request(file: Option<&std::path::Path>) -> anyhow::Result<ApiRequest> {
+    let raw = match file {
+        Some(path) if path != std::path::Path::new("-") => std::fs::read_to_string(path)
+            .with_context(|| format!("read query file {}", path.display()))?,
+        _ => std::io::read_to_string(std::io::stdin()).context("read query from stdin")?,
+    };
+    serde_json::from_str(&raw).context("parse ApiRequest JSON")
+}
+
+fn traces_with_commands(items: &[TraceListItem]) -> anyhow::Result<serde_json::Value> {
+    let mut value = serde_json::to_value(ApiResponse::Traces(items.to_vec()))?;
+    if let Some(rows) = value.get_mut("Traces").and_then(|v| v.as_array_mut()) {
+        for row in rows {
+            let command = row
+                .get("trace_id")
+                .and_then(|v| v.as_str())
+                .map(trace_command);
+            if let (Some(obj), Some(command)) = (row.as_object_mut(), command) {
+                obj.insert("command".to_string(), serde_json::Value::String(command));
+            }
+        }
+    }
+    Ok(value)
+}
+
+async fn fetch_trace_trees(
+    client: &mut QueryClient,
+    items: &[TraceListItem],
+) -> anyhow::Result<Vec<TraceResponse>> {
+    let mut trees = Vec::new();
+    for item in items.iter().take(TRACES_TREE_MAX) {
+        let req = ApiRequest::Trace(TraceRequest {
+            trace_id: item.trace_id.clone(),
+            root_span_id: None,
+            logs: LogContextMode::None,
+            logs_offset: 0,
+            logs_limit: None,
+        });
+        match client.request(req).await? {
+            ApiResponse::Trace(trace) => trees.push(trace),
+            ApiResponse::Error(err) => anyhow::bail!(err),
+            other => anyhow::bail!("unexpected response: {other:?}"),
+        }
+    }
+    Ok(trees)
+}
+
+/// Adds a `spans` array to each expanded row of a serialized trace list.
+fn embed_trace_spans(value: &mut serde_json::Value, trees: &[TraceResponse]) -> anyhow::Result<()> {
+    if let Some(rows) = value.get_mut("Traces").and_then(|v| v.as_array_mut()) {
+        for (row, trace) in rows.iter_mut().zip(trees) {
+            if let Some(obj) = row.as_object_mut() {
+                obj.insert("spans".to_string(), serde_json::to_value(&trace.spans)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn explain_handle(handle: &str) -> anyhow::Result<String> {
+    let req = decode_handle(handle).context("decode handle")?;
+    Ok(serde_json::to_string_pretty(&req)?)
+}
+
+/// Per-second growth of each signal between two status polls. Counts that
+/// shrank (retention pruning) report zero.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct StatusRates {
+    logs_per_sec: f64,
+    spans_per_sec: f64,
+    metrics_per_sec: f64,
+}
+
+fn status_rates(
+    prev: &StatusResponse,
+    cur: &StatusResponse,
+    elapsed: std::time::Duration,
+) -> StatusRates {
+    let secs = elapsed.as_secs_f64();
+    let rate = |before: usize, after: usize| {
+        if secs <= 0.0 {
+            0.0
+        } else {
+            after.saturating_sub(before) as f64 / secs
+        }
+    };
+    StatusRates {
+        logs_per_sec: rate(prev.logs_count, cur.logs_count),
+        spans_per_sec: rate(prev.spans_count, cur.spans_count),
+        metrics_per_sec: rate(prev.metrics_count, cur.metrics_count),
+    }
+}
+
+async fn run_status_watch(
+    uds: Option<PathBuf>,
+    addr: Option<String>,
+    interval: std::time::Duration,
+    json: bool,
+) -> anyhow::Result<()> {
+    let redraw = !json && std::io::stdout().is_terminal();
+    let mut client: Option<QueryClient> = None;
+    let mut prev: Option<(StatusResponse, std::time::Instant)> = None;
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        if client.is_none() {
+            match QueryClient::connect(uds.clone(), addr.clone()).await {
+                Ok(c) => client = Some(c),
+                Err(err) => {
+                    eprintln!("status: waiting for server: {err}");
+                    continue;
+                }
+            }
+        }
+        let status = match client
+            .as_mut()
+            .expect("client connected")
+            .request(ApiRequest::Status)
+            .await
+        {
+            Ok(ApiResponse::Status(status)) => status,
+            Ok(ApiResponse::Error(err)) => anyhow::bail!(err),
+            Ok(other) => anyhow::bail!("unexpected response: {other:?}"),
+            Err(err) => {
+                eprintln!("status: connection lost, reconnecting: {err}");
+                client = None;
+                continue;
+            }
+        };
+        let now = std::time::Instant::now();
+        let rates = prev
+            .as_ref()
+            .map(|(before, at)| status_rates(before, &status, now - *at));
+
+        if json {
+            println!(
+                "{}",
+                serde_json::json!({ "status": &status, "rates": rates })
+            );
+        } else {
+            if redraw {
+                print!("\x1b[2J\x1b[H");
+            }
+            print_status_human(&status);
+            if let Some(r) = rates {
+                println!(
+                    "rate logs={:.1}/s spans={:.1}/s metrics={:.1}/s",
+                    r.logs_per_sec, r.spans_per_sec, r.metrics_per_sec
+                );
+            }
+            if !redraw {
+                println!("--");
+            }
+        }
+        prev = Some((status, now));
+    }
+}
+
+/// Explains a zero-match search: an empty store, a window that excluded
+/// everything stored, or filters that matched nothing.
+fn empty_search_hint(window: &TimeWindow, status: &StatusResponse) -> String {
+    if status.logs_count == 0 {
+        return "hint: the store has no logs yet; is anything exporting OTLP logs to otell?"
+            .to_string();
+    }
+    let stored = status.logs_count;
+    if window.since.is_none() && window.until.is_none() {
+        return format!("hint: no logs matched these filters ({stored} logs stored)");
+    }
+    let fmt = |ts: Option<chrono::DateTime<chrono::Utc>>| {
+        ts.map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    format!(
+        "hint: no logs matched in this window ({stored} logs stored, oldest={} newest={})",
+        fmt(status.oldest_ts),
+        fmt(status.newest_ts)
+    )
+}
+
+/// Where a query's replay handle goes: a trailing `handle=` line after human
+/// output (dropped by `--no-handle` / `OTELL_NO_HANDLE`), or a top-level
+/// `handle` field in the JSON envelope with `--json --embed-handle`.
+#[derive(Debug, Clone, Copy)]
+struct HandleOutput {
+    hide: bool,
+    embed: bool,
+}
+
+impl HandleOutput {
+    fn from_cli(cli: &Cli) -> Self {
+        let env_hide = std::env::var("OTELL_NO_HANDLE").is_ok_and(|v| {
+            matches!(
+                v.trim().to_ascii_lowercase().as_str(),
+                "1" | "true" | "yes" | "on"
+            )
+        });
+        Self {
+            hide: cli.no_handle || env_hide,
+            embed: cli.embed_handle,
+        }
+    }
+
+    fn print_line(self, handle: &str) {
+        if !self.hide {
+            println!("handle={handle}");
+        }
+    }
+
+    fn embed_into(self, value: &mut serde_json::Value, handle: &str) {
+        if self.embed
+            && let Some(obj) = value.as_object_mut()
+        {
+            obj.insert("handle".to_string(), handle.into());
+        }
+    }
+}
+
+fn print_query_response(
+    response: ApiResponse,
+    handle: &str,
+    json: bool,
+    handles: HandleOutput,
+) -> anyhow::Result<()> {
+    if json && handles.embed {
+        let mut value = serde_json::to_value(&response)?;
+        handles.embed_into(&mut value, handle);
+        println!("{}", serde_json::to_string_pretty(&value)?);
+        return Ok(());
+    }
+    print_response(response, json)?;
+    if !json {
+        handles.print_line(handle);
+    }
+    Ok(())
+}
+
 fn print_response(response: ApiResponse, json: bool) -> anyhow::Result<()> {
     if json {
         println!("{}", serde_json::to_string_pretty(&response)?);
@@ -1117,13 +2256,20 @@ fn print_response(response: ApiResponse, json: bool) -> anyhow::Result<()> {
     }
 
     match response {
-        ApiResponse::Search(v) => print_search_human(&v),
+        ApiResponse::Search(v) => print_search_human(&v, false, false),
         ApiResponse::Trace(v) => print_trace_human(&v),
+        ApiResponse::TraceLogs(v) => print_trace_logs_human(&v),
         ApiResponse::Span(v) => print_span_human(&v),
-        ApiResponse::Traces(v) => print_traces_human(&v),
+        ApiResponse::Traces(v) => print_traces_human(&v, false),
+        ApiResponse::SpansInWindow(v) => print_spans_human(&v),
+        ApiResponse::FailedSpans(v) => print_failed_spans_human(&v),
         ApiResponse::Metrics(v) => print_metrics_human(&v),
         ApiResponse::MetricsList(v) => print_metrics_list_human(&v),
         ApiResponse::Status(v) => print_status_human(&v),
+        ApiResponse::Check(v) => print_check_human(&v),
+        ApiResponse::ServiceGraph(v) => print_service_graph_human(&v),
+        ApiResponse::SamplingStats(v) => print_sampling_human(&v),
+        ApiResponse::Count(n) => println!("count={n}"),
         ApiResponse::Error(e) => eprintln!("error: {e}"),
     }
     Ok(())
@@ -1132,6 +2278,7 @@ fn print_response(response: ApiResponse, json: bool) -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
     use clap::error::ErrorKind;
 
     #[test]
@@ -1158,9 +2305,26 @@ mod tests {
             parse_sort("duration_desc"),
             SortOrder::DurationDesc
         ));
+        assert!(matches!(parse_sort("relevance"), SortOrder::Relevance));
         assert!(matches!(parse_sort("other"), SortOrder::TsAsc));
     }
 
+    #[test]
+    fn parse_metrics_list_sort_variants() {
+        assert_eq!(
+            parse_metrics_list_sort("cardinality"),
+            MetricsListSort::Cardinality
+        );
+        assert_eq!(parse_metrics_list_sort("count"), MetricsListSort::Count);
+    }
+
+    #[test]
+    fn parse_rollup_variants() {
+        assert_eq!(parse_rollup("hour").unwrap(), RollupUnit::Hour);
+        assert_eq!(parse_rollup("day").unwrap(), RollupUnit::Day);
+        assert!(parse_rollup("1h").is_err());
+    }
+
     #[test]
     fn parse_context_lines_and_time() {
         assert_eq!(parse_context(Some("20".into())).unwrap(), (20, None));
@@ -1168,6 +2332,240 @@ mod tests {
         assert!(parse_context(Some("wat".into())).is_err());
     }
 
+    #[test]
+    fn compare_window_precedes_with_equal_length() {
+        let now = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 12, 0, 0).unwrap();
+        let mut open = TimeWindow {
+            since: Some(now - chrono::Duration::minutes(15)),
+            until: None,
+        };
+        let previous = pin_compare_window(&mut open, now).unwrap();
+        assert_eq!(open.until, Some(now));
+        assert_eq!(previous.since, Some(now - chrono::Duration::minutes(30)));
+        assert_eq!(
+            previous.until,
+            Some(now - chrono::Duration::minutes(15) - chrono::Duration::microseconds(1))
+        );
+
+        let mut fixed = TimeWindow {
+            since: Some(now - chrono::Duration::hours(3)),
+            until: Some(now - chrono::Duration::hours(1)),
+        };
+        let previous = pin_compare_window(&mut fixed, now).unwrap();
+        assert_eq!(previous.since, Some(now - chrono::Duration::hours(5)));
+        assert!(previous.until.unwrap() < fixed.since.unwrap());
+
+        assert!(pin_compare_window(&mut TimeWindow::all(), now).is_err());
+
+        let cmp = SearchComparison::new(12, 8, fixed);
+        assert_eq!(cmp.delta, 4);
+        assert_eq!(
+            cmp.human_line(),
+            "count=12 previous=8 delta=+4 change=+50.0%"
+        );
+        assert_eq!(
+            SearchComparison::new(3, 0, TimeWindow::all()).change_pct,
+            None
+        );
+    }
+
+    #[test]
+    fn saved_query_round_trips_and_resolves_relative_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otell/queries.json");
+        let cli = Cli::try_parse_from([
+            "otell",
+            "save",
+            "redis",
+            "timeout",
+            "--service",
+            "api",
+            "--where",
+            "peer=redis*",
+            "--since",
+            "15m",
+            "--limit",
+            "5",
+        ])
+        .unwrap();
+        let Commands::Save { name, search } = cli.command else {
+            panic!("expected save");
+        };
+        let req = search_request(&search).unwrap();
+        let saved = SavedQuery::from_search(&search).unwrap();
+        save_query(&path, &name, saved.clone()).unwrap();
+        save_query(&path, "other", saved.clone()).unwrap();
+
+        let loaded = load_saved_query(&path, "redis").unwrap();
+        assert_eq!(loaded, saved);
+        let ApiRequest::Search(rerun) = loaded.request().unwrap() else {
+            panic!("expected search");
+        };
+        assert_eq!(rerun.pattern, req.pattern);
+        assert_eq!(rerun.service, req.service);
+        assert_eq!(rerun.attr_filters, req.attr_filters);
+        assert_eq!(rerun.limit, 5);
+        assert!(rerun.window.since.unwrap() >= req.window.since.unwrap());
+        assert!(load_saved_query(&path, "missing").is_err());
+    }
+
+    #[test]
+    fn save_rejects_output_only_flags() {
+        let cli = Cli::try_parse_from([
+            "otell",
+            "save",
+            "errors",
+            "error",
+            "--count",
+            "--compare",
+            "--fail-on-empty",
+        ])
+        .unwrap();
+        let Commands::Save { search, .. } = cli.command else {
+            panic!("expected save");
+        };
+        let err = SavedQuery::from_search(&search).unwrap_err().to_string();
+        assert!(err.contains("--compare, --fail-on-empty"), "{err}");
+
+        let cli = Cli::try_parse_from(["otell", "save", "errors", "error", "--count"]).unwrap();
+        let Commands::Save { search, .. } = cli.command else {
+            panic!("expected save");
+        };
+        let saved = SavedQuery::from_search(&search).unwrap();
+        let ApiRequest::Search(req) = saved.request().unwrap() else {
+            panic!("expected search");
+        };
+        assert!(req.count_only);
+    }
+
+    #[test]
+    fn explain_handle_round_trips_search() {
+        let req = ApiRequest::Search(SearchRequest {
+            pattern: Some("timeout".into()),
+            service: Some("api".into()),
+            limit: 5,
+            ..SearchRequest::default()
+        });
+        let explained = explain_handle(&encode_handle(&req).unwrap()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&explained).unwrap();
+        assert_eq!(value["Search"]["pattern"], "timeout");
+        assert_eq!(value["Search"]["service"], "api");
+        assert_eq!(value["Search"]["limit"], 5);
+        assert!(explain_handle("not base64!").is_err());
+    }
+
+    #[test]
+    fn traces_json_includes_follow_up_commands() {
+        let items = vec![TraceListItem {
+            trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".into(),
+            root_name: "GET /checkout".into(),
+            duration_ms: 12,
+            span_count: 3,
+            status: "OK".into(),
+        }];
+        let value = traces_with_commands(&items).unwrap();
+        let row = &value["Traces"][0];
+        assert_eq!(
+            row["command"],
+            "otell trace 4bf92f3577b34da6a3ce929d0e0e4736 --logs bounded"
+        );
+        assert_eq!(row["trace_id"], "4bf92f3577b34da6a3ce929d0e0e4736");
+
+        let cli = Cli::try_parse_from(
+            row["command"]
+                .as_str()
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert!(matches!(cli.command, Commands::Trace { trace_id, logs, .. }
+            if trace_id == "4bf92f3577b34da6a3ce929d0e0e4736" && logs == "bounded"));
+    }
+
+    #[test]
+    fn empty_search_hint_distinguishes_empty_store_from_window() {
+        let mut status = StatusResponse {
+            db_path: "otell.duckdb".into(),
+            db_size_bytes: 0,
+            logs_count: 0,
+            spans_count: 0,
+            metrics_count: 0,
+            oldest_ts: None,
+            newest_ts: None,
+            pending_logs: 0,
+            pending_spans: 0,
+            pending_metrics: 0,
+            max_flush_lag_ms: 0,
+        };
+        let window = parse_window(Some("5m".into()), None).unwrap();
+        assert!(empty_search_hint(&window, &status).contains("no logs yet"));
+
+        status.logs_count = 12;
+        let hint = empty_search_hint(&window, &status);
+        assert!(hint.contains("in this window"));
+        assert!(hint.contains("12 logs stored"));
+        assert!(empty_search_hint(&TimeWindow::all(), &status).contains("these filters"));
+    }
+
+    #[test]
+    fn status_rates_divide_deltas_by_elapsed() {
+        let status = |logs, spans, metrics| StatusResponse {
+            db_path: "otell.duckdb".into(),
+            db_size_bytes: 0,
+            logs_count: logs,
+            spans_count: spans,
+            metrics_count: metrics,
+            oldest_ts: None,
+            newest_ts: None,
+            pending_logs: 0,
+            pending_spans: 0,
+            pending_metrics: 0,
+            max_flush_lag_ms: 0,
+        };
+        let rates = status_rates(
+            &status(100, 10, 50),
+            &status(300, 15, 40),
+            std::time::Duration::from_secs(4),
+        );
+        assert_eq!(rates.logs_per_sec, 50.0);
+        assert_eq!(rates.spans_per_sec, 1.25);
+        assert_eq!(rates.metrics_per_sec, 0.0, "pruned counts clamp to zero");
+
+        let instant = status_rates(
+            &status(1, 1, 1),
+            &status(2, 2, 2),
+            std::time::Duration::ZERO,
+        );
+        assert_eq!(instant.logs_per_sec, 0.0);
+    }
+
+    #[test]
+    fn tail_line_surfaces_color_by_attribute() {
+        let record = otell_core::model::log::LogRecord {
+            ts: chrono::Utc::now(),
+            service: "api".into(),
+            severity: 13,
+            trace_id: None,
+            span_id: None,
+            body: "slow upstream".into(),
+            attrs_json: r#"{"pod":"api-7f9c","replicas":3}"#.into(),
+            attrs_text: "pod=api-7f9c replicas=3".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+        let line = render_tail_record(&record, Some("pod"), false);
+        assert!(
+            line.ends_with(" api WARN [pod=api-7f9c] | slow upstream"),
+            "{line}"
+        );
+        assert!(
+            render_tail_record(&record, Some("attrs.replicas"), false).contains("[replicas=3]")
+        );
+        assert!(render_tail_record(&record, Some("zone"), false).contains("[zone=-]"));
+        assert!(render_tail_record(&record, None, false).ends_with(" api WARN | slow upstream"));
+    }
+
     #[test]
     fn parse_version_subcommand() {
         let cli = Cli::try_parse_from(["otell", "version"]).unwrap();
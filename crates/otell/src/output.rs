@@ -1,19 +1,58 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
 
 use chrono::SecondsFormat;
+use otell_core::model::log::LogRecord;
+use otell_core::model::span::SpanRecord;
 use otell_core::query::{
-    MetricsListResponse, MetricsResponse, SearchResponse, SpanResponse, StatusResponse,
-    TraceListItem, TraceResponse,
+    FailedSpansResponse, IntegrityReport, LogsContextMeta, MetricsListResponse, MetricsResponse,
+    SamplingStatsResponse, SearchResponse, ServiceGraphResponse, SpanResponse, StatusResponse,
+    TraceListItem, TraceLogsResponse, TraceResponse,
 };
+use otell_ingest::raw::{DecodedFrame, RawFrame};
 use owo_colors::OwoColorize;
 
-pub fn print_search_human(v: &SearchResponse) {
-    for row in &v.records {
+/// `merge_attrs` prints each record's attributes as sorted `key=value` lines
+/// under the body instead of the inline `attrs_text` blob.
+pub fn print_search_human(v: &SearchResponse, merge_attrs: bool, show_delay: bool) {
+    let separators = v
+        .context_groups
+        .iter()
+        .flatten()
+        .skip(1)
+        .map(|g| g.start)
+        .collect::<HashSet<_>>();
+    for (idx, row) in v.records.iter().enumerate() {
+        if separators.contains(&idx) {
+            println!("--");
+        }
         let ts = row.ts.to_rfc3339_opts(SecondsFormat::Millis, true);
         let trace = row.trace_id.clone().unwrap_or_else(|| "-".to_string());
         let span = row.span_id.clone().unwrap_or_else(|| "-".to_string());
+        let delay = if show_delay {
+            match row.observed_delay_ms() {
+                Some(ms) => format!(" delay={ms}ms"),
+                None => " delay=-".to_string(),
+            }
+        } else {
+            String::new()
+        };
+        if merge_attrs {
+            println!(
+                "{ts} {} {} trace={} span={}{delay} | {}",
+                row.service.cyan(),
+                severity_colored(row.severity),
+                trace,
+                span,
+                row.body
+            );
+            for line in attr_lines(&row.attrs_json) {
+                println!("    {line}");
+            }
+            continue;
+        }
         println!(
-            "{ts} {} {} trace={} span={} | {} {}",
+            "{ts} {} {} trace={} span={}{delay} | {} {}",
             row.service.cyan(),
             severity_colored(row.severity),
             trace,
@@ -30,6 +69,29 @@ pub fn print_search_human(v: &SearchResponse) {
         println!("stats.by_service={:?}", stats.by_service);
         println!("stats.by_severity={:?}", stats.by_severity);
     }
+    if let Some(rollup) = &v.rollup {
+        for entry in rollup {
+            println!("rollup {} count={}", entry.period, entry.count);
+        }
+    }
+}
+
+/// Attributes from an `attrs_json` object as `key=value` strings sorted by
+/// key. String values are printed bare; anything else as JSON.
+pub fn attr_lines(attrs_json: &str) -> Vec<String> {
+    let Ok(serde_json::Value::Object(map)) = serde_json::from_str::<serde_json::Value>(attrs_json)
+    else {
+        return Vec::new();
+    };
+    let mut attrs = map.into_iter().collect::<Vec<_>>();
+    attrs.sort_by(|a, b| a.0.cmp(&b.0));
+    attrs
+        .into_iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(s) => format!("{key}={s}"),
+            other => format!("{key}={other}"),
+        })
+        .collect()
 }
 
 pub fn print_trace_human(v: &TraceResponse) {
@@ -38,7 +100,7 @@ pub fn print_trace_human(v: &TraceResponse) {
     } else {
         0
     };
-    let errors = v.spans.iter().filter(|s| s.status == "ERROR").count();
+    let errors = v.spans.iter().filter(|s| s.status != "OK").count();
     println!(
         "TRACE {} duration={}ms spans={} errors={}",
         v.trace_id.bright_white(),
@@ -47,12 +109,25 @@ pub fn print_trace_human(v: &TraceResponse) {
         errors
     );
 
-    print_span_tree(&v.spans);
-    println!(
+    print_span_tree(&v.spans, 0);
+    print_trace_logs(&v.context, &v.logs);
+}
+
+pub fn print_trace_logs_human(v: &TraceLogsResponse) {
+    println!("TRACE {} logs={}", v.trace_id.bright_white(), v.logs.len());
+    print_trace_logs(&v.context, &v.logs);
+}
+
+fn print_trace_logs(context: &LogsContextMeta, logs: &[LogRecord]) {
+    print!(
         "logs={} limit={} truncated={}",
-        v.context.policy, v.context.limit, v.context.truncated
+        context.policy, context.limit, context.truncated
     );
-    for log in &v.logs {
+    match context.next_offset {
+        Some(next) => println!(" next_offset={next}"),
+        None => println!(),
+    }
+    for log in logs {
         println!(
             "{} {} {} | {}",
             log.ts.to_rfc3339_opts(SecondsFormat::Millis, true),
@@ -63,6 +138,71 @@ pub fn print_trace_human(v: &TraceResponse) {
     }
 }
 
+/// Line budget for `otell trace --compact`, including the header.
+pub const COMPACT_TRACE_MAX_LINES: usize = 12;
+
+/// The TL;DR of a trace: a header with the root and total duration, then one
+/// line per error or slow span (at least a quarter of the trace) with its
+/// offset from the trace start. Errors are picked before slow spans, and
+/// anything past `max_lines` is folded into a trailing `... N more` line.
+pub fn compact_trace_lines(v: &TraceResponse, max_lines: usize) -> Vec<String> {
+    let Some(start) = v.spans.iter().map(|s| s.start_ts).min() else {
+        return vec![format!("TRACE {} (no spans)", v.trace_id)];
+    };
+    let end = v.spans.iter().map(|s| s.end_ts).max().unwrap_or(start);
+    let total_ms = (end - start).num_milliseconds();
+    let root = v
+        .spans
+        .iter()
+        .find(|s| s.parent_span_id.is_none())
+        .or_else(|| v.spans.iter().min_by_key(|s| s.start_ts));
+    let errors = v.spans.iter().filter(|s| s.status != "OK").count();
+
+    let mut lines = vec![format!(
+        "TRACE {} {}ms spans={} errors={} root=\"{}\"",
+        v.trace_id,
+        total_ms,
+        v.spans.len(),
+        errors,
+        root.map(|r| r.name.as_str()).unwrap_or("-")
+    )];
+
+    let is_root = |s: &SpanRecord| root.is_some_and(|r| r.span_id == s.span_id);
+    let mut picked = v
+        .spans
+        .iter()
+        .filter(|s| !is_root(s) && (s.status != "OK" || s.duration_ms() * 4 >= total_ms))
+        .collect::<Vec<_>>();
+    picked.sort_by_key(|s| (s.status == "OK", std::cmp::Reverse(s.duration_ms())));
+
+    // Keep one line for the overflow note when not everything fits, unless
+    // that would push out an error span.
+    let budget = max_lines.saturating_sub(1);
+    let shown = if picked.len() > budget {
+        budget.saturating_sub(1).max(errors.min(budget))
+    } else {
+        picked.len()
+    };
+    let omitted = v.spans.len() - usize::from(root.is_some()) - shown;
+    picked.truncate(shown);
+    picked.sort_by_key(|s| s.start_ts);
+
+    for span in picked {
+        lines.push(format!(
+            "  +{}ms {}ms {} {} {}",
+            (span.start_ts - start).num_milliseconds(),
+            span.duration_ms(),
+            span.status,
+            span.service,
+            span.name
+        ));
+    }
+    if omitted > 0 && lines.len() < max_lines {
+        lines.push(format!("  ... {omitted} more spans"));
+    }
+    lines
+}
+
 pub fn print_span_human(v: &SpanResponse) {
     println!(
         "SPAN {} service={} name={} status={} duration={}ms",
@@ -88,18 +228,228 @@ pub fn print_span_human(v: &SpanResponse) {
     }
 }
 
-pub fn print_traces_human(v: &[TraceListItem]) {
-    for item in v {
+/// Pads each cell to its column's widest value so `key=value` tables line up.
+/// With `max_width`, the last cell is cut (ending in `…`) to keep lines
+/// within it, but never below a few characters.
+pub fn align_columns(rows: &[Vec<String>], max_width: Option<usize>) -> Vec<String> {
+    const MIN_LAST_CELL: usize = 8;
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            widths[idx] = widths[idx].max(cell.chars().count());
+        }
+    }
+
+    rows.iter()
+        .map(|row| {
+            let mut line = String::new();
+            for (idx, cell) in row.iter().enumerate() {
+                if idx + 1 == row.len() {
+                    let used = line.chars().count();
+                    let budget = max_width
+                        .map(|w| w.saturating_sub(used).max(MIN_LAST_CELL))
+                        .unwrap_or(usize::MAX);
+                    if cell.chars().count() > budget {
+                        line.extend(cell.chars().take(budget - 1));
+                        line.push('…');
+                    } else {
+                        line.push_str(cell);
+                    }
+                } else {
+                    line.push_str(&format!("{cell:<width$} ", width = widths[idx]));
+                }
+            }
+            line
+        })
+        .collect()
+}
+
+/// Line width for aligned tables: `OTELL_OUTPUT_WIDTH` (`0` = unlimited),
+/// else the terminal's width when stdout is one. `COLUMNS` is a shell
+/// variable that usually isn't exported, so the terminal is asked directly.
+fn output_width() -> Option<usize> {
+    if let Ok(raw) = std::env::var("OTELL_OUTPUT_WIDTH") {
+        return raw.trim().parse::<usize>().ok().filter(|w| *w > 0);
+    }
+    let stdout = std::io::stdout();
+    if !stdout.is_terminal() {
+        return None;
+    }
+    rustix::termios::tcgetwinsize(&stdout)
+        .ok()
+        .map(|size| usize::from(size.ws_col))
+        .filter(|w| *w > 0)
+}
+
+fn trace_list_lines(v: &[TraceListItem]) -> Vec<String> {
+    let rows = v
+        .iter()
+        .map(|item| {
+            vec![
+                format!("trace={}", item.trace_id),
+                format!("duration={}ms", item.duration_ms),
+                format!("spans={}", item.span_count),
+                format!("status={}", item.status),
+                format!("root=\"{}\"", item.root_name),
+            ]
+        })
+        .collect::<Vec<_>>();
+    align_columns(&rows, output_width())
+}
+
+pub fn print_traces_human(v: &[TraceListItem], commands: bool) {
+    for (item, line) in v.iter().zip(trace_list_lines(v)) {
+        println!("{line}");
+        if commands {
+            println!("  next={}", trace_command(&item.trace_id));
+        }
+    }
+    println!("-- {} traces --", v.len());
+}
+
+/// Upper bound on traces expanded by `otell traces --tree`; the rest are
+/// listed without their span tree.
+pub const TRACES_TREE_MAX: usize = 10;
+
+/// Prints the trace list with each trace's span tree under its line. `trees`
+/// holds the expanded traces, in list order, for a prefix of `v`.
+pub fn print_traces_tree_human(v: &[TraceListItem], trees: &[TraceResponse], commands: bool) {
+    for (idx, (item, line)) in v.iter().zip(trace_list_lines(v)).enumerate() {
+        println!("{line}");
+        if commands {
+            println!("  next={}", trace_command(&item.trace_id));
+        }
+        if let Some(trace) = trees.get(idx) {
+            print_span_tree(&trace.spans, 1);
+        }
+    }
+    if trees.len() < v.len() {
+        println!(
+            "-- {} traces ({} expanded, narrow with --limit) --",
+            v.len(),
+            trees.len()
+        );
+    } else {
+        println!("-- {} traces --", v.len());
+    }
+}
+
+pub fn print_spans_human(v: &[SpanRecord]) {
+    for span in v {
         println!(
-            "trace={} duration={}ms spans={} status={} root=\"{}\"",
-            item.trace_id, item.duration_ms, item.span_count, item.status, item.root_name
+            "{} trace={} span={} {} {} ({}ms) {}",
+            span.start_ts.to_rfc3339_opts(SecondsFormat::Millis, true),
+            span.trace_id,
+            span.span_id,
+            span.service.cyan(),
+            span.name,
+            span.duration_ms(),
+            status_colored(&span.status)
         );
     }
-    println!("-- {} traces --", v.len());
+    println!("-- {} spans --", v.len());
+}
+
+pub fn print_failed_spans_human(v: &FailedSpansResponse) {
+    for g in &v.groups {
+        let message = g
+            .status_message
+            .as_deref()
+            .map(|m| format!(" message={m:?}"))
+            .unwrap_or_default();
+        println!(
+            "{:>5} {} {}{} last={} trace={}",
+            g.count,
+            g.service.cyan(),
+            g.name,
+            message.red(),
+            g.last_ts.to_rfc3339_opts(SecondsFormat::Millis, true),
+            g.example_trace_id
+        );
+    }
+    println!(
+        "-- {} failing operations ({} spans) --",
+        v.groups.len(),
+        v.total_spans
+    );
+}
+
+pub fn print_replay_frame_human(
+    idx: usize,
+    frame: &RawFrame,
+    decoded: &Result<DecodedFrame, String>,
+) {
+    let head = format!(
+        "frame={idx} ts={} signal={} content_type={} bytes={}",
+        frame.ts.to_rfc3339_opts(SecondsFormat::Millis, true),
+        frame.signal.as_str(),
+        frame.content_type,
+        frame.payload.len()
+    );
+    let records = match decoded {
+        Ok(records) => records,
+        Err(err) => {
+            println!("{head} {}", format!("error={err}").red());
+            return;
+        }
+    };
+    println!("{head} records={}", records.len());
+    match records {
+        DecodedFrame::Logs(logs) => {
+            for log in logs {
+                println!(
+                    "  {} {} {} | {} {}",
+                    log.ts.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    log.service.cyan(),
+                    severity_colored(log.severity),
+                    log.body,
+                    log.attrs_text
+                );
+            }
+        }
+        DecodedFrame::Spans(spans) => {
+            for span in spans {
+                println!(
+                    "  trace={} span={} {} {} ({}ms) {}",
+                    span.trace_id,
+                    span.span_id,
+                    span.service.cyan(),
+                    span.name,
+                    span.duration_ms(),
+                    status_colored(&span.status)
+                );
+            }
+        }
+        DecodedFrame::Metrics(points) => {
+            for point in points {
+                println!(
+                    "  {} {} {} value={} {}",
+                    point.ts.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    point.service.cyan(),
+                    point.name,
+                    point.value,
+                    point.kind
+                );
+            }
+        }
+    }
+}
+
+/// Ready-to-run follow-up command for drilling into a listed trace.
+pub fn trace_command(trace_id: &str) -> String {
+    format!("otell trace {trace_id} --logs bounded")
 }
 
 pub fn print_metrics_human(v: &MetricsResponse) {
-    println!("points={}", v.points.len());
+    let mut header = format!("points={}", v.points.len());
+    if !v.unit.is_empty() {
+        header.push_str(&format!(" unit={}", v.unit));
+    }
+    if !v.kind.is_empty() {
+        header.push_str(&format!(" kind={}", v.kind));
+    }
+    println!("{header}");
     for s in &v.series {
         println!("group={} value={}", s.group, s.value);
     }
@@ -110,9 +460,79 @@ pub fn print_metrics_human(v: &MetricsResponse) {
     );
 }
 
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Renders `values` as one block character each, scaled between the series'
+/// own min (`▁`) and max (`█`). A flat series renders at mid height; empty
+/// (or non-finite) buckets render as a blank.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    let finite = values.iter().flatten().copied().filter(|v| v.is_finite());
+    let min = finite.clone().fold(f64::INFINITY, f64::min);
+    let max = finite.fold(f64::NEG_INFINITY, f64::max);
+    let top = (SPARK_BLOCKS.len() - 1) as f64;
+    values
+        .iter()
+        .map(|v| match v {
+            Some(v) if v.is_finite() => {
+                if max <= min {
+                    SPARK_BLOCKS[SPARK_BLOCKS.len() / 2 - 1]
+                } else {
+                    SPARK_BLOCKS[((v - min) / (max - min) * top).round() as usize]
+                }
+            }
+            _ => ' ',
+        })
+        .collect()
+}
+
+pub fn print_metrics_sparklines(v: &MetricsResponse) {
+    let mut header = format!("points={}", v.points.len());
+    if !v.unit.is_empty() {
+        header.push_str(&format!(" unit={}", v.unit));
+    }
+    println!("{header}");
+    for s in &v.series {
+        let values = s.points.iter().map(|b| b.value).collect::<Vec<_>>();
+        let (lo, hi) = values
+            .iter()
+            .flatten()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(*v), hi.max(*v))
+            });
+        if values.is_empty() {
+            println!("group={} (no buckets)", s.group);
+        } else {
+            println!(
+                "group={} {} min={} max={} buckets={}",
+                s.group,
+                sparkline(&values),
+                lo,
+                hi,
+                values.len()
+            );
+        }
+    }
+    println!("-- {} series --", v.series.len());
+}
+
 pub fn print_metrics_list_human(v: &MetricsListResponse) {
-    for metric in &v.metrics {
-        println!("name={} count={}", metric.name, metric.count);
+    let rows = v
+        .metrics
+        .iter()
+        .map(|metric| {
+            let mut row = vec![
+                format!("name={}", metric.name),
+                format!("count={}", metric.count),
+                format!("cardinality={}", metric.cardinality),
+            ];
+            if !metric.services.is_empty() {
+                row.push(format!("services={}", metric.services.join(",")));
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    for line in align_columns(&rows, output_width()) {
+        println!("{line}");
     }
     println!("-- {} metric names --", v.metrics.len());
 }
@@ -124,6 +544,10 @@ pub fn print_status_human(v: &StatusResponse) {
         "logs={} spans={} metrics={}",
         v.logs_count, v.spans_count, v.metrics_count
     );
+    println!(
+        "pending logs={} spans={} metrics={} max_flush_lag_ms={}",
+        v.pending_logs, v.pending_spans, v.pending_metrics, v.max_flush_lag_ms
+    );
     if let Some(oldest) = v.oldest_ts {
         println!(
             "oldest={}",
@@ -138,6 +562,51 @@ pub fn print_status_human(v: &StatusResponse) {
     }
 }
 
+pub fn print_service_graph_human(v: &ServiceGraphResponse) {
+    for edge in &v.edges {
+        println!(
+            "{} -> {} calls={} errors={}",
+            edge.caller, edge.callee, edge.calls, edge.errors
+        );
+    }
+    println!("-- {} edges --", v.edges.len());
+}
+
+pub fn print_sampling_human(v: &SamplingStatsResponse) {
+    for s in &v.services {
+        let total = s.sampled + s.unsampled + s.unknown;
+        let pct = if total == 0 {
+            0.0
+        } else {
+            s.sampled as f64 * 100.0 / total as f64
+        };
+        println!(
+            "{} sampled={} unsampled={} unknown={} sampled_pct={pct:.1}",
+            s.service.cyan(),
+            s.sampled,
+            s.unsampled,
+            s.unknown
+        );
+    }
+    println!("-- {} services --", v.services.len());
+}
+
+pub fn print_check_human(v: &IntegrityReport) {
+    for check in &v.checks {
+        let verdict = match (check.problems, check.advisory) {
+            (0, _) => "ok".green().to_string(),
+            (_, true) => "warn".yellow().to_string(),
+            (_, false) => "FAIL".red().to_string(),
+        };
+        print!("{verdict} {} problems={}", check.name, check.problems);
+        match &check.detail {
+            Some(detail) => println!(" | {detail}"),
+            None => println!(),
+        }
+    }
+    println!("-- integrity {} --", if v.ok { "ok" } else { "FAILED" });
+}
+
 fn severity_label(level: i32) -> &'static str {
     match level {
         1..=4 => "TRACE",
@@ -160,7 +629,7 @@ fn severity_colored(level: i32) -> String {
     }
 }
 
-fn print_span_tree(spans: &[otell_core::model::span::SpanRecord]) {
+fn print_span_tree(spans: &[otell_core::model::span::SpanRecord], depth: usize) {
     let mut children: HashMap<Option<String>, Vec<&otell_core::model::span::SpanRecord>> =
         HashMap::new();
     for span in spans {
@@ -171,7 +640,7 @@ fn print_span_tree(spans: &[otell_core::model::span::SpanRecord]) {
     }
     if let Some(roots) = children.get(&None) {
         for root in roots {
-            print_node(root, &children, 0);
+            print_node(root, &children, depth);
         }
     }
 }
@@ -188,11 +657,7 @@ fn print_node(
         span.service.cyan(),
         span.name,
         span.duration_ms(),
-        if span.status == "ERROR" {
-            span.status.red().to_string()
-        } else {
-            span.status.green().to_string()
-        }
+        status_colored(&span.status)
     );
 
     if let Some(kids) = children.get(&Some(span.span_id.clone())) {
@@ -201,3 +666,165 @@ fn print_node(
         }
     }
 }
+
+/// Error spans keep their status message in place of `ERROR`, so anything
+/// but `OK` is an error.
+fn status_colored(status: &str) -> String {
+    if status == "OK" {
+        status.green().to_string()
+    } else {
+        status.red().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use otell_core::query::LogsContextMeta;
+
+    use super::*;
+
+    #[test]
+    fn align_columns_pads_cells_to_column_width() {
+        let rows = vec![
+            vec!["name=a".into(), "count=1".into(), "cardinality=1".into()],
+            vec![
+                "name=http.server.duration".into(),
+                "count=42".into(),
+                "cardinality=6".into(),
+            ],
+            vec![
+                "name=b".into(),
+                "count=100000".into(),
+                "cardinality=12".into(),
+            ],
+        ];
+        let lines = align_columns(&rows, None);
+        assert_eq!(
+            lines[0],
+            "name=a                    count=1      cardinality=1"
+        );
+        for key in ["count=", "cardinality="] {
+            let offsets = lines
+                .iter()
+                .map(|l| l.find(key).unwrap())
+                .collect::<Vec<_>>();
+            assert!(offsets.windows(2).all(|w| w[0] == w[1]), "{lines:#?}");
+        }
+        assert!(lines.iter().all(|l| !l.ends_with(' ')));
+    }
+
+    #[test]
+    fn align_columns_cuts_last_cell_to_width() {
+        let rows = vec![vec![
+            "trace=t1".into(),
+            "root=\"GET /a/very/long/route/name\"".into(),
+        ]];
+        let lines = align_columns(&rows, Some(24));
+        assert_eq!(lines[0], "trace=t1 root=\"GET /a/v…");
+        assert_eq!(lines[0].chars().count(), 24);
+
+        // Never squeezes the last cell below a readable minimum.
+        let lines = align_columns(&rows, Some(4));
+        assert_eq!(lines[0], "trace=t1 root=\"G…");
+    }
+
+    fn span(
+        id: &str,
+        parent: Option<&str>,
+        offset_ms: i64,
+        dur_ms: i64,
+        status: &str,
+    ) -> SpanRecord {
+        let base = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        SpanRecord {
+            trace_id: "t1".into(),
+            span_id: id.into(),
+            parent_span_id: parent.map(Into::into),
+            service: "api".into(),
+            name: id.into(),
+            start_ts: base + chrono::Duration::milliseconds(offset_ms),
+            end_ts: base + chrono::Duration::milliseconds(offset_ms + dur_ms),
+            status: status.into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        }
+    }
+
+    #[test]
+    fn attr_lines_are_sorted_by_key() {
+        let lines =
+            attr_lines(r#"{"peer":"redis:6379","attempt":3,"http.method":"GET","ok":false}"#);
+        assert_eq!(
+            lines,
+            vec![
+                "attempt=3",
+                "http.method=GET",
+                "ok=false",
+                "peer=redis:6379"
+            ]
+        );
+        assert!(attr_lines("not json").is_empty());
+    }
+
+    #[test]
+    fn sparkline_maps_range_onto_blocks() {
+        let spark =
+            |values: &[f64]| sparkline(&values.iter().copied().map(Some).collect::<Vec<_>>());
+        assert_eq!(spark(&[0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]), "▁▂▃▄▅▆▇█");
+        assert_eq!(spark(&[10.0, 20.0, 15.0]), "▁█▅");
+        assert_eq!(spark(&[3.0, 3.0]), "▄▄");
+        assert_eq!(spark(&[]), "");
+        assert_eq!(sparkline(&[Some(10.0), None, Some(20.0)]), "▁ █");
+        assert_eq!(sparkline(&[None, None]), "  ");
+    }
+
+    #[test]
+    fn compact_trace_keeps_errors_within_line_cap() {
+        let mut spans = vec![span("root", None, 0, 1000, "OK")];
+        for i in 0..40 {
+            spans.push(span(&format!("fast{i}"), Some("root"), i * 10, 5, "OK"));
+        }
+        spans.push(span("slow", Some("root"), 100, 600, "OK"));
+        spans.push(span("db.err", Some("root"), 900, 3, "ERROR"));
+        spans.push(span(
+            "cache.err",
+            Some("root"),
+            950,
+            2,
+            "connection refused",
+        ));
+        let trace = TraceResponse {
+            trace_id: "t1".into(),
+            spans,
+            logs: Vec::new(),
+            context: LogsContextMeta {
+                policy: "none".into(),
+                limit: 0,
+                truncated: false,
+                next_offset: None,
+            },
+        };
+
+        let lines = compact_trace_lines(&trace, COMPACT_TRACE_MAX_LINES);
+        assert!(lines.len() <= COMPACT_TRACE_MAX_LINES);
+        assert!(lines[0].contains("1000ms spans=44 errors=2 root=\"root\""));
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("+900ms 3ms ERROR api db.err"))
+        );
+        assert!(
+            lines
+                .iter()
+                .any(|l| l.contains("+950ms 2ms connection refused api cache.err"))
+        );
+        assert!(lines.iter().any(|l| l.contains("+100ms 600ms OK api slow")));
+        assert_eq!(lines.last().unwrap(), "  ... 40 more spans");
+
+        let tight = compact_trace_lines(&trace, 3);
+        assert_eq!(tight.len(), 3);
+        assert!(tight[1..].iter().any(|l| l.contains("db.err")));
+        assert!(tight[1..].iter().any(|l| l.contains("cache.err")));
+    }
+}
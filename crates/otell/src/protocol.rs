@@ -1,30 +1,53 @@
+use otell_core::model::span::SpanRecord;
 use otell_core::query::{
-    MetricsListRequest, MetricsListResponse, MetricsRequest, MetricsResponse, QueryHandle,
-    SearchRequest, SearchResponse, SpanRequest, SpanResponse, StatusResponse, TraceListItem,
-    TraceRequest, TraceResponse, TracesRequest,
+    FailedSpansRequest, FailedSpansResponse, IntegrityReport, MetricsListRequest,
+    MetricsListResponse, MetricsRequest, MetricsResponse, QueryHandle, SamplingStatsRequest,
+    SamplingStatsResponse, SearchRequest, SearchResponse, ServiceGraphRequest,
+    ServiceGraphResponse, SpanRequest, SpanResponse, SpansInWindowRequest, StatusResponse,
+    TraceListItem, TraceLogsRequest, TraceLogsResponse, TraceRequest, TraceResponse, TracesRequest,
 };
 use serde::{Deserialize, Serialize};
 
+/// First line a TCP client sends when the server has `OTELL_QUERY_TOKEN` set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthPreamble {
+    pub token: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ApiRequest {
     Search(SearchRequest),
     Trace(TraceRequest),
+    TraceLogs(TraceLogsRequest),
     Span(SpanRequest),
     Traces(TracesRequest),
+    SpansInWindow(SpansInWindowRequest),
+    FailedSpans(FailedSpansRequest),
     Metrics(MetricsRequest),
     MetricsList(MetricsListRequest),
+    ServiceGraph(ServiceGraphRequest),
+    SamplingStats(SamplingStatsRequest),
     ResolveHandle(QueryHandle),
     Status,
+    Check,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ApiResponse {
     Search(SearchResponse),
     Trace(TraceResponse),
+    TraceLogs(TraceLogsResponse),
     Span(SpanResponse),
     Traces(Vec<TraceListItem>),
+    SpansInWindow(Vec<SpanRecord>),
+    FailedSpans(FailedSpansResponse),
     Metrics(MetricsResponse),
     MetricsList(MetricsListResponse),
+    ServiceGraph(ServiceGraphResponse),
+    SamplingStats(SamplingStatsResponse),
     Status(StatusResponse),
+    Check(IntegrityReport),
+    /// Reply to a `count_only` traces or metrics-list request.
+    Count(usize),
     Error(String),
 }
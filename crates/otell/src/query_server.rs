@@ -1,30 +1,49 @@
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use anyhow::Context;
 use axum::extract::{Path, Query, State};
+use axum::http::{HeaderValue, Method, StatusCode, header};
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use base64::Engine;
-use otell_core::filter::Severity;
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use otell_core::filter::{Severity, SortOrder, TimeWindow};
 use otell_core::model::log::LogRecord;
 use otell_core::query::{
-    MetricsListRequest, MetricsRequest, QueryHandle, SearchRequest, SpanRequest, TraceRequest,
-    TracesRequest,
+    FailedSpansRequest, MetricsListRequest, MetricsRequest, QueryHandle, SamplingStatsRequest,
+    SearchRequest, ServiceGraphRequest, SpanRequest, SpansInWindowRequest, TraceLogsRequest,
+    TraceRequest, TracesRequest,
 };
-use regex::RegexBuilder;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use otell_core::time::parse_time_or_relative;
+use otell_ingest::limit::with_concurrency_limit;
+use regex::Regex;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::Semaphore;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::Level;
 
-use crate::protocol::{ApiRequest, ApiResponse};
+use crate::audit::QueryAudit;
+use crate::protocol::{ApiRequest, ApiResponse, AuthPreamble};
+
+/// How long a TCP client has to send its token line.
+const PREAMBLE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Longest accepted token line, newline included.
+const MAX_PREAMBLE_BYTES: u64 = 4096;
 
 pub async fn run_query_server(
     store: otell_store::Store,
+    audit: QueryAudit,
     uds_path: PathBuf,
     tcp_addr: SocketAddr,
+    max_concurrent_requests: usize,
+    token: Option<String>,
 ) -> anyhow::Result<()> {
     if let Some(parent) = uds_path.parent() {
         tokio::fs::create_dir_all(parent)
@@ -50,9 +69,29 @@ pub async fn run_query_server(
 
     tracing::info!(path = %uds_path.display(), "query UDS server listening");
     tracing::info!(addr = %tcp_addr, "query TCP server listening");
+    if token.is_none() && !tcp_addr.ip().is_loopback() {
+        tracing::warn!(addr = %tcp_addr, "query TCP server is reachable off-host without OTELL_QUERY_TOKEN");
+    }
+    if token.is_some() {
+        tracing::warn!(
+            "OTELL_QUERY_TOKEN protects the TCP query endpoint only; the HTTP query API stays unauthenticated"
+        );
+    }
 
-    let uds_task = tokio::spawn(run_uds_loop(uds_listener, store.clone()));
-    let tcp_task = tokio::spawn(run_tcp_loop(tcp_listener, store));
+    let limits = SocketLimits::new(max_concurrent_requests);
+    let uds_task = tokio::spawn(run_uds_loop(
+        uds_listener,
+        store.clone(),
+        audit.clone(),
+        limits.clone(),
+    ));
+    let tcp_task = tokio::spawn(run_tcp_loop(
+        tcp_listener,
+        store,
+        audit,
+        limits,
+        token.map(Arc::from),
+    ));
 
     tokio::select! {
         res = uds_task => {
@@ -66,73 +105,262 @@ pub async fn run_query_server(
     Ok(())
 }
 
+/// Budgets shared by the UDS and TCP listeners.
+#[derive(Clone)]
+struct SocketLimits {
+    /// Open connections, each with its own task; new ones beyond this are
+    /// turned away before a task is spawned.
+    connections: Arc<Semaphore>,
+    /// Requests being answered at once across all open connections.
+    requests: Arc<Semaphore>,
+}
+
+impl SocketLimits {
+    fn new(max: usize) -> Self {
+        Self {
+            connections: Arc::new(Semaphore::new(max)),
+            requests: Arc::new(Semaphore::new(max)),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct QueryState {
+    store: otell_store::Store,
+    audit: QueryAudit,
+    /// Open `/v1/tail` streams, kept apart from the request limit because a
+    /// stream holds its permit for as long as the client stays connected.
+    tails: Arc<Semaphore>,
+}
+
+impl QueryState {
+    fn handle(&self, req: ApiRequest) -> ApiResponse {
+        self.audit.record("http", &req);
+        handle_request(req, &self.store)
+    }
+}
+
 pub async fn run_query_http_server(
     store: otell_store::Store,
+    audit: QueryAudit,
     http_addr: SocketAddr,
+    max_concurrent_requests: usize,
+    cors_origins: Vec<String>,
 ) -> anyhow::Result<()> {
-    let app = Router::new()
+    let app = query_http_router(store, audit, max_concurrent_requests, &cors_origins)?;
+
+    let listener = tokio::net::TcpListener::bind(http_addr)
+        .await
+        .context("bind HTTP query listener")?;
+    tracing::info!(addr = %http_addr, "query HTTP server listening");
+    if !http_addr.ip().is_loopback() {
+        tracing::warn!(addr = %http_addr, "query HTTP server is reachable off-host and has no authentication");
+    }
+    axum::serve(listener, app)
+        .await
+        .context("run HTTP query server")
+}
+
+fn query_http_router(
+    store: otell_store::Store,
+    audit: QueryAudit,
+    max_concurrent_requests: usize,
+    cors_origins: &[String],
+) -> anyhow::Result<Router> {
+    let state = QueryState {
+        store,
+        audit,
+        tails: Arc::new(Semaphore::new(max_concurrent_requests)),
+    };
+    let requests = Router::new()
         .route("/v1/search", post(http_search))
         .route("/v1/trace", post(http_trace))
         .route("/v1/trace/{trace_id}", get(http_trace_get))
+        .route("/v1/trace-logs", post(http_trace_logs))
         .route("/v1/span", post(http_span))
         .route("/v1/traces", post(http_traces))
+        .route("/v1/spans", post(http_spans))
+        .route("/v1/failures", post(http_failures))
         .route("/v1/metrics", post(http_metrics))
         .route("/v1/metrics/list", post(http_metrics_list))
+        .route("/v1/graph", post(http_graph))
+        .route("/v1/sampling", post(http_sampling))
         .route("/v1/status", get(http_status))
+        .route("/v1/check", get(http_check))
+        .with_state(state.clone());
+    // Tail streams stay open, so they draw on `QueryState::tails` instead.
+    let tail = Router::new()
         .route("/v1/tail", get(http_tail))
+        .with_state(state);
+    let router = with_concurrency_limit(requests, max_concurrent_requests)
+        .merge(tail)
         .layer(
             TraceLayer::new_for_http()
                 .on_request(tower_http::trace::DefaultOnRequest::new().level(Level::INFO))
                 .on_response(tower_http::trace::DefaultOnResponse::new().level(Level::INFO)),
-        )
-        .with_state(store);
+        );
+    match query_cors_layer(cors_origins)? {
+        Some(cors) => Ok(router.layer(cors)),
+        None => Ok(router),
+    }
+}
 
-    let listener = tokio::net::TcpListener::bind(http_addr)
-        .await
-        .context("bind HTTP query listener")?;
-    tracing::info!(addr = %http_addr, "query HTTP server listening");
-    axum::serve(listener, app)
-        .await
-        .context("run HTTP query server")
+/// Cross-origin access for browser dashboards (`OTELL_QUERY_CORS_ORIGINS`).
+/// Unlike OTLP ingest, the query API is same-origin only unless origins are
+/// listed; `*` allows any origin.
+fn query_cors_layer(origins: &[String]) -> anyhow::Result<Option<CorsLayer>> {
+    if origins.is_empty() {
+        return Ok(None);
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        let values = origins
+            .iter()
+            .map(|o| HeaderValue::from_str(o).with_context(|| format!("bad CORS origin {o:?}")))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(values)
+    };
+    Ok(Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers([header::CONTENT_TYPE]),
+    ))
 }
 
-async fn run_uds_loop(listener: UnixListener, store: otell_store::Store) -> anyhow::Result<()> {
+async fn run_uds_loop(
+    listener: UnixListener,
+    store: otell_store::Store,
+    audit: QueryAudit,
+    limits: SocketLimits,
+) -> anyhow::Result<()> {
     loop {
-        let (stream, _) = listener.accept().await?;
+        let (mut stream, _) = listener.accept().await?;
+        let Ok(permit) = limits.connections.clone().try_acquire_owned() else {
+            tokio::spawn(async move { reject_busy(&mut stream).await });
+            continue;
+        };
         let store = store.clone();
+        let audit = audit.clone();
+        let requests = limits.requests.clone();
         tokio::spawn(async move {
-            if let Err(err) = handle_stream(BufReader::new(stream), store).await {
+            let _permit = permit;
+            if let Err(err) =
+                handle_stream(BufReader::new(stream), store, audit, requests, "uds", None).await
+            {
                 tracing::warn!(error = ?err, "uds client request failed");
             }
         });
     }
 }
 
-async fn run_tcp_loop(listener: TcpListener, store: otell_store::Store) -> anyhow::Result<()> {
+async fn run_tcp_loop(
+    listener: TcpListener,
+    store: otell_store::Store,
+    audit: QueryAudit,
+    limits: SocketLimits,
+    token: Option<Arc<str>>,
+) -> anyhow::Result<()> {
     loop {
-        let (stream, _) = listener.accept().await?;
+        let (mut stream, _) = listener.accept().await?;
+        let Ok(permit) = limits.connections.clone().try_acquire_owned() else {
+            tokio::spawn(async move { reject_busy(&mut stream).await });
+            continue;
+        };
         let store = store.clone();
+        let audit = audit.clone();
+        let requests = limits.requests.clone();
+        let token = token.clone();
         tokio::spawn(async move {
-            if let Err(err) = handle_stream(BufReader::new(stream), store).await {
+            let _permit = permit;
+            if let Err(err) =
+                handle_stream(BufReader::new(stream), store, audit, requests, "tcp", token).await
+            {
                 tracing::warn!(error = ?err, "tcp client request failed");
             }
         });
     }
 }
 
-async fn handle_stream<T>(mut stream: BufReader<T>, store: otell_store::Store) -> anyhow::Result<()>
+/// Answers a connection over the limit with one error line and closes it,
+/// so clients fail fast instead of hanging.
+async fn reject_busy<T>(stream: &mut T)
+where
+    T: tokio::io::AsyncWrite + Unpin,
+{
+    tracing::warn!("query server at connection limit; rejecting connection");
+    let busy = ApiResponse::Error("query server busy: too many open connections".to_string());
+    if let Ok(mut payload) = serde_json::to_vec(&busy) {
+        payload.push(b'\n');
+        let _ = stream.write_all(&payload).await;
+        let _ = stream.shutdown().await;
+    }
+}
+
+async fn handle_stream<T>(
+    mut stream: BufReader<T>,
+    store: otell_store::Store,
+    audit: QueryAudit,
+    limit: Arc<Semaphore>,
+    transport: &str,
+    token: Option<Arc<str>>,
+) -> anyhow::Result<()>
 where
     T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
 {
+    let expects_token = token.is_some();
+    if let Some(expected) = token {
+        let authorized = read_preamble(&mut stream).await.is_some_and(|line| {
+            serde_json::from_str::<AuthPreamble>(&line)
+                .is_ok_and(|p| token_matches(&p.token, &expected))
+        });
+        if !authorized {
+            tracing::warn!(
+                transport,
+                "rejecting query connection without a valid token"
+            );
+            let denied = ApiResponse::Error(
+                "unauthorized: this server requires OTELL_QUERY_TOKEN".to_string(),
+            );
+            let mut payload = serde_json::to_vec(&denied)?;
+            payload.push(b'\n');
+            stream.get_mut().write_all(&payload).await?;
+            stream.get_mut().shutdown().await?;
+            return Ok(());
+        }
+    }
+
+    let mut first_line = true;
     loop {
         let mut line = String::new();
         let n = stream.read_line(&mut line).await?;
         if n == 0 {
             break;
         }
+        // Clients with OTELL_QUERY_TOKEN set always send it, even to a
+        // server that doesn't require one.
+        if std::mem::take(&mut first_line)
+            && !expects_token
+            && serde_json::from_str::<AuthPreamble>(&line).is_ok()
+        {
+            continue;
+        }
 
         let req: ApiRequest = serde_json::from_str(&line)?;
-        let response = handle_request(req, &store);
+        audit.record(transport, &req);
+        // A request over the limit gets an error line so the client fails
+        // fast; the connection stays open for its next request.
+        let response = match limit.try_acquire() {
+            Ok(_permit) => handle_request(req, &store),
+            Err(_) => {
+                tracing::warn!(
+                    transport,
+                    "query server at concurrency limit; rejecting request"
+                );
+                ApiResponse::Error("query server busy: too many concurrent requests".to_string())
+            }
+        };
         let payload = serde_json::to_vec(&response)?;
         stream.get_mut().write_all(&payload).await?;
         stream.get_mut().write_all(b"\n").await?;
@@ -142,16 +370,52 @@ where
     Ok(())
 }
 
-pub fn handle_request(req: ApiRequest, store: &otell_store::Store) -> ApiResponse {
+/// Reads the token line with a deadline and a size cap, so a silent or
+/// oversized client can't hold a connection permit indefinitely.
+async fn read_preamble<T>(stream: &mut BufReader<T>) -> Option<String>
+where
+    T: tokio::io::AsyncRead + Unpin,
+{
+    let mut line = String::new();
+    let mut capped = (&mut *stream).take(MAX_PREAMBLE_BYTES);
+    match tokio::time::timeout(PREAMBLE_TIMEOUT, capped.read_line(&mut line)).await {
+        Ok(Ok(_)) if line.ends_with('\n') => Some(line),
+        _ => None,
+    }
+}
+
+/// Compares without an early exit so the time taken doesn't reveal how much
+/// of the token matched.
+fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+pub fn handle_request(mut req: ApiRequest, store: &otell_store::Store) -> ApiResponse {
+    expand_zero_limit(&mut req);
     let resp = match req {
         ApiRequest::Search(r) => store.search_logs(&r).map(ApiResponse::Search),
         ApiRequest::Trace(r) => store.get_trace(&r).map(ApiResponse::Trace),
+        ApiRequest::TraceLogs(r) => store.get_trace_logs(&r).map(ApiResponse::TraceLogs),
         ApiRequest::Span(r) => store.get_span(&r).map(ApiResponse::Span),
+        ApiRequest::Traces(r) if r.count_only => store.count_traces(&r).map(ApiResponse::Count),
         ApiRequest::Traces(r) => store.list_traces(&r).map(ApiResponse::Traces),
+        ApiRequest::SpansInWindow(r) => store.spans_in_window(&r).map(ApiResponse::SpansInWindow),
+        ApiRequest::FailedSpans(r) => store.failed_spans(&r).map(ApiResponse::FailedSpans),
         ApiRequest::Metrics(r) => store.query_metrics(&r).map(ApiResponse::Metrics),
+        ApiRequest::MetricsList(r) if r.count_only => {
+            store.count_metric_names(&r).map(ApiResponse::Count)
+        }
         ApiRequest::MetricsList(r) => store.list_metric_names(&r).map(ApiResponse::MetricsList),
+        ApiRequest::ServiceGraph(r) => store.service_graph(&r).map(ApiResponse::ServiceGraph),
+        ApiRequest::SamplingStats(r) => store.sampling_stats(&r).map(ApiResponse::SamplingStats),
         ApiRequest::ResolveHandle(handle) => resolve_handle(handle, store),
         ApiRequest::Status => store.status().map(ApiResponse::Status),
+        ApiRequest::Check => store.integrity_check().map(ApiResponse::Check),
     };
     match resp {
         Ok(value) => value,
@@ -159,6 +423,23 @@ pub fn handle_request(req: ApiRequest, store: &otell_store::Store) -> ApiRespons
     }
 }
 
+/// `limit: 0` means "no limit" for every list-style request, on every
+/// transport, rather than silently returning nothing.
+fn expand_zero_limit(req: &mut ApiRequest) {
+    let limit = match req {
+        ApiRequest::Search(r) => &mut r.limit,
+        ApiRequest::Traces(r) => &mut r.limit,
+        ApiRequest::SpansInWindow(r) => &mut r.limit,
+        ApiRequest::FailedSpans(r) => &mut r.limit,
+        ApiRequest::Metrics(r) => &mut r.limit,
+        ApiRequest::MetricsList(r) => &mut r.limit,
+        _ => return,
+    };
+    if *limit == 0 {
+        *limit = usize::MAX;
+    }
+}
+
 fn resolve_handle(
     handle: QueryHandle,
     store: &otell_store::Store,
@@ -172,74 +453,118 @@ fn resolve_handle(
 }
 
 async fn http_search(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<SearchRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(limit = req.limit, "http query search request");
-    Json(handle_request(ApiRequest::Search(req), &store))
+    Json(state.handle(ApiRequest::Search(req)))
 }
 
 async fn http_trace(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<TraceRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(trace_id = %req.trace_id, "http query trace request");
-    Json(handle_request(ApiRequest::Trace(req), &store))
+    Json(state.handle(ApiRequest::Trace(req)))
 }
 
 async fn http_trace_get(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Path(trace_id): Path<String>,
 ) -> Json<ApiResponse> {
     tracing::debug!(trace_id = %trace_id, "http query trace get request");
-    Json(handle_request(
-        ApiRequest::Trace(TraceRequest {
-            trace_id,
-            root_span_id: None,
-            logs: otell_core::query::LogContextMode::Bounded,
-        }),
-        &store,
-    ))
+    Json(state.handle(ApiRequest::Trace(TraceRequest {
+        trace_id,
+        root_span_id: None,
+        logs: otell_core::query::LogContextMode::Bounded,
+        logs_offset: 0,
+        logs_limit: None,
+    })))
+}
+
+async fn http_trace_logs(
+    State(state): State<QueryState>,
+    Json(req): Json<TraceLogsRequest>,
+) -> Json<ApiResponse> {
+    tracing::debug!(trace_id = %req.trace_id, "http query trace logs request");
+    Json(state.handle(ApiRequest::TraceLogs(req)))
 }
 
 async fn http_span(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<SpanRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(trace_id = %req.trace_id, span_id = %req.span_id, "http query span request");
-    Json(handle_request(ApiRequest::Span(req), &store))
+    Json(state.handle(ApiRequest::Span(req)))
 }
 
 async fn http_traces(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<TracesRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(limit = req.limit, "http query traces request");
-    Json(handle_request(ApiRequest::Traces(req), &store))
+    Json(state.handle(ApiRequest::Traces(req)))
+}
+
+async fn http_spans(
+    State(state): State<QueryState>,
+    Json(req): Json<SpansInWindowRequest>,
+) -> Json<ApiResponse> {
+    tracing::debug!(limit = req.limit, "http query spans in window request");
+    Json(state.handle(ApiRequest::SpansInWindow(req)))
+}
+
+async fn http_failures(
+    State(state): State<QueryState>,
+    Json(req): Json<FailedSpansRequest>,
+) -> Json<ApiResponse> {
+    tracing::debug!(limit = req.limit, "http query failed spans request");
+    Json(state.handle(ApiRequest::FailedSpans(req)))
 }
 
 async fn http_metrics(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<MetricsRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(name = %req.name, limit = req.limit, "http query metrics request");
-    Json(handle_request(ApiRequest::Metrics(req), &store))
+    Json(state.handle(ApiRequest::Metrics(req)))
 }
 
 async fn http_metrics_list(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Json(req): Json<MetricsListRequest>,
 ) -> Json<ApiResponse> {
     tracing::debug!(limit = req.limit, "http query metrics list request");
-    Json(handle_request(ApiRequest::MetricsList(req), &store))
+    Json(state.handle(ApiRequest::MetricsList(req)))
+}
+
+async fn http_graph(
+    State(state): State<QueryState>,
+    Json(req): Json<ServiceGraphRequest>,
+) -> Json<ApiResponse> {
+    tracing::debug!("http query service graph request");
+    Json(state.handle(ApiRequest::ServiceGraph(req)))
+}
+
+async fn http_sampling(
+    State(state): State<QueryState>,
+    Json(req): Json<SamplingStatsRequest>,
+) -> Json<ApiResponse> {
+    tracing::debug!("http query sampling stats request");
+    Json(state.handle(ApiRequest::SamplingStats(req)))
 }
 
-async fn http_status(State(store): State<otell_store::Store>) -> Json<ApiResponse> {
+async fn http_status(State(state): State<QueryState>) -> Json<ApiResponse> {
     tracing::debug!("http query status request");
-    Json(handle_request(ApiRequest::Status, &store))
+    Json(state.handle(ApiRequest::Status))
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+async fn http_check(State(state): State<QueryState>) -> Json<ApiResponse> {
+    tracing::debug!("http query integrity check request");
+    Json(state.handle(ApiRequest::Check))
+}
+
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
 struct TailQuery {
     pattern: Option<String>,
     fixed: Option<bool>,
@@ -248,42 +573,189 @@ struct TailQuery {
     trace_id: Option<String>,
     span_id: Option<String>,
     severity: Option<String>,
+    since: Option<String>,
+    until: Option<String>,
 }
 
+/// Upper bound on records replayed from the store before a tail goes live.
+const TAIL_BACKLOG_LIMIT: usize = 10_000;
+
 async fn http_tail(
-    State(store): State<otell_store::Store>,
+    State(state): State<QueryState>,
     Query(query): Query<TailQuery>,
-) -> Sse<impl futures::Stream<Item = std::result::Result<Event, std::convert::Infallible>>> {
+) -> Result<
+    Sse<impl futures::Stream<Item = std::result::Result<Event, std::convert::Infallible>>>,
+    (StatusCode, String),
+> {
+    let permit = state.tails.try_acquire_owned().map_err(|_| {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "query server busy: too many tail streams".to_string(),
+        )
+    })?;
     tracing::info!(?query, "http query tail stream opened");
-    let mut rx = store.subscribe_logs();
-    let stream = async_stream::stream! {
+    state.audit.record_tail("http", &query);
+    let store = state.store;
+    let window = tail_window(&query).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let regex = tail_regex(&query, store.regex_size_limit())
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    // Subscribe before reading the backlog so nothing ingested in between is lost.
+    let rx = store.subscribe_logs();
+    let backlog = if window.since.is_some() || window.until.is_some() {
+        tail_backlog(&store, &query, &window)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        Vec::new()
+    };
+    let follow = window.until.is_none_or(|until| until > Utc::now());
+
+    let stream = tail_stream(rx, backlog, follow, query, regex, window).map(move |item| {
+        // The stream owns the permit, so it is released when the client goes away.
+        let _permit = &permit;
+        let event = match item {
+            TailItem::Record(record) => {
+                Event::default().data(serde_json::to_string(&record).unwrap_or_default())
+            }
+            TailItem::Gap(skipped) => Event::default()
+                .event("gap")
+                .data(serde_json::json!({ "skipped": skipped }).to_string()),
+        };
+        Ok(event)
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+#[derive(Debug)]
+enum TailItem {
+    Record(LogRecord),
+    /// The subscriber fell behind the broadcast buffer and this many records were dropped.
+    Gap(u64),
+}
+
+fn tail_stream(
+    mut rx: tokio::sync::broadcast::Receiver<LogRecord>,
+    backlog: Vec<LogRecord>,
+    follow: bool,
+    query: TailQuery,
+    regex: Option<Regex>,
+    window: TimeWindow,
+) -> impl futures::Stream<Item = TailItem> {
+    // Records ingested between subscribing and reading the backlog arrive
+    // live as well; each replayed record suppresses one live copy. Nothing
+    // newer than the backlog can be a replay, so the set is dropped once such
+    // a record arrives.
+    let newest_replayed = backlog.iter().map(|l| l.ts).max();
+    let mut replayed: HashMap<(DateTime<Utc>, String, Option<String>), usize> = HashMap::new();
+    for l in &backlog {
+        *replayed
+            .entry((l.ts, l.body.clone(), l.span_id.clone()))
+            .or_default() += 1;
+    }
+
+    async_stream::stream! {
+        for record in backlog {
+            yield TailItem::Record(record);
+        }
+        if !follow {
+            return;
+        }
         loop {
             match rx.recv().await {
                 Ok(record) => {
-                    if !matches_tail_query(&record, &query) {
+                    if !matches_tail_query(&record, &query, regex.as_ref(), &window) {
                         continue;
                     }
-                    if let Ok(data) = serde_json::to_string(&record) {
-                        yield Ok(Event::default().data(data));
+                    if newest_replayed.is_some_and(|newest| record.ts > newest) {
+                        replayed = HashMap::new();
+                    }
+                    let key = (record.ts, record.body.clone(), record.span_id.clone());
+                    if let Some(count) = replayed.get_mut(&key) {
+                        *count -= 1;
+                        if *count == 0 {
+                            replayed.remove(&key);
+                        }
+                        continue;
                     }
+                    yield TailItem::Record(record);
                 }
-                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
-                    tracing::warn!("tail stream lagged; dropping stale events");
-                    continue;
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "tail stream lagged; dropped records");
+                    yield TailItem::Gap(skipped);
                 }
                 Err(tokio::sync::broadcast::error::RecvError::Closed) => {
                     break;
                 }
             }
         }
+    }
+}
+
+fn tail_window(query: &TailQuery) -> otell_core::Result<TimeWindow> {
+    Ok(TimeWindow {
+        since: query
+            .since
+            .as_deref()
+            .map(parse_time_or_relative)
+            .transpose()?,
+        until: query
+            .until
+            .as_deref()
+            .map(parse_time_or_relative)
+            .transpose()?,
+    })
+}
+
+fn tail_backlog(
+    store: &otell_store::Store,
+    query: &TailQuery,
+    window: &TimeWindow,
+) -> otell_core::Result<Vec<LogRecord>> {
+    let req = SearchRequest {
+        pattern: query.pattern.clone(),
+        fixed: query.fixed.unwrap_or(false),
+        ignore_case: query.ignore_case.unwrap_or(false),
+        service: query.service.clone(),
+        trace_id: query.trace_id.clone(),
+        span_id: query.span_id.clone(),
+        severity_gte: query.severity.as_deref().and_then(|s| s.parse().ok()),
+        window: window.clone(),
+        sort: SortOrder::TsDesc,
+        limit: TAIL_BACKLOG_LIMIT,
+        ..SearchRequest::default()
     };
+    let mut records = store.search_logs(&req)?.records;
+    records.reverse();
+    Ok(records)
+}
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+/// Compiled once per stream; `None` for fixed-string or pattern-less tails.
+fn tail_regex(query: &TailQuery, size_limit: usize) -> otell_core::Result<Option<Regex>> {
+    match &query.pattern {
+        Some(pattern) if !query.fixed.unwrap_or(false) => otell_store::query::compile_pattern(
+            pattern,
+            query.ignore_case.unwrap_or(false),
+            size_limit,
+        )
+        .map(Some),
+        _ => Ok(None),
+    }
 }
 
-fn matches_tail_query(record: &LogRecord, query: &TailQuery) -> bool {
+fn matches_tail_query(
+    record: &LogRecord,
+    query: &TailQuery,
+    regex: Option<&Regex>,
+    window: &TimeWindow,
+) -> bool {
+    if window.since.is_some_and(|since| record.ts < since)
+        || window.until.is_some_and(|until| record.ts > until)
+    {
+        return false;
+    }
     if let Some(service) = &query.service
-        && &record.service != service
+        && !(record.service == *service
+            || query.ignore_case.unwrap_or(false) && record.service.eq_ignore_ascii_case(service))
     {
         return false;
     }
@@ -321,14 +793,425 @@ fn matches_tail_query(record: &LogRecord, query: &TailQuery) -> bool {
             };
             return haystack.contains(&needle);
         }
-        let regex = RegexBuilder::new(pattern)
-            .case_insensitive(query.ignore_case.unwrap_or(false))
-            .build();
-        if let Ok(regex) = regex {
-            return regex.is_match(&record.body);
-        }
-        return false;
+        return regex.is_some_and(|regex| regex.is_match(&record.body));
     }
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use otell_core::model::span::SpanRecord;
+
+    use super::*;
+
+    fn log(ts: chrono::DateTime<Utc>, body: &str) -> LogRecord {
+        LogRecord {
+            ts,
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        }
+    }
+
+    #[test]
+    fn tail_backlog_respects_since() {
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let now = Utc::now();
+        store
+            .insert_logs(&[
+                log(now - Duration::hours(2), "old"),
+                log(now - Duration::minutes(2), "recent"),
+            ])
+            .unwrap();
+
+        let query = TailQuery {
+            since: Some("5m".into()),
+            ..TailQuery::default()
+        };
+        let window = tail_window(&query).unwrap();
+        let backlog = tail_backlog(&store, &query, &window).unwrap();
+        assert_eq!(backlog.len(), 1);
+        assert_eq!(backlog[0].body, "recent");
+        assert!(matches_tail_query(&backlog[0], &query, None, &window));
+        assert!(!matches_tail_query(
+            &log(now - Duration::hours(2), "old"),
+            &query,
+            None,
+            &window
+        ));
+    }
+
+    #[test]
+    fn tail_regex_respects_size_limit() {
+        let query = |pattern: &str| TailQuery {
+            pattern: Some(pattern.into()),
+            ..TailQuery::default()
+        };
+        let err = tail_regex(&query(r"\w{1000}"), 10_000).unwrap_err();
+        assert!(err.to_string().contains("regex pattern too large"));
+
+        let ok = query("time.*out");
+        let regex = tail_regex(&ok, 10_000).unwrap();
+        let window = TimeWindow::all();
+        let record = log(Utc::now(), "connection reset");
+        assert!(!matches_tail_query(&record, &ok, regex.as_ref(), &window));
+        let record = log(Utc::now(), "timeout");
+        assert!(matches_tail_query(&record, &ok, regex.as_ref(), &window));
+    }
+
+    #[test]
+    fn zero_limit_returns_every_match() {
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let now = Utc::now();
+        let logs = (0..3)
+            .map(|i| log(now, &format!("line {i}")))
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+        let spans = (0..3)
+            .map(|i| SpanRecord {
+                trace_id: format!("t{i}"),
+                span_id: format!("s{i}"),
+                parent_span_id: None,
+                service: "api".into(),
+                name: "GET /".into(),
+                start_ts: now,
+                end_ts: now + Duration::milliseconds(5),
+                status: "OK".into(),
+                attrs_json: "{}".into(),
+                events_json: "[]".into(),
+            })
+            .collect::<Vec<_>>();
+        store.insert_spans(&spans).unwrap();
+
+        let search = handle_request(
+            ApiRequest::Search(SearchRequest {
+                limit: 0,
+                ..SearchRequest::default()
+            }),
+            &store,
+        );
+        assert!(matches!(search, ApiResponse::Search(r) if r.returned == 3));
+
+        let traces = handle_request(
+            ApiRequest::Traces(TracesRequest {
+                service: None,
+                status: None,
+                window: TimeWindow::all(),
+                sort: SortOrder::DurationDesc,
+                limit: 0,
+                count_only: false,
+                attr_filters: Vec::new(),
+            }),
+            &store,
+        );
+        assert!(matches!(traces, ApiResponse::Traces(t) if t.len() == 3));
+    }
+
+    #[tokio::test]
+    async fn tail_stream_reports_lag_gap() {
+        let store = otell_store::Store::open_in_memory()
+            .unwrap()
+            .with_tail_buffer(2);
+        let rx = store.subscribe_logs();
+        let now = Utc::now();
+        let logs = (0..5)
+            .map(|i| log(now, &format!("line {i}")))
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+
+        let stream = tail_stream(
+            rx,
+            Vec::new(),
+            true,
+            TailQuery::default(),
+            None,
+            TimeWindow::all(),
+        );
+        let items = stream.take(3).collect::<Vec<_>>().await;
+        assert!(matches!(items[0], TailItem::Gap(3)));
+        assert!(matches!(&items[1], TailItem::Record(r) if r.body == "line 3"));
+        assert!(matches!(&items[2], TailItem::Record(r) if r.body == "line 4"));
+    }
+
+    #[tokio::test]
+    async fn tail_stream_skips_each_replayed_record_once() {
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let rx = store.subscribe_logs();
+        let now = Utc::now();
+        // The first copy was read into the backlog; the second is a new record
+        // that happens to be identical.
+        let repeated = log(now, "retrying");
+        store
+            .insert_logs(&[repeated.clone(), repeated.clone(), log(now, "done")])
+            .unwrap();
+
+        let stream = tail_stream(
+            rx,
+            vec![repeated],
+            true,
+            TailQuery::default(),
+            None,
+            TimeWindow::all(),
+        );
+        let items = stream.take(3).collect::<Vec<_>>().await;
+        let bodies = items
+            .iter()
+            .map(|item| match item {
+                TailItem::Record(r) => r.body.as_str(),
+                TailItem::Gap(_) => "gap",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["retrying", "retrying", "done"]);
+    }
+
+    #[tokio::test]
+    async fn search_over_uds_writes_audit_line() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("audit/queries.jsonl");
+        let audit = QueryAudit::open(&path).unwrap();
+        let store = otell_store::Store::open_in_memory().unwrap();
+
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(handle_stream(
+            BufReader::new(server),
+            store,
+            audit,
+            Arc::new(Semaphore::new(1)),
+            "uds",
+            None,
+        ));
+        let mut client = BufReader::new(client);
+        let req = ApiRequest::Search(SearchRequest {
+            pattern: Some("timeout".into()),
+            ..SearchRequest::default()
+        });
+        let mut line = serde_json::to_vec(&req).unwrap();
+        line.push(b'\n');
+        client.get_mut().write_all(&line).await.unwrap();
+        let mut reply = String::new();
+        client.read_line(&mut reply).await.unwrap();
+        drop(client);
+        server.await.unwrap().unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let lines = written.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["transport"], "uds");
+        assert_eq!(entry["kind"], "Search");
+        assert_eq!(entry["request"]["Search"]["pattern"], "timeout");
+        assert!(entry["ts"].as_str().is_some());
+    }
+
+    #[tokio::test]
+    async fn tcp_connections_and_requests_over_limit_are_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let limits = SocketLimits {
+            connections: Arc::new(Semaphore::new(2)),
+            requests: Arc::new(Semaphore::new(1)),
+        };
+        tokio::spawn(run_tcp_loop(
+            listener,
+            store,
+            QueryAudit::disabled(),
+            limits.clone(),
+            None,
+        ));
+
+        async fn request(stream: &mut BufReader<tokio::net::TcpStream>) -> ApiResponse {
+            let mut line = serde_json::to_vec(&ApiRequest::Status).unwrap();
+            line.push(b'\n');
+            stream.get_mut().write_all(&line).await.unwrap();
+            let mut reply = String::new();
+            stream.read_line(&mut reply).await.unwrap();
+            serde_json::from_str(&reply).unwrap()
+        }
+
+        // Idle connections don't hold the only request permit.
+        let mut first = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+        assert!(matches!(request(&mut first).await, ApiResponse::Status(_)));
+        let mut second = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+        assert!(matches!(request(&mut second).await, ApiResponse::Status(_)));
+
+        // A third connection is over the connection limit and is closed.
+        let mut third = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+        let mut reply = String::new();
+        third.read_line(&mut reply).await.unwrap();
+        assert!(matches!(
+            serde_json::from_str::<ApiResponse>(&reply).unwrap(),
+            ApiResponse::Error(e) if e.contains("busy")
+        ));
+        reply.clear();
+        assert_eq!(third.read_line(&mut reply).await.unwrap(), 0, "closed");
+
+        // With the request permit taken by a request in flight, the next one
+        // is rejected but its connection stays usable.
+        let in_flight = limits.requests.clone().try_acquire_owned().unwrap();
+        assert!(matches!(
+            request(&mut second).await,
+            ApiResponse::Error(e) if e.contains("busy")
+        ));
+        drop(in_flight);
+        assert!(matches!(request(&mut second).await, ApiResponse::Status(_)));
+
+        drop(first);
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let mut fourth = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+        assert!(matches!(request(&mut fourth).await, ApiResponse::Status(_)));
+    }
+
+    #[tokio::test]
+    async fn http_tail_streams_use_their_own_limit() {
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let router = query_http_router(store, QueryAudit::disabled(), 1, &[]).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        let tail_url = format!("http://{addr}/v1/tail");
+        let tail = reqwest::get(&tail_url).await.unwrap();
+        assert_eq!(tail.status(), reqwest::StatusCode::OK);
+
+        // The open stream leaves the request limit free...
+        let status = reqwest::get(format!("http://{addr}/v1/status"))
+            .await
+            .unwrap();
+        assert_eq!(status.status(), reqwest::StatusCode::OK);
+
+        // ...but a second stream is over the tail limit.
+        let second = reqwest::get(&tail_url).await.unwrap();
+        assert_eq!(second.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        drop(tail);
+    }
+
+    #[tokio::test]
+    async fn tcp_requires_token_when_configured() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let store = otell_store::Store::open_in_memory().unwrap();
+        tokio::spawn(run_tcp_loop(
+            listener,
+            store,
+            QueryAudit::disabled(),
+            SocketLimits::new(8),
+            Some(Arc::from("s3cret")),
+        ));
+
+        async fn exchange(addr: SocketAddr, lines: &[String]) -> ApiResponse {
+            let mut stream = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+            for line in lines {
+                stream.get_mut().write_all(line.as_bytes()).await.unwrap();
+                stream.get_mut().write_all(b"\n").await.unwrap();
+            }
+            let mut reply = String::new();
+            stream.read_line(&mut reply).await.unwrap();
+            serde_json::from_str(&reply).unwrap()
+        }
+        let status = serde_json::to_string(&ApiRequest::Status).unwrap();
+        let preamble = |token: &str| {
+            serde_json::to_string(&AuthPreamble {
+                token: token.into(),
+            })
+            .unwrap()
+        };
+
+        let anonymous = exchange(addr, std::slice::from_ref(&status)).await;
+        assert!(matches!(anonymous, ApiResponse::Error(e) if e.contains("unauthorized")));
+
+        let wrong = exchange(addr, &[preamble("guess"), status.clone()]).await;
+        assert!(matches!(wrong, ApiResponse::Error(e) if e.contains("unauthorized")));
+
+        let right = exchange(addr, &[preamble("s3cret"), status.clone()]).await;
+        assert!(matches!(right, ApiResponse::Status(_)));
+
+        let oversized = preamble(&"s".repeat(MAX_PREAMBLE_BYTES as usize));
+        let oversized = exchange(addr, &[oversized, status]).await;
+        assert!(matches!(oversized, ApiResponse::Error(e) if e.contains("unauthorized")));
+    }
+
+    #[tokio::test]
+    async fn tcp_without_token_ignores_client_preamble() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let store = otell_store::Store::open_in_memory().unwrap();
+        tokio::spawn(run_tcp_loop(
+            listener,
+            store,
+            QueryAudit::disabled(),
+            SocketLimits::new(8),
+            None,
+        ));
+
+        let mut stream = BufReader::new(tokio::net::TcpStream::connect(addr).await.unwrap());
+        let mut lines = serde_json::to_vec(&AuthPreamble {
+            token: "local".into(),
+        })
+        .unwrap();
+        lines.push(b'\n');
+        lines.extend(serde_json::to_vec(&ApiRequest::Status).unwrap());
+        lines.push(b'\n');
+        stream.get_mut().write_all(&lines).await.unwrap();
+        let mut reply = String::new();
+        stream.read_line(&mut reply).await.unwrap();
+        assert!(matches!(
+            serde_json::from_str::<ApiResponse>(&reply).unwrap(),
+            ApiResponse::Status(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn query_http_cors_allows_listed_origins_only() {
+        let store = otell_store::Store::open_in_memory().unwrap();
+        let router = query_http_router(
+            store,
+            QueryAudit::disabled(),
+            8,
+            &["http://localhost:3000".to_string()],
+        )
+        .unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/v1/status");
+        let allowed = client
+            .get(&url)
+            .header("Origin", "http://localhost:3000")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            allowed
+                .headers()
+                .get("access-control-allow-origin")
+                .unwrap(),
+            "http://localhost:3000"
+        );
+
+        let other = client
+            .get(&url)
+            .header("Origin", "http://evil.example")
+            .send()
+            .await
+            .unwrap();
+        assert!(other.headers().get("access-control-allow-origin").is_none());
+
+        assert!(query_cors_layer(&[]).unwrap().is_none());
+        assert!(query_cors_layer(&["bad\norigin".to_string()]).is_err());
+    }
+}
@@ -20,6 +20,10 @@ use tracing_subscriber::{EnvFilter, Layer};
 #[derive(Debug, Clone)]
 pub struct TelemetryConfig {
     pub self_observe: SelfObserveMode,
+    /// Least severe event level written to the store when self-observing.
+    pub self_observe_min_level: tracing::Level,
+    /// Service name stamped on self-observed logs and spans.
+    pub self_observe_service: String,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -47,6 +51,31 @@ impl SelfObserveMode {
     }
 }
 
+/// Reads `OTELL_SELF_OBSERVE_LEVEL` (`trace`..`error`), defaulting to INFO.
+pub fn self_observe_level_from_env() -> otell_core::Result<tracing::Level> {
+    parse_self_observe_level(std::env::var("OTELL_SELF_OBSERVE_LEVEL").ok().as_deref())
+}
+
+fn parse_self_observe_level(value: Option<&str>) -> otell_core::Result<tracing::Level> {
+    match value.map(str::trim) {
+        None | Some("") => Ok(tracing::Level::INFO),
+        Some(v) => v.parse::<tracing::Level>().map_err(|_| {
+            otell_core::OtellError::Config(format!(
+                "bad OTELL_SELF_OBSERVE_LEVEL in environment: {v:?} (expected trace, debug, info, warn or error)"
+            ))
+        }),
+    }
+}
+
+/// Reads `OTELL_SELF_SERVICE`, defaulting to `otell`.
+pub fn self_observe_service_from_env() -> String {
+    std::env::var("OTELL_SELF_SERVICE")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "otell".to_string())
+}
+
 pub fn init_cli_tracing() {
     let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
     let _ = tracing_subscriber::fmt()
@@ -66,7 +95,9 @@ pub fn init_run_tracing(cfg: TelemetryConfig, store: Option<Store>) {
 
     let otlp_layer = build_otlp_layer();
     let store_layer = if cfg.self_observe.uses_store() {
-        store.map(SelfObserveLayer::new)
+        store.map(|store| {
+            SelfObserveLayer::new(store, cfg.self_observe_min_level, cfg.self_observe_service)
+        })
     } else {
         None
     };
@@ -133,16 +164,20 @@ struct SpanStart {
     parent_span_id: Option<String>,
     name: String,
     start_ts: chrono::DateTime<Utc>,
+    /// Most severe event level recorded while this span was current.
+    max_level: Option<tracing::Level>,
 }
 
 #[derive(Clone)]
 struct SelfObserveLayer {
     tx: mpsc::Sender<Signal>,
     spans: Arc<Mutex<HashMap<u64, SpanStart>>>,
+    min_level: tracing::Level,
+    service: Arc<str>,
 }
 
 impl SelfObserveLayer {
-    fn new(store: Store) -> Self {
+    fn new(store: Store, min_level: tracing::Level, service: String) -> Self {
         let (tx, mut rx) = mpsc::channel::<Signal>(8192);
         tokio::spawn(async move {
             let mut logs = Vec::new();
@@ -174,6 +209,8 @@ impl SelfObserveLayer {
         Self {
             tx,
             spans: Arc::new(Mutex::new(HashMap::new())),
+            min_level,
+            service: service.into(),
         }
     }
 }
@@ -187,6 +224,29 @@ where
             return;
         }
 
+        // Track severity on the current span before the floor below, so a
+        // span's status reflects its errors even when their logs are dropped.
+        let event_level = *event.metadata().level();
+        let mut trace_id = None;
+        let mut span_id = None;
+        if let Some(current) = ctx.lookup_current() {
+            let id = current.id().into_u64();
+            if let Ok(mut spans) = self.spans.lock()
+                && let Some(span) = spans.get_mut(&id)
+            {
+                // More verbose levels compare greater (TRACE > DEBUG > INFO).
+                if span.max_level.is_none_or(|max| event_level < max) {
+                    span.max_level = Some(event_level);
+                }
+                trace_id = Some(span.trace_id.clone());
+                span_id = Some(span.span_id.clone());
+            }
+        }
+
+        if event_level > self.min_level {
+            return;
+        }
+
         let mut visitor = FieldVisitor::default();
         event.record(&mut visitor);
 
@@ -198,16 +258,6 @@ where
             tracing::Level::ERROR => 17,
         };
 
-        let mut trace_id = None;
-        let mut span_id = None;
-        if let Some(current) = ctx.lookup_current() {
-            let id = current.id().into_u64();
-            if let Some(span) = self.spans.lock().ok().and_then(|m| m.get(&id).cloned()) {
-                trace_id = Some(span.trace_id);
-                span_id = Some(span.span_id);
-            }
-        }
-
         let attrs_json =
             serde_json::to_string(&visitor.fields).unwrap_or_else(|_| "{}".to_string());
         let attrs_text = visitor
@@ -223,13 +273,15 @@ where
 
         let _ = self.tx.try_send(Signal::Log(LogRecord {
             ts: Utc::now(),
-            service: "otell".to_string(),
+            service: self.service.to_string(),
             severity: level,
             trace_id,
             span_id,
             body,
             attrs_json,
             attrs_text,
+            flags: 0,
+            observed_ts: None,
         }));
     }
 
@@ -259,6 +311,7 @@ where
             parent_span_id,
             name,
             start_ts: Utc::now(),
+            max_level: None,
         };
 
         if let Ok(mut map) = self.spans.lock() {
@@ -280,11 +333,16 @@ where
             trace_id: start.trace_id,
             span_id: start.span_id,
             parent_span_id: start.parent_span_id,
-            service: "otell".to_string(),
+            service: self.service.to_string(),
             name: start.name,
             start_ts: start.start_ts,
             end_ts: Utc::now(),
-            status: "OK".to_string(),
+            status: if start.max_level == Some(tracing::Level::ERROR) {
+                "ERROR"
+            } else {
+                "OK"
+            }
+            .to_string(),
             attrs_json: "{}".to_string(),
             events_json: "[]".to_string(),
         }));
@@ -314,3 +372,151 @@ impl tracing::field::Visit for FieldVisitor {
             .insert(field.name().to_string(), value.to_string());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use otell_core::filter::{SortOrder, TimeWindow};
+    use otell_core::query::{SearchRequest, TracesRequest};
+
+    use super::*;
+
+    #[test]
+    fn self_observe_level_rejects_unknown_values() {
+        assert_eq!(
+            parse_self_observe_level(None).unwrap(),
+            tracing::Level::INFO
+        );
+        assert_eq!(
+            parse_self_observe_level(Some(" warn ")).unwrap(),
+            tracing::Level::WARN
+        );
+        let err = parse_self_observe_level(Some("verbose")).unwrap_err();
+        assert!(err.to_string().contains("OTELL_SELF_OBSERVE_LEVEL"));
+    }
+
+    #[tokio::test]
+    async fn self_observe_drops_events_below_floor() {
+        let store = Store::open_in_memory().unwrap();
+        let subscriber = tracing_subscriber::registry().with(SelfObserveLayer::new(
+            store.clone(),
+            tracing::Level::INFO,
+            "otell".into(),
+        ));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "otell::query_server", "noisy debug event");
+            tracing::info!(target: "otell::query_server", "kept info event");
+        });
+
+        // Dropping the subscriber closes the channel and flushes the writer task.
+        let mut bodies = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            bodies = store
+                .search_logs(&SearchRequest::default())
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|r| r.body)
+                .collect::<Vec<_>>();
+            if !bodies.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(bodies, vec!["kept info event".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn self_observe_marks_span_with_error_event_as_error() {
+        let store = Store::open_in_memory().unwrap();
+        let subscriber = tracing_subscriber::registry().with(SelfObserveLayer::new(
+            store.clone(),
+            tracing::Level::INFO,
+            "otell".into(),
+        ));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!(target: "otell::query_server", "failing_query").in_scope(|| {
+                tracing::error!(target: "otell::query_server", "store unavailable");
+            });
+            tracing::info_span!(target: "otell::query_server", "healthy_query").in_scope(|| {
+                tracing::warn!(target: "otell::query_server", "slow but fine");
+            });
+        });
+
+        let mut statuses = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            statuses = store
+                .list_traces(&TracesRequest {
+                    service: Some("otell".into()),
+                    status: None,
+                    window: TimeWindow::all(),
+                    sort: SortOrder::TsAsc,
+                    limit: 10,
+                    count_only: false,
+                    attr_filters: Vec::new(),
+                })
+                .unwrap()
+                .into_iter()
+                .map(|t| (t.root_name, t.status))
+                .collect::<Vec<_>>();
+            if statuses.len() == 2 {
+                break;
+            }
+        }
+        statuses.sort();
+        assert_eq!(
+            statuses,
+            vec![
+                ("failing_query".to_string(), "ERROR".to_string()),
+                ("healthy_query".to_string(), "OK".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn self_observe_records_use_configured_service() {
+        let store = Store::open_in_memory().unwrap();
+        let subscriber = tracing_subscriber::registry().with(SelfObserveLayer::new(
+            store.clone(),
+            tracing::Level::INFO,
+            "otell-staging".into(),
+        ));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info_span!(target: "otell::query_server", "query").in_scope(|| {
+                tracing::info!(target: "otell::query_server", "handled");
+            });
+        });
+
+        let traces_for = |service: &str| {
+            store
+                .list_traces(&TracesRequest {
+                    service: Some(service.into()),
+                    status: None,
+                    window: TimeWindow::all(),
+                    sort: SortOrder::TsAsc,
+                    limit: 10,
+                    count_only: false,
+                    attr_filters: Vec::new(),
+                })
+                .unwrap()
+                .len()
+        };
+        let mut log_services = Vec::new();
+        for _ in 0..50 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            log_services = store
+                .search_logs(&SearchRequest::default())
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|r| r.service)
+                .collect::<Vec<_>>();
+            if !log_services.is_empty() && traces_for("otell-staging") == 1 {
+                break;
+            }
+        }
+        assert_eq!(log_services, vec!["otell-staging".to_string()]);
+        assert_eq!(traces_for("otell-staging"), 1);
+        assert_eq!(traces_for("otell"), 0);
+    }
+}
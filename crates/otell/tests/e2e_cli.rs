@@ -6,10 +6,12 @@ use std::time::Duration;
 
 use opentelemetry_proto::tonic::collector::logs::v1::ExportLogsServiceRequest;
 use opentelemetry_proto::tonic::collector::logs::v1::logs_service_client::LogsServiceClient;
+use opentelemetry_proto::tonic::collector::trace::v1::ExportTraceServiceRequest;
 use opentelemetry_proto::tonic::common::v1::any_value::Value;
 use opentelemetry_proto::tonic::common::v1::{AnyValue, InstrumentationScope, KeyValue};
 use opentelemetry_proto::tonic::logs::v1::{LogRecord, ResourceLogs, ScopeLogs};
 use opentelemetry_proto::tonic::resource::v1::Resource;
+use opentelemetry_proto::tonic::trace::v1::{ResourceSpans, ScopeSpans, Span};
 use prost::Message;
 use serial_test::serial;
 
@@ -102,6 +104,67 @@ fn sample_logs_request(body: &str) -> ExportLogsServiceRequest {
     }
 }
 
+/// `count` traces, each a `checkout-N` root span with one `db-N` child.
+fn sample_traces_request(count: u8) -> ExportTraceServiceRequest {
+    let start = 1_700_000_000_000_000_000u64;
+    let spans = (0..count)
+        .flat_map(|i| {
+            let trace_id = vec![i + 1; 16];
+            [
+                Span {
+                    trace_id: trace_id.clone(),
+                    span_id: vec![i + 1; 8],
+                    name: format!("checkout-{i}"),
+                    start_time_unix_nano: start,
+                    end_time_unix_nano: start + 50_000_000,
+                    ..Default::default()
+                },
+                Span {
+                    trace_id,
+                    span_id: vec![i + 101; 8],
+                    parent_span_id: vec![i + 1; 8],
+                    name: format!("db-{i}"),
+                    start_time_unix_nano: start + 10_000_000,
+                    end_time_unix_nano: start + 20_000_000,
+                    ..Default::default()
+                },
+            ]
+        })
+        .collect();
+    ExportTraceServiceRequest {
+        resource_spans: vec![ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![KeyValue {
+                    key: "service.name".into(),
+                    value: Some(AnyValue {
+                        value: Some(Value::StringValue("api".into())),
+                    }),
+                }],
+                dropped_attributes_count: 0,
+                entity_refs: vec![],
+            }),
+            scope_spans: vec![ScopeSpans {
+                spans,
+                ..Default::default()
+            }],
+            schema_url: "".into(),
+        }],
+    }
+}
+
+fn strip_ansi(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
 async fn wait_http_ready(port: u16, child: &mut Child) {
     let client = reqwest::Client::new();
     let mut ready = false;
@@ -162,6 +225,129 @@ async fn e2e_http_ingest_and_tcp_search() {
     let _ = child.wait();
 }
 
+#[tokio::test]
+#[serial]
+async fn e2e_handle_flags_hide_or_embed_handles() {
+    let temp = tempfile::tempdir().unwrap();
+    let (mut child, _grpc_port, http_port, query_port, _query_http_port, _db, _uds) =
+        spawn_server(temp.path());
+
+    wait_http_ready(http_port, &mut child).await;
+
+    let req = sample_logs_request("timeout error");
+    let mut payload = Vec::new();
+    req.encode(&mut payload).unwrap();
+    let resp = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{http_port}/v1/logs"))
+        .body(payload)
+        .send()
+        .await
+        .unwrap();
+    assert!(resp.status().is_success());
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let quiet = Command::new(bin())
+        .arg("search")
+        .arg("timeout")
+        .arg("--no-handle")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&quiet.stdout);
+    assert!(stdout.contains("timeout error"));
+    assert!(!stdout.contains("handle="));
+
+    let quiet_env = Command::new(bin())
+        .arg("status")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .env("OTELL_NO_HANDLE", "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&quiet_env.stdout);
+    assert!(stdout.contains("logs=1"));
+    assert!(!stdout.contains("handle="));
+
+    let embedded = Command::new(bin())
+        .arg("--json")
+        .arg("--embed-handle")
+        .arg("search")
+        .arg("timeout")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&embedded.stdout).unwrap();
+    assert_eq!(value["Search"]["total_matches"], 1);
+    assert!(value["handle"].as_str().is_some_and(|h| !h.is_empty()));
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[tokio::test]
+#[serial]
+async fn e2e_query_file_runs_raw_search_request() {
+    let temp = tempfile::tempdir().unwrap();
+    let (mut child, _grpc_port, http_port, query_port, _query_http_port, _db, _uds) =
+        spawn_server(temp.path());
+
+    wait_http_ready(http_port, &mut child).await;
+
+    let req = sample_logs_request("raw query hit");
+    let mut payload = Vec::new();
+    req.encode(&mut payload).unwrap();
+    reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{http_port}/v1/logs"))
+        .body(payload)
+        .send()
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let request = serde_json::json!({
+        "Search": {
+            "pattern": "raw query",
+            "fixed": true,
+            "ignore_case": false,
+            "service": "api",
+            "trace_id": null,
+            "span_id": null,
+            "severity_gte": null,
+            "attr_filters": [],
+            "window": {"since": null, "until": null},
+            "sort": "ts_asc",
+            "limit": 10,
+            "context_lines": 0,
+            "context_seconds": null,
+            "count_only": false,
+            "include_stats": false
+        }
+    });
+    let file = temp.path().join("search.json");
+    std::fs::write(&file, request.to_string()).unwrap();
+
+    let output = Command::new(bin())
+        .arg("--json")
+        .arg("query")
+        .arg("--file")
+        .arg(&file)
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(value["Search"]["total_matches"], 1);
+    assert_eq!(value["Search"]["records"][0]["body"], "raw query hit");
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 #[tokio::test]
 #[serial]
 async fn e2e_search_count_stats_and_status_json_shape() {
@@ -196,6 +382,20 @@ async fn e2e_search_count_stats_and_status_json_shape() {
     assert!(search_out.contains("-- 1 matches (0 returned) --"));
     assert!(search_out.contains("stats.by_service"));
 
+    let records = Command::new(bin())
+        .arg("search")
+        .arg("count")
+        .arg("--records-only")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&records.stdout).unwrap();
+    let rows = value.as_array().unwrap();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["body"], "count me");
+    assert_eq!(rows[0]["service"], "api");
+
     let status = Command::new(bin())
         .arg("--json")
         .arg("status")
@@ -210,6 +410,61 @@ async fn e2e_search_count_stats_and_status_json_shape() {
     let _ = child.wait();
 }
 
+#[tokio::test]
+#[serial]
+async fn e2e_saved_search_matches_direct_search() {
+    let temp = tempfile::tempdir().unwrap();
+    let (mut child, _grpc_port, http_port, query_port, _query_http_port, _db, _uds) =
+        spawn_server(temp.path());
+
+    wait_http_ready(http_port, &mut child).await;
+
+    for body in ["saved hit one", "saved hit two", "unrelated"] {
+        let mut payload = Vec::new();
+        sample_logs_request(body).encode(&mut payload).unwrap();
+        reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{http_port}/v1/logs"))
+            .body(payload)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let config = temp.path().join("config/config.toml");
+    let search_flags = ["saved hit", "--fixed", "--service", "api", "--limit", "10"];
+    let save = Command::new(bin())
+        .env("OTELL_CONFIG", &config)
+        .arg("save")
+        .arg("hits")
+        .args(search_flags)
+        .output()
+        .unwrap();
+    assert!(save.status.success());
+    assert!(temp.path().join("config/queries.json").exists());
+
+    let run = |args: &[&str]| {
+        let output = Command::new(bin())
+            .env("OTELL_CONFIG", &config)
+            .arg("--json")
+            .args(args)
+            .arg("--addr")
+            .arg(format!("127.0.0.1:{query_port}"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    let saved = run(&["run-saved", "hits"]);
+    let direct = run(&[&["search"], &search_flags[..]].concat());
+    assert_eq!(saved["Search"]["total_matches"], 2);
+    assert_eq!(saved, direct);
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
 #[tokio::test]
 #[serial]
 async fn e2e_grpc_ingest_and_uds_search() {
@@ -258,19 +513,22 @@ async fn e2e_http_query_api() {
         spawn_server(temp.path());
     wait_http_ready(http_port, &mut child).await;
 
-    let req = sample_logs_request("via query http");
-    let mut payload = Vec::new();
-    req.encode(&mut payload).unwrap();
-    reqwest::Client::new()
-        .post(format!("http://127.0.0.1:{http_port}/v1/logs"))
-        .body(payload)
-        .send()
-        .await
-        .unwrap();
+    let mut later = sample_logs_request("via query http later");
+    later.resource_logs[0].scope_logs[0].log_records[0].time_unix_nano += 1_000_000_000;
+    for req in [sample_logs_request("via query http"), later] {
+        let mut payload = Vec::new();
+        req.encode(&mut payload).unwrap();
+        reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{http_port}/v1/logs"))
+            .body(payload)
+            .send()
+            .await
+            .unwrap();
+    }
 
     tokio::time::sleep(Duration::from_millis(300)).await;
 
-    let body = serde_json::json!({
+    let mut body = serde_json::json!({
         "pattern": "query",
         "fixed": false,
         "ignore_case": false,
@@ -288,16 +546,174 @@ async fn e2e_http_query_api() {
         "include_stats": false
     });
 
+    let search = |body: serde_json::Value| async move {
+        reqwest::Client::new()
+            .post(format!("http://127.0.0.1:{query_http_port}/v1/search"))
+            .json(&body)
+            .send()
+            .await
+            .unwrap()
+            .json::<serde_json::Value>()
+            .await
+            .unwrap()
+    };
+    let bodies = |resp: &serde_json::Value| {
+        resp["Search"]["records"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["body"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    let resp = search(body.clone()).await;
+    assert_eq!(
+        bodies(&resp),
+        vec!["via query http", "via query http later"],
+        "legacy variant name still accepted"
+    );
+
+    body["sort"] = "ts_desc".into();
+    let resp = search(body).await;
+    assert_eq!(
+        bodies(&resp),
+        vec!["via query http later", "via query http"]
+    );
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[tokio::test]
+#[serial]
+async fn e2e_traces_tree_expands_listed_traces_up_to_cap() {
+    let temp = tempfile::tempdir().unwrap();
+    let (mut child, _grpc_port, http_port, query_port, _query_http_port, _db, _uds) =
+        spawn_server(temp.path());
+    wait_http_ready(http_port, &mut child).await;
+
+    reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{http_port}/v1/traces"))
+        .body(sample_traces_request(12).encode_to_vec())
+        .send()
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let traces = |limit: &str| {
+        let output = Command::new(bin())
+            .arg("traces")
+            .arg("--tree")
+            .arg("--limit")
+            .arg(limit)
+            .arg("--addr")
+            .arg(format!("127.0.0.1:{query_port}"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        strip_ansi(&String::from_utf8_lossy(&output.stdout))
+    };
+
+    let out = traces("3");
+    let roots = out.lines().filter(|l| l.starts_with("  api checkout-"));
+    assert_eq!(roots.count(), 3, "{out}");
+    for line in out.lines().filter(|l| l.starts_with("trace=")) {
+        let root = line.split("root=\"").nth(1).unwrap().trim_end_matches('"');
+        let child = root.replace("checkout", "db");
+        assert!(out.contains(&format!("  api {root} (50ms)")), "{out}");
+        assert!(out.contains(&format!("    api {child} (10ms)")), "{out}");
+    }
+    assert!(out.contains("-- 3 traces --"));
+
+    let out = traces("50");
+    let children = out.lines().filter(|l| l.starts_with("    api db-"));
+    assert_eq!(children.count(), 10, "{out}");
+    assert!(out.contains("-- 12 traces (10 expanded, narrow with --limit) --"));
+
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+#[tokio::test]
+#[serial]
+async fn e2e_ndjson_ingest_spans_and_metrics() {
+    let temp = tempfile::tempdir().unwrap();
+    let (mut child, _grpc_port, http_port, query_port, _query_http_port, _db, _uds) =
+        spawn_server(temp.path());
+    wait_http_ready(http_port, &mut child).await;
+
+    let now = chrono::Utc::now();
+    let start = (now - chrono::Duration::seconds(5)).to_rfc3339();
+    let spans = format!(
+        "{{\"trace_id\":\"nd1\",\"span_id\":\"root\",\"service\":\"api\",\"name\":\"GET /jobs\",\"start_ts\":\"{start}\",\"duration_ms\":40,\"status\":\"ERROR\"}}\n\
+         {{\"trace_id\":\"nd1\",\"span_id\":\"child\",\"parent_span_id\":\"root\",\"service\":\"db\",\"name\":\"select\",\"start_ts\":\"{start}\",\"duration_ms\":10}}\n\
+         {{\"trace_id\":\"nd1\",\"service\":\"db\",\"name\":\"broken\"}}\n"
+    );
+    let resp = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{http_port}/v1/traces/ndjson"))
+        .body(spans)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let summary: serde_json::Value = resp.json().await.unwrap();
+    assert_eq!(summary["accepted"], 2);
+    assert_eq!(summary["rejected"], 1);
+    assert_eq!(summary["errors"][0]["line"], 3);
+
+    let ts = now.to_rfc3339();
+    let metrics = format!(
+        "{{\"name\":\"jobs.queued\",\"service\":\"worker\",\"value\":7,\"ts\":\"{ts}\"}}\n\
+         {{\"name\":\"jobs.queued\",\"service\":\"worker\",\"value\":9,\"ts\":\"{ts}\"}}\n"
+    );
     let resp = reqwest::Client::new()
-        .post(format!("http://127.0.0.1:{query_http_port}/v1/search"))
-        .json(&body)
+        .post(format!("http://127.0.0.1:{http_port}/v1/metrics/ndjson"))
+        .body(metrics)
         .send()
         .await
-        .unwrap()
-        .text()
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let resp = reqwest::Client::new()
+        .post(format!("http://127.0.0.1:{http_port}/v1/metrics/ndjson"))
+        .body("{\"name\":\"jobs.queued\"}\n")
+        .send()
         .await
         .unwrap();
-    assert!(resp.contains("via query http"));
+    assert_eq!(resp.status(), 400);
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let trace = Command::new(bin())
+        .arg("--json")
+        .arg("trace")
+        .arg("nd1")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    assert!(trace.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&trace.stdout).unwrap();
+    let spans = value["Trace"]["spans"].as_array().unwrap();
+    assert_eq!(spans.len(), 2);
+    let child_span = spans.iter().find(|s| s["span_id"] == "child").unwrap();
+    assert_eq!(child_span["parent_span_id"], "root");
+    assert_eq!(child_span["service"], "db");
+
+    let list = Command::new(bin())
+        .arg("--json")
+        .arg("metrics")
+        .arg("list")
+        .arg("--addr")
+        .arg(format!("127.0.0.1:{query_port}"))
+        .output()
+        .unwrap();
+    assert!(list.status.success());
+    let value: serde_json::Value = serde_json::from_slice(&list.stdout).unwrap();
+    let names = value["MetricsList"]["metrics"].as_array().unwrap();
+    let queued = names.iter().find(|m| m["name"] == "jobs.queued").unwrap();
+    assert_eq!(queued["count"], 2);
 
     let _ = child.kill();
     let _ = child.wait();
@@ -330,6 +746,67 @@ fn mcp_initialize_and_tools_list() {
     assert!(stdout.contains("tools"));
 }
 
+#[test]
+#[serial]
+fn mcp_batch_returns_response_array() {
+    let output = Command::new(bin())
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            let stdin = child.stdin.as_mut().unwrap();
+            stdin.write_all(
+                b"[{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"initialize\",\"params\":{}},{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"tools/list\",\"params\":{}}]\n",
+            )?;
+            drop(child.stdin.take());
+            child.wait_with_output()
+        })
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let response: serde_json::Value = serde_json::from_str(stdout.trim()).unwrap();
+    let items = response.as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["id"], 1);
+    assert!(items[0]["result"]["protocolVersion"].is_string());
+    assert_eq!(items[1]["id"], 2);
+    assert!(items[1]["result"]["tools"].is_array());
+}
+
+#[test]
+#[serial]
+fn mcp_notifications_get_no_response() {
+    let output = Command::new(bin())
+        .arg("mcp")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            let stdin = child.stdin.as_mut().unwrap();
+            stdin.write_all(b"{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}\n")?;
+            stdin.write_all(
+                b"[{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"}]\n",
+            )?;
+            stdin.write_all(
+                b"[{\"jsonrpc\":\"2.0\",\"method\":\"notifications/initialized\"},{\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"tools/list\",\"params\":{}}]\n",
+            )?;
+            drop(child.stdin.take());
+            child.wait_with_output()
+        })
+        .unwrap();
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{stdout}");
+    let response: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    let items = response.as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], 3);
+}
+
 #[test]
 #[serial]
 fn mcp_rejects_legacy_tool_shape() {
@@ -364,3 +841,50 @@ fn intro_without_server_guides_startup() {
     assert!(out.contains("The local `otell` collector is not running."));
     assert!(out.contains("otell run"));
 }
+
+#[test]
+#[serial]
+fn run_fails_fast_when_required_forward_target_is_unreachable() {
+    let temp = tempfile::tempdir().unwrap();
+    let mut child = Command::new(bin())
+        .arg("run")
+        .arg("--db-path")
+        .arg(temp.path().join("otell.duckdb"))
+        .arg("--otlp-grpc-addr")
+        .arg(format!("127.0.0.1:{}", free_port()))
+        .arg("--otlp-http-addr")
+        .arg(format!("127.0.0.1:{}", free_port()))
+        .arg("--query-tcp-addr")
+        .arg(format!("127.0.0.1:{}", free_port()))
+        .arg("--query-http-addr")
+        .arg(format!("127.0.0.1:{}", free_port()))
+        .arg("--query-uds-path")
+        .arg(temp.path().join("otell.sock"))
+        .env(
+            "OTELL_FORWARD_OTLP_ENDPOINT",
+            format!("http://127.0.0.1:{}", free_port()),
+        )
+        .env("OTELL_FORWARD_REQUIRE", "true")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let mut status = None;
+    for _ in 0..100 {
+        if let Some(s) = child.try_wait().unwrap() {
+            status = Some(s);
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    let Some(status) = status else {
+        let _ = child.kill();
+        panic!("otell kept running despite unreachable required forward target");
+    };
+    assert!(!status.success());
+
+    let output = child.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("OTELL_FORWARD_REQUIRE"));
+}
@@ -0,0 +1,171 @@
+use duckdb::Connection;
+use otell_core::error::Result;
+use otell_core::query::{IntegrityCheck, IntegrityReport};
+
+use crate::Store;
+
+/// Reads every column of each table so damaged pages fail the scan rather
+/// than hiding behind a metadata-only `COUNT(*)`.
+const TABLE_SCANS: &[(&str, &str)] = &[
+    (
+        "logs",
+        "SELECT COUNT(*), MAX(ts), SUM(severity),
+                SUM(LENGTH(service) + LENGTH(body) + LENGTH(attrs_json) + LENGTH(attrs_text)),
+                SUM(LENGTH(COALESCE(trace_id, '')) + LENGTH(COALESCE(span_id, '')))
+         FROM logs",
+    ),
+    (
+        "spans",
+        "SELECT COUNT(*), MAX(start_ts), MAX(end_ts),
+                SUM(LENGTH(trace_id) + LENGTH(span_id) + LENGTH(COALESCE(parent_span_id, ''))),
+                SUM(LENGTH(service) + LENGTH(name) + LENGTH(status)),
+                SUM(LENGTH(attrs_json) + LENGTH(events_json))
+         FROM spans",
+    ),
+    (
+        "metric_points",
+        "SELECT COUNT(*), MAX(ts), SUM(value),
+                SUM(LENGTH(name) + LENGTH(service) + LENGTH(attrs_json)),
+                SUM(LENGTH(unit) + LENGTH(kind))
+         FROM metric_points",
+    ),
+];
+
+const ADVISORY_CHECKS: &[(&str, &str)] = &[
+    (
+        "spans_end_before_start",
+        "SELECT COUNT(*) FROM spans WHERE end_ts < start_ts",
+    ),
+    (
+        "orphaned_spans",
+        "SELECT COUNT(*) FROM spans c
+         WHERE c.parent_span_id IS NOT NULL
+           AND NOT EXISTS (
+             SELECT 1 FROM spans p
+             WHERE p.trace_id = c.trace_id AND p.span_id = c.parent_span_id
+           )",
+    ),
+    (
+        "logs_missing_trace",
+        "SELECT COUNT(*) FROM logs l
+         WHERE l.trace_id IS NOT NULL
+           AND NOT EXISTS (SELECT 1 FROM spans s WHERE s.trace_id = l.trace_id)",
+    ),
+];
+
+impl Store {
+    /// Validates a suspect database. DuckDB has no `PRAGMA integrity_check`,
+    /// so each table is scanned in full; a failed scan fails the report.
+    /// Relational checks (orphaned spans, logs whose trace has no spans) are
+    /// advisory because partial exports produce them too.
+    pub fn integrity_check(&self) -> Result<IntegrityReport> {
+        let conn = self.conn();
+        let mut checks = Vec::new();
+
+        let unreadable = TABLE_SCANS
+            .iter()
+            .filter_map(|(table, sql)| {
+                conn.execute_batch(sql)
+                    .err()
+                    .map(|e| format!("{table}: {e}"))
+            })
+            .collect::<Vec<_>>();
+        checks.push(IntegrityCheck {
+            name: "tables_readable".to_string(),
+            problems: unreadable.len() as u64,
+            advisory: false,
+            detail: (!unreadable.is_empty()).then(|| unreadable.join("; ")),
+        });
+
+        for (name, sql) in ADVISORY_CHECKS {
+            checks.push(match count(&conn, sql) {
+                Ok(problems) => IntegrityCheck {
+                    name: name.to_string(),
+                    problems,
+                    advisory: true,
+                    detail: None,
+                },
+                // A query that cannot run is itself a hard failure.
+                Err(e) => IntegrityCheck {
+                    name: name.to_string(),
+                    problems: 1,
+                    advisory: false,
+                    detail: Some(e.to_string()),
+                },
+            });
+        }
+
+        let ok = checks.iter().all(|c| c.advisory || c.problems == 0);
+        Ok(IntegrityReport { ok, checks })
+    }
+}
+
+fn count(conn: &Connection, sql: &str) -> duckdb::Result<u64> {
+    conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+        .map(|v| v.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use otell_core::model::log::LogRecord;
+    use otell_core::model::span::SpanRecord;
+
+    use crate::Store;
+
+    fn span(span_id: &str, parent: Option<&str>) -> SpanRecord {
+        let start = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        SpanRecord {
+            trace_id: "t1".into(),
+            span_id: span_id.into(),
+            parent_span_id: parent.map(Into::into),
+            service: "api".into(),
+            name: span_id.into(),
+            start_ts: start,
+            end_ts: start + chrono::Duration::milliseconds(20),
+            status: "OK".into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        }
+    }
+
+    #[test]
+    fn healthy_store_passes_integrity_check() {
+        let store = Store::open_in_memory().unwrap();
+        store
+            .insert_spans(&[span("root", None), span("child", Some("root"))])
+            .unwrap();
+        store
+            .insert_logs(&[LogRecord {
+                ts: chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap(),
+                service: "api".into(),
+                severity: 9,
+                trace_id: Some("t1".into()),
+                span_id: Some("child".into()),
+                body: "handled".into(),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            }])
+            .unwrap();
+
+        let report = store.integrity_check().unwrap();
+        assert!(report.ok);
+        assert!(report.checks.iter().all(|c| c.problems == 0));
+
+        // An orphan is reported but stays advisory.
+        store
+            .insert_spans(&[span("stray", Some("missing"))])
+            .unwrap();
+        let report = store.integrity_check().unwrap();
+        assert!(report.ok);
+        let orphaned = report
+            .checks
+            .iter()
+            .find(|c| c.name == "orphaned_spans")
+            .unwrap();
+        assert_eq!(orphaned.problems, 1);
+        assert!(orphaned.advisory);
+    }
+}
@@ -4,11 +4,15 @@ use std::sync::{Arc, Mutex};
 
 use chrono::{DateTime, NaiveDateTime, Utc};
 use duckdb::Connection;
+pub use otell_core::config::{
+    DEFAULT_CONTEXT_SCAN_LIMIT, DEFAULT_REGEX_SIZE_LIMIT, DEFAULT_TAIL_BUFFER,
+};
 use otell_core::error::{OtellError, Result};
 use otell_core::model::log::LogRecord;
 use otell_core::query::StatusResponse;
 use tokio::sync::broadcast;
 
+use crate::pending::PendingWrites;
 use crate::schema::SCHEMA_SQL;
 
 #[derive(Clone)]
@@ -16,6 +20,9 @@ pub struct Store {
     conn: Arc<Mutex<Connection>>,
     db_path: String,
     log_tx: broadcast::Sender<LogRecord>,
+    pending: Arc<PendingWrites>,
+    regex_size_limit: usize,
+    context_scan_limit: usize,
 }
 
 impl Store {
@@ -32,12 +39,15 @@ impl Store {
         conn.execute_batch(SCHEMA_SQL)
             .map_err(|e| OtellError::Store(format!("failed to initialize schema: {e}")))?;
 
-        let (log_tx, _) = broadcast::channel(8192);
+        let (log_tx, _) = broadcast::channel(DEFAULT_TAIL_BUFFER);
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             db_path: path.display().to_string(),
             log_tx,
+            pending: Arc::default(),
+            regex_size_limit: DEFAULT_REGEX_SIZE_LIMIT,
+            context_scan_limit: DEFAULT_CONTEXT_SCAN_LIMIT,
         })
     }
 
@@ -46,16 +56,64 @@ impl Store {
             .map_err(|e| OtellError::Store(format!("failed to open in-memory db: {e}")))?;
         conn.execute_batch(SCHEMA_SQL)
             .map_err(|e| OtellError::Store(format!("failed to initialize schema: {e}")))?;
-        let (log_tx, _) = broadcast::channel(8192);
+        let (log_tx, _) = broadcast::channel(DEFAULT_TAIL_BUFFER);
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             db_path: ":memory:".to_string(),
             log_tx,
+            pending: Arc::default(),
+            regex_size_limit: DEFAULT_REGEX_SIZE_LIMIT,
+            context_scan_limit: DEFAULT_CONTEXT_SCAN_LIMIT,
         })
     }
 
+    /// Replaces the live log channel with one holding `capacity` records.
+    /// Call before cloning the store or subscribing; existing receivers keep
+    /// the old channel and stop receiving.
+    pub fn with_tail_buffer(mut self, capacity: usize) -> Self {
+        let (log_tx, _) = broadcast::channel(capacity.max(1));
+        self.log_tx = log_tx;
+        self
+    }
+
+    /// Caps the compiled size of user-supplied search patterns, so a
+    /// pathological regex fails to compile instead of using unbounded memory.
+    pub fn with_regex_size_limit(mut self, bytes: usize) -> Self {
+        self.regex_size_limit = bytes;
+        self
+    }
+
+    pub fn regex_size_limit(&self) -> usize {
+        self.regex_size_limit
+    }
+
+    /// Caps the logs loaded to expand time context; a search whose padded
+    /// span holds more fails instead of reading them all into memory.
+    pub fn with_context_scan_limit(mut self, rows: usize) -> Self {
+        self.context_scan_limit = rows;
+        self
+    }
+
+    pub(crate) fn context_scan_limit(&self) -> usize {
+        self.context_scan_limit
+    }
+
+    /// Accepted-but-unwritten record counts, updated by the ingest pipeline.
+    pub fn pending_writes(&self) -> Arc<PendingWrites> {
+        self.pending.clone()
+    }
+
     pub(crate) fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
-        self.conn.lock().expect("store mutex poisoned")
+        match self.conn.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => {
+                // A panic while holding the lock leaves the connection itself
+                // usable (open transactions roll back on drop), so keep serving.
+                tracing::warn!("store mutex was poisoned by a panicking thread; recovering");
+                self.conn.clear_poison();
+                poisoned.into_inner()
+            }
+        }
     }
 
     pub fn status(&self) -> Result<StatusResponse> {
@@ -82,6 +140,18 @@ impl Store {
             metrics_count,
             oldest_ts,
             newest_ts,
+            pending_logs: self.pending.logs.count(),
+            pending_spans: self.pending.spans.count(),
+            pending_metrics: self.pending.metrics.count(),
+            max_flush_lag_ms: [
+                &self.pending.logs,
+                &self.pending.spans,
+                &self.pending.metrics,
+            ]
+            .iter()
+            .map(|p| p.lag_ms())
+            .max()
+            .unwrap_or(0),
         })
     }
 
@@ -118,4 +188,101 @@ mod tests {
         assert_eq!(status.spans_count, 0);
         assert_eq!(status.metrics_count, 0);
     }
+
+    /// Tables as created by the first release, before any `ALTER TABLE`.
+    const BASELINE_SCHEMA_SQL: &str = r#"
+CREATE TABLE logs (
+  id BIGINT PRIMARY KEY,
+  ts TIMESTAMP NOT NULL,
+  service TEXT NOT NULL,
+  severity INTEGER NOT NULL,
+  trace_id TEXT,
+  span_id TEXT,
+  body TEXT NOT NULL,
+  attrs_json TEXT NOT NULL,
+  attrs_text TEXT NOT NULL
+);
+CREATE TABLE metric_points (
+  id BIGINT PRIMARY KEY,
+  ts TIMESTAMP NOT NULL,
+  name TEXT NOT NULL,
+  service TEXT NOT NULL,
+  value DOUBLE NOT NULL,
+  attrs_json TEXT NOT NULL
+);
+INSERT INTO logs VALUES (1, '2026-02-01 00:00:00', 'api', 9, '0af7651916cd43dd8448eb211c80319c', NULL, 'legacy', '{}', '');
+INSERT INTO metric_points VALUES (1, '2026-02-01 00:00:00', 'queue.depth', 'worker', 3.0, '{}');
+"#;
+
+    #[test]
+    fn opens_database_created_with_baseline_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("otell.duckdb");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(BASELINE_SCHEMA_SQL)
+            .unwrap();
+
+        let store = Store::open(&path).unwrap();
+        let status = store.status().unwrap();
+        assert_eq!((status.logs_count, status.metrics_count), (1, 1));
+
+        let logs = store
+            .search_logs(&otell_core::query::SearchRequest {
+                pattern: Some("legacy".into()),
+                ..otell_core::query::SearchRequest::default()
+            })
+            .unwrap();
+        assert_eq!(logs.records.len(), 1);
+        assert_eq!(logs.records[0].observed_ts, None);
+
+        // Legacy rows have no flags: neither sampled nor unsampled.
+        for sampled in [true, false] {
+            let matched = store
+                .search_logs(&otell_core::query::SearchRequest {
+                    sampled: Some(sampled),
+                    ..otell_core::query::SearchRequest::default()
+                })
+                .unwrap();
+            assert_eq!(matched.total_matches, 0);
+        }
+        let sampling = store
+            .sampling_stats(&otell_core::query::SamplingStatsRequest {
+                window: otell_core::filter::TimeWindow::all(),
+                service: None,
+            })
+            .unwrap();
+        assert_eq!(sampling.services[0].unknown, 1);
+
+        let metrics = store
+            .query_metrics(&otell_core::query::MetricsRequest {
+                name: "queue.depth".into(),
+                service: None,
+                window: otell_core::filter::TimeWindow::all(),
+                group_by: None,
+                agg: None,
+                limit: 10,
+                bucket_secs: None,
+            })
+            .unwrap();
+        assert_eq!(metrics.points.len(), 1);
+        assert_eq!(metrics.points[0].kind, "");
+    }
+
+    #[test]
+    fn poisoned_connection_lock_recovers() {
+        let store = Store::open_in_memory().unwrap();
+        let poisoner = store.clone();
+        let joined = std::thread::spawn(move || {
+            let _guard = poisoner.conn();
+            panic!("simulated panic while holding the store lock");
+        })
+        .join();
+        assert!(joined.is_err());
+        assert!(store.conn.is_poisoned());
+
+        let status = store.status().unwrap();
+        assert_eq!(status.logs_count, 0);
+        assert!(!store.conn.is_poisoned());
+    }
 }
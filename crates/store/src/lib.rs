@@ -1,4 +1,6 @@
+pub mod check;
 pub mod db;
+pub mod pending;
 pub mod query;
 pub mod retention;
 pub mod schema;
@@ -0,0 +1,115 @@
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+use chrono::Utc;
+
+/// Records the ingest pipeline has accepted but not yet written to the
+/// store, per signal. `otell status` reports these so users can tell how
+/// far its counts trail what was sent.
+#[derive(Debug, Default)]
+pub struct PendingWrites {
+    pub logs: PendingSignal,
+    pub spans: PendingSignal,
+    pub metrics: PendingSignal,
+}
+
+#[derive(Debug, Default)]
+pub struct PendingSignal {
+    count: AtomicU64,
+    /// Unix millis when the oldest pending record was accepted; 0 when idle.
+    oldest_ms: AtomicI64,
+}
+
+impl PendingSignal {
+    pub fn accepted(&self, n: usize) {
+        self.accepted_at(n, Utc::now().timestamp_millis());
+    }
+
+    fn accepted_at(&self, n: usize, now_ms: i64) {
+        if n == 0 {
+            return;
+        }
+        self.count.fetch_add(n as u64, Ordering::Relaxed);
+        let _ = self
+            .oldest_ms
+            .compare_exchange(0, now_ms, Ordering::Relaxed, Ordering::Relaxed);
+    }
+
+    /// Called after `n` records were written (or given up on). Whatever is
+    /// still pending was accepted before now, so restarting the clock here
+    /// under-reports its age by at most one flush.
+    pub fn flushed(&self, n: usize) {
+        self.flushed_at(n, Utc::now().timestamp_millis());
+    }
+
+    fn flushed_at(&self, n: usize, now_ms: i64) {
+        let n = n as u64;
+        let prev = self
+            .count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |c| {
+                Some(c.saturating_sub(n))
+            })
+            .unwrap_or(0);
+        let next = if prev <= n { 0 } else { now_ms };
+        self.oldest_ms.store(next, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn lag_ms(&self) -> u64 {
+        self.lag_ms_at(Utc::now().timestamp_millis())
+    }
+
+    fn lag_ms_at(&self, now_ms: i64) -> u64 {
+        match self.oldest_ms.load(Ordering::Relaxed) {
+            0 => 0,
+            oldest => (now_ms - oldest).max(0) as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lag_tracks_oldest_pending_record() {
+        let pending = PendingSignal::default();
+        assert_eq!(pending.lag_ms_at(1_000), 0);
+
+        pending.accepted_at(2, 1_000);
+        pending.accepted_at(1, 1_200);
+        assert_eq!(pending.count(), 3);
+        assert_eq!(
+            pending.lag_ms_at(1_250),
+            250,
+            "measured from the first accept"
+        );
+
+        // A partial flush restarts the clock for what is left.
+        pending.flushed_at(2, 1_300);
+        assert_eq!(pending.count(), 1);
+        assert_eq!(pending.lag_ms_at(1_350), 50);
+
+        pending.flushed_at(1, 1_400);
+        assert_eq!(pending.count(), 0);
+        assert_eq!(pending.lag_ms_at(2_000), 0);
+    }
+
+    #[test]
+    fn over_flushing_saturates_at_zero() {
+        let pending = PendingSignal::default();
+        pending.accepted_at(0, 1_000);
+        assert_eq!(
+            pending.lag_ms_at(2_000),
+            0,
+            "empty accepts don't start the clock"
+        );
+
+        pending.accepted_at(1, 1_000);
+        pending.flushed_at(5, 1_100);
+        assert_eq!(pending.count(), 0);
+        assert_eq!(pending.lag_ms_at(2_000), 0);
+    }
+}
@@ -1,29 +1,36 @@
 use std::cmp::Reverse;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use chrono::{DateTime, Duration, NaiveDateTime, Utc};
 use duckdb::{params, params_from_iter};
 use otell_core::error::{OtellError, Result};
-use otell_core::filter::SortOrder;
+use otell_core::filter::{AttrFilter, SortOrder, TimeWindow};
 use otell_core::model::log::LogRecord;
 use otell_core::model::metric::MetricPoint;
 use otell_core::model::span::SpanRecord;
 use otell_core::query::{
-    LogContextMode, LogsContextMeta, MetricNameItem, MetricSeries, MetricsListRequest,
-    MetricsListResponse, MetricsRequest, MetricsResponse, SearchRequest, SearchResponse,
-    SearchStats, SpanRequest, SpanResponse, TraceListItem, TraceRequest, TraceResponse,
-    TracesRequest,
+    ContextGroup, FailedSpanGroup, FailedSpansRequest, FailedSpansResponse, LogContextMode,
+    LogsContextMeta, METRIC_BUCKETS_MAX, METRIC_SERVICES_MAX, MetricBucket, MetricNameItem,
+    MetricSeries, MetricsListRequest, MetricsListResponse, MetricsListSort, MetricsRequest,
+    MetricsResponse, RollupEntry, RollupUnit, SamplingStatsRequest, SamplingStatsResponse,
+    SearchRequest, SearchResponse, SearchStats, ServiceEdge, ServiceGraphRequest,
+    ServiceGraphResponse, ServiceSampling, SpanRequest, SpanResponse, SpansInWindowRequest,
+    TraceListItem, TraceLogsRequest, TraceLogsResponse, TraceRequest, TraceResponse, TracesRequest,
 };
-use regex::RegexBuilder;
+use regex::{Regex, RegexBuilder};
 
 use crate::Store;
 
 impl Store {
     pub fn search_logs(&self, req: &SearchRequest) -> Result<SearchResponse> {
         let candidates = self.fetch_logs_candidates(req)?;
-        let filtered = apply_pattern(candidates, req)?;
+        let mut filtered = apply_pattern(candidates, req, self.regex_size_limit())?;
+        if matches!(req.sort, SortOrder::Relevance) {
+            sort_by_relevance(&mut filtered, req.relevance_half_life_secs);
+        }
         let total_matches = filtered.len();
         let stats = req.include_stats.then(|| compute_search_stats(&filtered));
+        let rollup = req.rollup.map(|unit| compute_rollup(&filtered, unit));
 
         if req.count_only {
             return Ok(SearchResponse {
@@ -31,15 +38,23 @@ impl Store {
                 returned: 0,
                 records: Vec::new(),
                 stats,
+                rollup,
+                context_groups: None,
             });
         }
 
         let mut selected = filtered.into_iter().take(req.limit).collect::<Vec<_>>();
+        let mut context_groups = None;
         if req.context_lines > 0 {
-            selected = self.expand_with_context(&selected, req.context_lines)?;
+            let (expanded, groups) =
+                self.expand_with_context(&selected, req.context_lines, &req.window)?;
+            selected = expanded;
+            context_groups = Some(groups);
         }
         if let Some(seconds) = req.context_seconds {
-            selected = self.expand_with_time_context(&selected, seconds)?;
+            selected = self.expand_with_time_context(&selected, seconds, &req.window)?;
+            // Time context reshapes the list, so line-context groups no longer apply.
+            context_groups = None;
         }
 
         Ok(SearchResponse {
@@ -47,6 +62,8 @@ impl Store {
             returned: selected.len(),
             records: selected,
             stats,
+            rollup,
+            context_groups,
         })
     }
 
@@ -58,15 +75,34 @@ impl Store {
             spans
         };
 
+        let mut next_offset = None;
         let logs = match req.logs {
             LogContextMode::None => Vec::new(),
-            LogContextMode::All => self.fetch_logs_for_trace(&req.trace_id, usize::MAX)?,
+            // A zero page size means no paging, like `limit: 0` elsewhere; a
+            // zero-row page would hand back the same `next_offset` forever.
+            LogContextMode::All => match req.logs_limit.filter(|l| *l > 0) {
+                Some(page) => {
+                    // One extra row tells us whether another page exists.
+                    let mut logs = self.fetch_logs_for_trace_page(
+                        &req.trace_id,
+                        req.logs_offset,
+                        page.saturating_add(1),
+                    )?;
+                    if logs.len() > page {
+                        logs.truncate(page);
+                        next_offset = Some(req.logs_offset + page);
+                    }
+                    logs
+                }
+                None => self.fetch_logs_for_trace(&req.trace_id, usize::MAX)?,
+            },
             LogContextMode::Bounded => {
-                self.fetch_logs_for_trace_bounded(&req.trace_id, &spans, 50)?
+                self.fetch_logs_for_trace_bounded(&req.trace_id, Some(&spans), 50)?
             }
         };
 
-        let truncated = matches!(req.logs, LogContextMode::Bounded) && logs.len() >= 50;
+        let truncated = matches!(req.logs, LogContextMode::Bounded) && logs.len() >= 50
+            || next_offset.is_some();
         Ok(TraceResponse {
             trace_id: req.trace_id.clone(),
             spans,
@@ -78,8 +114,56 @@ impl Store {
                     LogContextMode::Bounded => "bounded",
                 }
                 .to_string(),
-                limit: 50,
+                limit: match (&req.logs, req.logs_limit) {
+                    (LogContextMode::All, Some(page)) => page,
+                    _ => 50,
+                },
+                truncated,
+                next_offset,
+            },
+        })
+    }
+
+    /// Like the logs half of `get_trace`. Spans are only read when `Bounded`
+    /// has more logs than fit and needs them to pick anchors.
+    pub fn get_trace_logs(&self, req: &TraceLogsRequest) -> Result<TraceLogsResponse> {
+        let limit = req.limit.filter(|l| *l > 0);
+        let (logs, limit, truncated) = match req.mode {
+            LogContextMode::None => (Vec::new(), 0, false),
+            LogContextMode::All => match limit {
+                Some(limit) => {
+                    let mut logs = self.fetch_logs_for_trace_page(&req.trace_id, 0, limit + 1)?;
+                    let truncated = logs.len() > limit;
+                    logs.truncate(limit);
+                    (logs, limit, truncated)
+                }
+                None => {
+                    let logs = self.fetch_logs_for_trace(&req.trace_id, usize::MAX)?;
+                    let n = logs.len();
+                    (logs, n, false)
+                }
+            },
+            LogContextMode::Bounded => {
+                let limit = limit.unwrap_or(50);
+                let logs = self.fetch_logs_for_trace_bounded(&req.trace_id, None, limit)?;
+                let truncated = logs.len() >= limit;
+                (logs, limit, truncated)
+            }
+        };
+
+        Ok(TraceLogsResponse {
+            trace_id: req.trace_id.clone(),
+            logs,
+            context: LogsContextMeta {
+                policy: match req.mode {
+                    LogContextMode::None => "none",
+                    LogContextMode::All => "all",
+                    LogContextMode::Bounded => "bounded",
+                }
+                .to_string(),
+                limit,
                 truncated,
+                next_offset: None,
             },
         })
     }
@@ -89,6 +173,8 @@ impl Store {
             trace_id: req.trace_id.clone(),
             root_span_id: None,
             logs: LogContextMode::None,
+            logs_offset: 0,
+            logs_limit: None,
         })?;
 
         let span = trace
@@ -123,11 +209,186 @@ impl Store {
                 .to_string(),
                 limit: 30,
                 truncated,
+                next_offset: None,
             },
         })
     }
 
     pub fn list_traces(&self, req: &TracesRequest) -> Result<Vec<TraceListItem>> {
+        let mut items = self.matching_traces(req)?;
+        // trace_id breaks ties so equal durations list in the same order every run.
+        match req.sort {
+            SortOrder::DurationDesc | SortOrder::TsDesc => items.sort_by(|a, b| {
+                (Reverse(a.duration_ms), &a.trace_id).cmp(&(Reverse(b.duration_ms), &b.trace_id))
+            }),
+            SortOrder::TsAsc => items
+                .sort_by(|a, b| (a.duration_ms, &a.trace_id).cmp(&(b.duration_ms, &b.trace_id))),
+            // Error roots keep their status message in place of `ERROR`.
+            SortOrder::Relevance => items.sort_by(|a, b| {
+                (a.status == "OK", Reverse(a.duration_ms), &a.trace_id).cmp(&(
+                    b.status == "OK",
+                    Reverse(b.duration_ms),
+                    &b.trace_id,
+                ))
+            }),
+        }
+
+        items.truncate(req.limit);
+        Ok(items)
+    }
+
+    pub fn spans_in_window(&self, req: &SpansInWindowRequest) -> Result<Vec<SpanRecord>> {
+        let conn = self.conn();
+        let mut where_parts = Vec::new();
+        let mut args: Vec<duckdb::types::Value> = Vec::new();
+        if let Some(service) = &req.service {
+            where_parts.push("service = ?");
+            args.push(duckdb::types::Value::Text(service.clone()));
+        }
+        if let Some(since) = req.window.since {
+            where_parts.push("start_ts >= ?");
+            args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+        }
+        if let Some(until) = req.window.until {
+            where_parts.push("start_ts <= ?");
+            args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+        }
+        let where_sql = if where_parts.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_parts.join(" AND "))
+        };
+        let order_sql = match req.sort {
+            SortOrder::TsAsc => "start_ts ASC, trace_id ASC, span_id ASC",
+            SortOrder::TsDesc => "start_ts DESC, trace_id DESC, span_id DESC",
+            SortOrder::DurationDesc => {
+                "epoch_ms(end_ts) - epoch_ms(start_ts) DESC, start_ts ASC, trace_id ASC, span_id ASC"
+            }
+            SortOrder::Relevance => {
+                "CASE WHEN status <> 'OK' THEN 0 ELSE 1 END ASC,
+                 epoch_ms(end_ts) - epoch_ms(start_ts) DESC, start_ts ASC, trace_id ASC, span_id ASC"
+            }
+        };
+        args.push(duckdb::types::Value::BigInt(
+            i64::try_from(req.limit).unwrap_or(i64::MAX),
+        ));
+
+        let sql = format!(
+            "SELECT trace_id, span_id, parent_span_id, service, name, start_ts, end_ts, status, attrs_json, events_json
+             FROM spans
+             {where_sql}
+             ORDER BY {order_sql}
+             LIMIT ?"
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| OtellError::Store(format!("prepare window spans failed: {e}")))?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), span_from_row)
+            .map_err(|e| OtellError::Store(format!("query window spans failed: {e}")))?;
+
+        let mut spans = Vec::new();
+        for row in rows {
+            spans.push(row.map_err(|e| OtellError::Store(format!("map window span failed: {e}")))?);
+        }
+        Ok(spans)
+    }
+
+    /// Groups failing spans by `(service, name)`, most frequent first. A span
+    /// fails when its status isn't `OK`: decode keeps a status message only
+    /// for the error code, storing it in place of `ERROR`.
+    pub fn failed_spans(&self, req: &FailedSpansRequest) -> Result<FailedSpansResponse> {
+        let conn = self.conn();
+        let mut where_parts = vec!["status <> 'OK'"];
+        let mut args: Vec<duckdb::types::Value> = Vec::new();
+        if let Some(service) = &req.service {
+            where_parts.push("service = ?");
+            args.push(duckdb::types::Value::Text(service.clone()));
+        }
+        if let Some(since) = req.window.since {
+            where_parts.push("start_ts >= ?");
+            args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+        }
+        if let Some(until) = req.window.until {
+            where_parts.push("start_ts <= ?");
+            args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+        }
+
+        args.push(duckdb::types::Value::BigInt(
+            i64::try_from(req.limit).unwrap_or(i64::MAX),
+        ));
+
+        // The example trace and message come from each group's most recent span.
+        let sql = format!(
+            "WITH failed AS (
+                 SELECT service, name, status, trace_id, span_id, start_ts
+                 FROM spans
+                 WHERE {}
+             ),
+             grouped AS (
+                 SELECT service, name, COUNT(*) AS n, MAX(start_ts) AS last_ts
+                 FROM failed
+                 GROUP BY service, name
+             )
+             SELECT g.service, g.name, g.n, g.last_ts,
+                    (SELECT f.trace_id FROM failed f
+                     WHERE f.service = g.service AND f.name = g.name
+                     ORDER BY f.start_ts DESC, f.trace_id ASC, f.span_id ASC
+                     LIMIT 1),
+                    (SELECT f.status FROM failed f
+                     WHERE f.service = g.service AND f.name = g.name AND f.status <> 'ERROR'
+                     ORDER BY f.start_ts DESC, f.trace_id ASC, f.span_id ASC
+                     LIMIT 1),
+                    CAST(SUM(g.n) OVER () AS BIGINT)
+             FROM grouped g
+             ORDER BY g.n DESC, g.service ASC, g.name ASC
+             LIMIT ?",
+            where_parts.join(" AND ")
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| OtellError::Store(format!("prepare failed spans failed: {e}")))?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                Ok((
+                    FailedSpanGroup {
+                        service: row.get::<_, String>(0)?,
+                        name: row.get::<_, String>(1)?,
+                        count: usize::try_from(row.get::<_, i64>(2)?).unwrap_or_default(),
+                        last_ts: naive_to_utc(row.get::<_, NaiveDateTime>(3)?),
+                        example_trace_id: row.get::<_, String>(4)?,
+                        status_message: row.get::<_, Option<String>>(5)?,
+                    },
+                    usize::try_from(row.get::<_, i64>(6)?).unwrap_or_default(),
+                ))
+            })
+            .map_err(|e| OtellError::Store(format!("query failed spans failed: {e}")))?;
+
+        let mut groups = Vec::new();
+        let mut total_spans = 0;
+        for row in rows {
+            let (group, total) =
+                row.map_err(|e| OtellError::Store(format!("map failed span failed: {e}")))?;
+            groups.push(group);
+            total_spans = total;
+        }
+        Ok(FailedSpansResponse {
+            groups,
+            total_spans,
+        })
+    }
+
+    /// Number of traces `list_traces` would match, ignoring `limit`.
+    pub fn count_traces(&self, req: &TracesRequest) -> Result<usize> {
+        Ok(self.matching_traces(req)?.len())
+    }
+
+    fn matching_traces(&self, req: &TracesRequest) -> Result<Vec<TraceListItem>> {
+        let attr_matches = if req.attr_filters.is_empty() {
+            None
+        } else {
+            Some(self.traces_with_matching_span(req)?)
+        };
         let conn = self.conn();
         let sql = if req.service.is_some() {
             "SELECT s.trace_id, s.name, s.start_ts, s.end_ts, s.status,
@@ -200,6 +461,11 @@ impl Store {
             {
                 continue;
             }
+            if let Some(matched) = &attr_matches
+                && !matched.contains(&trace_id)
+            {
+                continue;
+            }
             items.push(TraceListItem {
                 trace_id,
                 root_name,
@@ -208,22 +474,71 @@ impl Store {
                 status,
             });
         }
+        Ok(items)
+    }
 
-        match req.sort {
-            SortOrder::DurationDesc => items.sort_by_key(|i| Reverse(i.duration_ms)),
-            SortOrder::TsAsc => items.sort_by_key(|i| i.duration_ms),
-            SortOrder::TsDesc => items.sort_by_key(|i| Reverse(i.duration_ms)),
+    /// Trace ids with at least one span whose attributes match every filter.
+    /// Only spans of traces `req`'s window and service would list are read.
+    fn traces_with_matching_span(&self, req: &TracesRequest) -> Result<HashSet<String>> {
+        let mut where_parts = Vec::new();
+        let mut args: Vec<duckdb::types::Value> = Vec::new();
+        if req.window.since.is_some() || req.window.until.is_some() {
+            let mut root = String::from(
+                "EXISTS (SELECT 1 FROM spans r
+                         WHERE r.trace_id = s.trace_id AND r.parent_span_id IS NULL",
+            );
+            if let Some(since) = req.window.since {
+                root.push_str(" AND r.start_ts >= ?");
+                args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+            }
+            if let Some(until) = req.window.until {
+                root.push_str(" AND r.start_ts <= ?");
+                args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+            }
+            root.push(')');
+            where_parts.push(root);
+        }
+        if let Some(service) = &req.service {
+            where_parts.push(
+                "EXISTS (SELECT 1 FROM spans sf WHERE sf.trace_id = s.trace_id AND sf.service = ?)"
+                    .to_string(),
+            );
+            args.push(duckdb::types::Value::Text(service.clone()));
         }
+        let where_sql = if where_parts.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_parts.join(" AND "))
+        };
 
-        items.truncate(req.limit);
-        Ok(items)
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(&format!(
+                "SELECT s.trace_id, s.attrs_json FROM spans s {where_sql}"
+            ))
+            .map_err(|e| OtellError::Store(format!("prepare span attrs failed: {e}")))?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .map_err(|e| OtellError::Store(format!("query span attrs failed: {e}")))?;
+
+        let mut matched = HashSet::new();
+        for row in rows {
+            let (trace_id, attrs_json) =
+                row.map_err(|e| OtellError::Store(format!("map span attrs failed: {e}")))?;
+            if matches_attr_filters(&attrs_json, &req.attr_filters, false) {
+                matched.insert(trace_id);
+            }
+        }
+        Ok(matched)
     }
 
     pub fn query_metrics(&self, req: &MetricsRequest) -> Result<MetricsResponse> {
         let conn = self.conn();
         let mut stmt = conn
             .prepare(
-                "SELECT ts, name, service, value, attrs_json
+                "SELECT ts, name, service, value, attrs_json, unit, kind, temporality
                  FROM metric_points
                  WHERE name = ?
                  ORDER BY ts ASC",
@@ -238,6 +553,9 @@ impl Store {
                     service: row.get::<_, String>(2)?,
                     value: row.get::<_, f64>(3)?,
                     attrs_json: row.get::<_, String>(4)?,
+                    unit: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                    kind: row.get::<_, Option<String>>(6)?.unwrap_or_default(),
+                    temporality: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
                 })
             })
             .map_err(|e| OtellError::Store(format!("query metrics failed: {e}")))?;
@@ -256,19 +574,60 @@ impl Store {
             points.push(p);
         }
 
+        // Cumulative counters are only meaningful as a rate, so default to it.
+        let default_agg = if !points.is_empty() && points.iter().all(|p| p.kind == "monotonic_sum")
+        {
+            "rate"
+        } else {
+            "avg"
+        };
         let series = aggregate_metrics(
             &points,
             req.group_by.as_deref(),
-            req.agg.as_deref(),
+            req.agg.as_deref().unwrap_or(default_agg),
+            req.bucket_secs.filter(|secs| *secs > 0),
+            &req.window,
             req.limit,
         );
-        Ok(MetricsResponse { points, series })
+        let (unit, kind) = points
+            .last()
+            .map(|p| (p.unit.clone(), p.kind.clone()))
+            .unwrap_or_default();
+        Ok(MetricsResponse {
+            points,
+            series,
+            unit,
+            kind,
+        })
     }
 
     pub fn list_metric_names(&self, req: &MetricsListRequest) -> Result<MetricsListResponse> {
+        let mut metrics = self.matching_metric_names(req)?;
+        match req.sort {
+            MetricsListSort::Count => {
+                metrics.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)))
+            }
+            MetricsListSort::Cardinality => metrics.sort_by(|a, b| {
+                b.cardinality
+                    .cmp(&a.cardinality)
+                    .then_with(|| b.count.cmp(&a.count))
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+        }
+        metrics.truncate(req.limit);
+
+        Ok(MetricsListResponse { metrics })
+    }
+
+    /// Number of distinct metric names `list_metric_names` would match, ignoring `limit`.
+    pub fn count_metric_names(&self, req: &MetricsListRequest) -> Result<usize> {
+        Ok(self.matching_metric_names(req)?.len())
+    }
+
+    fn matching_metric_names(&self, req: &MetricsListRequest) -> Result<Vec<MetricNameItem>> {
         let conn = self.conn();
         let mut stmt = conn
-            .prepare("SELECT ts, name, service FROM metric_points ORDER BY ts DESC")
+            .prepare("SELECT ts, name, service, attrs_json FROM metric_points ORDER BY ts DESC")
             .map_err(|e| OtellError::Store(format!("prepare metric names failed: {e}")))?;
 
         let rows = stmt
@@ -276,13 +635,14 @@ impl Store {
                 let ts = naive_to_utc(row.get::<_, NaiveDateTime>(0)?);
                 let name = row.get::<_, String>(1)?;
                 let service = row.get::<_, String>(2)?;
-                Ok((ts, name, service))
+                let attrs_json = row.get::<_, String>(3)?;
+                Ok((ts, name, service, attrs_json))
             })
             .map_err(|e| OtellError::Store(format!("query metric names failed: {e}")))?;
 
-        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut counts: HashMap<String, (usize, HashSet<(String, String)>)> = HashMap::new();
         for row in rows {
-            let (ts, name, service) =
+            let (ts, name, service, attrs_json) =
                 row.map_err(|e| OtellError::Store(format!("map metric names row failed: {e}")))?;
             if !in_window(ts, &req.window.since, &req.window.until) {
                 continue;
@@ -292,27 +652,66 @@ impl Store {
             {
                 continue;
             }
-            *counts.entry(name).or_insert(0) += 1;
+            let entry = counts.entry(name).or_default();
+            entry.0 += 1;
+            entry.1.insert((service, attrs_json));
         }
 
-        let mut metrics = counts
+        Ok(counts
             .into_iter()
-            .map(|(name, count)| MetricNameItem { name, count })
-            .collect::<Vec<_>>();
-        metrics.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
-        metrics.truncate(req.limit);
-
-        Ok(MetricsListResponse { metrics })
+            .map(|(name, (count, series))| {
+                let services = if req.with_services {
+                    let mut services = series
+                        .iter()
+                        .map(|(service, _)| service.clone())
+                        .collect::<BTreeSet<_>>()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    services.truncate(METRIC_SERVICES_MAX);
+                    services
+                } else {
+                    Vec::new()
+                };
+                MetricNameItem {
+                    name,
+                    count,
+                    cardinality: series.len(),
+                    services,
+                }
+            })
+            .collect())
     }
 
     fn fetch_logs_candidates(&self, req: &SearchRequest) -> Result<Vec<LogRecord>> {
+        self.fetch_logs_capped(req, None)
+    }
+
+    /// Every log in `window`, oldest first, for time context. Fails once
+    /// more than `context_scan_limit` rows would be loaded.
+    fn fetch_context_logs(&self, window: &TimeWindow) -> Result<Vec<LogRecord>> {
+        let req = SearchRequest {
+            window: window.clone(),
+            ..SearchRequest::default()
+        };
+        self.fetch_logs_capped(&req, Some(self.context_scan_limit()))
+    }
+
+    fn fetch_logs_capped(
+        &self,
+        req: &SearchRequest,
+        context_cap: Option<usize>,
+    ) -> Result<Vec<LogRecord>> {
         let conn = self.conn();
 
         let mut where_parts = Vec::new();
         let mut args: Vec<duckdb::types::Value> = Vec::new();
 
         if let Some(service) = &req.service {
-            where_parts.push("service = ?");
+            where_parts.push(if req.ignore_case {
+                "LOWER(service) = LOWER(?)"
+            } else {
+                "service = ?"
+            });
             args.push(duckdb::types::Value::Text(service.clone()));
         }
         if let Some(trace_id) = &req.trace_id {
@@ -327,6 +726,12 @@ impl Store {
             where_parts.push("severity >= ?");
             args.push(duckdb::types::Value::Int(severity as i32));
         }
+        // NULL flags compare as NULL, so logs with unknown flags match neither.
+        match req.sampled {
+            Some(true) => where_parts.push("(flags & 1) = 1"),
+            Some(false) => where_parts.push("(flags & 1) = 0"),
+            None => {}
+        }
         if let Some(since) = req.window.since {
             where_parts.push("ts >= ?");
             args.push(duckdb::types::Value::Text(since.to_rfc3339()));
@@ -341,12 +746,19 @@ impl Store {
         } else {
             format!("WHERE {}", where_parts.join(" AND "))
         };
+        // One row past the cap is enough to tell the scan would exceed it.
+        // Capped scans carry no attribute filters, so every row counts.
+        let limit_sql = context_cap
+            .map(|cap| format!("LIMIT {}", cap.saturating_add(1)))
+            .unwrap_or_default();
 
         let sql = format!(
-            "SELECT ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text
+            "SELECT ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text, flags,
+                    observed_ts
              FROM logs
              {where_sql}
-             ORDER BY ts ASC"
+             ORDER BY ts ASC, id ASC
+             {limit_sql}"
         );
 
         let mut stmt = conn
@@ -354,29 +766,26 @@ impl Store {
             .map_err(|e| OtellError::Store(format!("prepare search failed: {e}")))?;
 
         let rows = stmt
-            .query_map(params_from_iter(args.iter()), |row| {
-                Ok(LogRecord {
-                    ts: naive_to_utc(row.get::<_, NaiveDateTime>(0)?),
-                    service: row.get::<_, String>(1)?,
-                    severity: row.get::<_, i32>(2)?,
-                    trace_id: row.get::<_, Option<String>>(3)?,
-                    span_id: row.get::<_, Option<String>>(4)?,
-                    body: row.get::<_, String>(5)?,
-                    attrs_json: row.get::<_, String>(6)?,
-                    attrs_text: row.get::<_, String>(7)?,
-                })
-            })
+            .query_map(params_from_iter(args.iter()), log_from_row)
             .map_err(|e| OtellError::Store(format!("query search failed: {e}")))?;
 
         let mut results = Vec::new();
         for row in rows {
             let record =
                 row.map_err(|e| OtellError::Store(format!("map search row failed: {e}")))?;
-            if !matches_attr_filters(&record.attrs_json, &req.attr_filters) {
+            if !matches_attr_filters(&record.attrs_json, &req.attr_filters, req.ignore_case) {
                 continue;
             }
             results.push(record);
         }
+        if let Some(cap) = context_cap
+            && results.len() > cap
+        {
+            return Err(OtellError::Store(format!(
+                "context expansion would scan more than {cap} logs; narrow --since/--until \
+                 or raise OTELL_CONTEXT_SCAN_LIMIT"
+            )));
+        }
 
         if matches!(req.sort, SortOrder::TsDesc) {
             results.reverse();
@@ -385,6 +794,117 @@ impl Store {
         Ok(results)
     }
 
+    pub fn service_graph(&self, req: &ServiceGraphRequest) -> Result<ServiceGraphResponse> {
+        let conn = self.conn();
+        let mut where_parts = vec!["p.service <> c.service"];
+        let mut args: Vec<duckdb::types::Value> = Vec::new();
+        if let Some(since) = req.window.since {
+            where_parts.push("c.start_ts >= ?");
+            args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+        }
+        if let Some(until) = req.window.until {
+            where_parts.push("c.start_ts <= ?");
+            args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+        }
+        // A child span is an error call when its status isn't `OK`, the same
+        // test `failed_spans` uses.
+        let sql = format!(
+            "SELECT p.service, c.service, COUNT(*) AS calls,
+                    CAST(SUM(CASE WHEN c.status <> 'OK' THEN 1 ELSE 0 END) AS BIGINT)
+             FROM spans c
+             JOIN spans p ON p.trace_id = c.trace_id AND p.span_id = c.parent_span_id
+             WHERE {}
+             GROUP BY p.service, c.service
+             ORDER BY p.service ASC, calls DESC, c.service ASC",
+            where_parts.join(" AND ")
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| OtellError::Store(format!("prepare service graph failed: {e}")))?;
+
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                Ok(ServiceEdge {
+                    caller: row.get(0)?,
+                    callee: row.get(1)?,
+                    calls: row.get::<_, i64>(2)? as usize,
+                    errors: row.get::<_, i64>(3)? as usize,
+                })
+            })
+            .map_err(|e| OtellError::Store(format!("query service graph failed: {e}")))?;
+
+        let mut edges = Vec::new();
+        for row in rows {
+            edges.push(
+                row.map_err(|e| OtellError::Store(format!("map service graph row failed: {e}")))?,
+            );
+        }
+        Ok(ServiceGraphResponse { edges })
+    }
+
+    pub fn sampling_stats(&self, req: &SamplingStatsRequest) -> Result<SamplingStatsResponse> {
+        let conn = self.conn();
+        let mut where_parts = Vec::new();
+        let mut args: Vec<duckdb::types::Value> = Vec::new();
+        if let Some(service) = &req.service {
+            where_parts.push("service = ?");
+            args.push(duckdb::types::Value::Text(service.clone()));
+        }
+        if let Some(since) = req.window.since {
+            where_parts.push("ts >= ?");
+            args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+        }
+        if let Some(until) = req.window.until {
+            where_parts.push("ts <= ?");
+            args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+        }
+        let where_sql = if where_parts.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_parts.join(" AND "))
+        };
+
+        // NULL flags (logs stored before they were recorded) fail both flag
+        // tests and land in `unknown` with the trace-less logs.
+        let sql = format!(
+            "SELECT service,
+                    CAST(SUM(CASE WHEN trace_id IS NOT NULL AND (flags & 1) = 1
+                                  THEN 1 ELSE 0 END) AS BIGINT),
+                    CAST(SUM(CASE WHEN trace_id IS NOT NULL AND (flags & 1) = 0
+                                  THEN 1 ELSE 0 END) AS BIGINT),
+                    CAST(SUM(CASE WHEN trace_id IS NULL OR flags IS NULL
+                                  THEN 1 ELSE 0 END) AS BIGINT)
+             FROM logs
+             {where_sql}
+             GROUP BY service
+             ORDER BY service ASC"
+        );
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| OtellError::Store(format!("prepare sampling stats failed: {e}")))?;
+        let count = |row: &duckdb::Row<'_>, idx| {
+            row.get::<_, i64>(idx)
+                .map(|n| usize::try_from(n).unwrap_or_default())
+        };
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                Ok(ServiceSampling {
+                    service: row.get::<_, String>(0)?,
+                    sampled: count(row, 1)?,
+                    unsampled: count(row, 2)?,
+                    unknown: count(row, 3)?,
+                })
+            })
+            .map_err(|e| OtellError::Store(format!("query sampling stats failed: {e}")))?;
+
+        let mut services = Vec::new();
+        for row in rows {
+            services
+                .push(row.map_err(|e| OtellError::Store(format!("map sampling row failed: {e}")))?);
+        }
+        Ok(SamplingStatsResponse { services })
+    }
+
     fn fetch_trace_spans(&self, trace_id: &str) -> Result<Vec<SpanRecord>> {
         let conn = self.conn();
         let mut stmt = conn
@@ -397,20 +917,7 @@ impl Store {
             .map_err(|e| OtellError::Store(format!("prepare trace spans failed: {e}")))?;
 
         let rows = stmt
-            .query_map(params![trace_id], |row| {
-                Ok(SpanRecord {
-                    trace_id: row.get::<_, String>(0)?,
-                    span_id: row.get::<_, String>(1)?,
-                    parent_span_id: row.get::<_, Option<String>>(2)?,
-                    service: row.get::<_, String>(3)?,
-                    name: row.get::<_, String>(4)?,
-                    start_ts: naive_to_utc(row.get::<_, NaiveDateTime>(5)?),
-                    end_ts: naive_to_utc(row.get::<_, NaiveDateTime>(6)?),
-                    status: row.get::<_, String>(7)?,
-                    attrs_json: row.get::<_, String>(8)?,
-                    events_json: row.get::<_, String>(9)?,
-                })
-            })
+            .query_map(params![trace_id], span_from_row)
             .map_err(|e| OtellError::Store(format!("query trace spans failed: {e}")))?;
 
         let mut spans = Vec::new();
@@ -431,6 +938,45 @@ impl Store {
         Ok(records)
     }
 
+    /// One page of a trace's logs, oldest first, with LIMIT/OFFSET pushed
+    /// into SQL so huge traces are never loaded whole.
+    fn fetch_logs_for_trace_page(
+        &self,
+        trace_id: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<LogRecord>> {
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(
+                "SELECT ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text, flags,
+                        observed_ts
+                 FROM logs
+                 WHERE trace_id = ?
+                 ORDER BY ts ASC, id ASC
+                 LIMIT ? OFFSET ?",
+            )
+            .map_err(|e| OtellError::Store(format!("prepare trace logs page failed: {e}")))?;
+        let rows = stmt
+            .query_map(
+                params![
+                    trace_id,
+                    i64::try_from(limit).unwrap_or(i64::MAX),
+                    i64::try_from(offset).unwrap_or(i64::MAX)
+                ],
+                log_from_row,
+            )
+            .map_err(|e| OtellError::Store(format!("query trace logs page failed: {e}")))?;
+
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(
+                row.map_err(|e| OtellError::Store(format!("map trace logs row failed: {e}")))?,
+            );
+        }
+        Ok(out)
+    }
+
     fn fetch_logs_around_span(
         &self,
         trace_id: &str,
@@ -458,16 +1004,26 @@ impl Store {
         Ok(rows)
     }
 
+    /// `spans` are loaded on demand when not given, and only if the trace has
+    /// more than `limit` logs.
     fn fetch_logs_for_trace_bounded(
         &self,
         trace_id: &str,
-        spans: &[SpanRecord],
+        spans: Option<&[SpanRecord]>,
         limit: usize,
     ) -> Result<Vec<LogRecord>> {
         let all_logs = self.fetch_logs_for_trace(trace_id, usize::MAX)?;
         if all_logs.len() <= limit {
             return Ok(all_logs);
         }
+        let loaded;
+        let spans = match spans {
+            Some(spans) => spans,
+            None => {
+                loaded = self.fetch_trace_spans(trace_id)?;
+                &loaded
+            }
+        };
 
         let mut anchors = Vec::new();
         if let Some(root) = spans.iter().find(|s| s.parent_span_id.is_none()) {
@@ -510,59 +1066,187 @@ impl Store {
         Ok(out)
     }
 
+    /// Returns the matches plus `context_lines` neighbours each, in store
+    /// order, along with the contiguous runs they form. Overlapping or
+    /// touching windows merge into one group, like grep's `--` separators.
+    /// Context lines never reach outside the search window, and each match
+    /// reads only its own neighbours, so the window's size doesn't matter.
     fn expand_with_context(
         &self,
         selected: &[LogRecord],
         context_lines: usize,
-    ) -> Result<Vec<LogRecord>> {
-        if selected.is_empty() {
-            return Ok(Vec::new());
+        window: &TimeWindow,
+    ) -> Result<(Vec<LogRecord>, Vec<ContextGroup>)> {
+        // Identical stored records are told apart by their order of appearance.
+        let mut seen: HashMap<(DateTime<Utc>, &str, Option<&str>), usize> = HashMap::new();
+        let mut blocks = Vec::with_capacity(selected.len());
+        for log in selected {
+            let nth = seen
+                .entry((log.ts, log.body.as_str(), log.span_id.as_deref()))
+                .or_default();
+            let block = self.fetch_line_context(log, *nth, context_lines, window)?;
+            *nth += 1;
+            if !block.rows.is_empty() {
+                blocks.push(block);
+            }
         }
-
-        let req = SearchRequest {
-            limit: usize::MAX,
-            ..SearchRequest::default()
-        };
-        let all = self.fetch_logs_candidates(&req)?;
-        let ids = selected
-            .iter()
-            .map(|l| (l.ts, l.body.clone(), l.span_id.clone()))
-            .collect::<HashSet<_>>();
-
-        let mut keep = HashSet::new();
-        for (idx, row) in all.iter().enumerate() {
-            if ids.contains(&(row.ts, row.body.clone(), row.span_id.clone())) {
-                let start = idx.saturating_sub(context_lines);
-                let end = (idx + context_lines + 1).min(all.len());
-                for i in start..end {
-                    keep.insert(i);
+        blocks.sort_by_key(|block| block.rows[0].0);
+
+        let mut runs: Vec<BTreeMap<LogKey, LogRecord>> = Vec::new();
+        for block in blocks {
+            match runs.last_mut() {
+                // Nothing in the window sits between the run and this block.
+                Some(run)
+                    if block.before.is_none_or(|key| {
+                        run.keys().next_back().is_some_and(|last| key <= *last)
+                    }) =>
+                {
+                    run.extend(block.rows)
                 }
+                _ => runs.push(block.rows.into_iter().collect()),
             }
         }
 
         let mut output = Vec::new();
-        for (idx, row) in all.iter().enumerate() {
-            if keep.contains(&idx) {
-                output.push(row.clone());
+        let mut groups = Vec::with_capacity(runs.len());
+        for run in runs {
+            groups.push(ContextGroup {
+                start: output.len(),
+                len: run.len(),
+            });
+            output.extend(run.into_values());
+        }
+        Ok((output, groups))
+    }
+
+    /// Reads the `nth` stored copy of `log` and up to `lines` rows of the
+    /// window on either side of it, plus the key of the row just before them.
+    fn fetch_line_context(
+        &self,
+        log: &LogRecord,
+        nth: usize,
+        lines: usize,
+        window: &TimeWindow,
+    ) -> Result<LineContext> {
+        let mut bounds = String::new();
+        let mut bound_args = Vec::new();
+        if let Some(since) = window.since {
+            bounds.push_str(" AND l.ts >= ?");
+            bound_args.push(duckdb::types::Value::Text(since.to_rfc3339()));
+        }
+        if let Some(until) = window.until {
+            bounds.push_str(" AND l.ts <= ?");
+            bound_args.push(duckdb::types::Value::Text(until.to_rfc3339()));
+        }
+        // One extra row on each side: the match itself after, the key of the
+        // preceding row before.
+        let side_limit = duckdb::types::Value::BigInt(
+            i64::try_from(lines.saturating_add(1)).unwrap_or(i64::MAX),
+        );
+
+        let sql = format!(
+            "WITH m AS (
+                 SELECT ts, id FROM logs
+                 WHERE ts = ? AND body = ? AND COALESCE(span_id, '') = ?
+                 ORDER BY id
+                 LIMIT 1 OFFSET ?
+             )
+             SELECT * FROM (
+                 SELECT l.ts, l.service, l.severity, l.trace_id, l.span_id, l.body, l.attrs_json,
+                        l.attrs_text, l.flags, l.observed_ts, l.id, 0 AS after_match
+                 FROM logs l, m
+                 WHERE (l.ts < m.ts OR (l.ts = m.ts AND l.id < m.id)){bounds}
+                 ORDER BY l.ts DESC, l.id DESC
+                 LIMIT ?
+             ) AS before_rows
+             UNION ALL
+             SELECT * FROM (
+                 SELECT l.ts, l.service, l.severity, l.trace_id, l.span_id, l.body, l.attrs_json,
+                        l.attrs_text, l.flags, l.observed_ts, l.id, 1 AS after_match
+                 FROM logs l, m
+                 WHERE (l.ts > m.ts OR (l.ts = m.ts AND l.id >= m.id)){bounds}
+                 ORDER BY l.ts ASC, l.id ASC
+                 LIMIT ?
+             ) AS after_rows"
+        );
+
+        let mut args = vec![
+            duckdb::types::Value::Text(log.ts.to_rfc3339()),
+            duckdb::types::Value::Text(log.body.clone()),
+            duckdb::types::Value::Text(log.span_id.clone().unwrap_or_default()),
+            duckdb::types::Value::BigInt(i64::try_from(nth).unwrap_or(i64::MAX)),
+        ];
+        args.extend(bound_args.iter().cloned());
+        args.push(side_limit.clone());
+        args.extend(bound_args);
+        args.push(side_limit);
+
+        let conn = self.conn();
+        let mut stmt = conn
+            .prepare(&sql)
+            .map_err(|e| OtellError::Store(format!("prepare context query failed: {e}")))?;
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                let log = log_from_row(row)?;
+                Ok((
+                    (log.ts, row.get::<_, i64>(10)?),
+                    log,
+                    row.get::<_, i32>(11)? == 1,
+                ))
+            })
+            .map_err(|e| OtellError::Store(format!("query context failed: {e}")))?;
+
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        for row in rows {
+            let (key, log, after_match) =
+                row.map_err(|e| OtellError::Store(format!("map context row failed: {e}")))?;
+            if after_match {
+                after.push((key, log));
+            } else {
+                before.push((key, log));
             }
         }
-        Ok(output)
+        if after.is_empty() {
+            // The match is gone (e.g. pruned by retention since it was read).
+            return Ok(LineContext::default());
+        }
+
+        before.sort_by_key(|(key, _)| Reverse(*key));
+        let preceding = if before.len() > lines {
+            before.pop().map(|(key, _)| key)
+        } else {
+            None
+        };
+        before.reverse();
+        after.sort_by_key(|(key, _)| *key);
+        before.extend(after);
+        Ok(LineContext {
+            before: preceding,
+            rows: before,
+        })
     }
 
+    /// Only loads logs within `seconds` of the first and last match, clamped
+    /// to the search window.
     fn expand_with_time_context(
         &self,
         selected: &[LogRecord],
         seconds: i64,
+        window: &TimeWindow,
     ) -> Result<Vec<LogRecord>> {
         if selected.is_empty() || seconds <= 0 {
             return Ok(selected.to_vec());
         }
 
-        let req = SearchRequest {
-            limit: usize::MAX,
-            ..SearchRequest::default()
+        let pad = Duration::seconds(seconds);
+        let first = selected.iter().map(|l| l.ts).min().unwrap_or_default() - pad;
+        let last = selected.iter().map(|l| l.ts).max().unwrap_or_default() + pad;
+        let scan = TimeWindow {
+            since: Some(window.since.map_or(first, |since| since.max(first))),
+            until: Some(window.until.map_or(last, |until| until.min(last))),
         };
-        let all = self.fetch_logs_candidates(&req)?;
+        let all = self.fetch_context_logs(&scan)?;
         let mut keep = Vec::new();
 
         for row in &all {
@@ -584,6 +1268,50 @@ impl Store {
     }
 }
 
+/// Store order of a log row: `(ts, id)`.
+type LogKey = (DateTime<Utc>, i64);
+
+/// One match's line context; `before` is the key of the window row just
+/// ahead of `rows`, if there is one.
+#[derive(Default)]
+struct LineContext {
+    before: Option<LogKey>,
+    rows: Vec<(LogKey, LogRecord)>,
+}
+
+/// Maps `SELECT ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text, flags,
+/// observed_ts`.
+fn log_from_row(row: &duckdb::Row<'_>) -> duckdb::Result<LogRecord> {
+    Ok(LogRecord {
+        ts: naive_to_utc(row.get::<_, NaiveDateTime>(0)?),
+        service: row.get::<_, String>(1)?,
+        severity: row.get::<_, i32>(2)?,
+        trace_id: row.get::<_, Option<String>>(3)?,
+        span_id: row.get::<_, Option<String>>(4)?,
+        body: row.get::<_, String>(5)?,
+        attrs_json: row.get::<_, String>(6)?,
+        attrs_text: row.get::<_, String>(7)?,
+        flags: row.get::<_, Option<u32>>(8)?.unwrap_or_default(),
+        observed_ts: row.get::<_, Option<NaiveDateTime>>(9)?.map(naive_to_utc),
+    })
+}
+
+/// Maps `SELECT trace_id, span_id, parent_span_id, service, name, start_ts, end_ts, status, attrs_json, events_json`.
+fn span_from_row(row: &duckdb::Row<'_>) -> duckdb::Result<SpanRecord> {
+    Ok(SpanRecord {
+        trace_id: row.get::<_, String>(0)?,
+        span_id: row.get::<_, String>(1)?,
+        parent_span_id: row.get::<_, Option<String>>(2)?,
+        service: row.get::<_, String>(3)?,
+        name: row.get::<_, String>(4)?,
+        start_ts: naive_to_utc(row.get::<_, NaiveDateTime>(5)?),
+        end_ts: naive_to_utc(row.get::<_, NaiveDateTime>(6)?),
+        status: row.get::<_, String>(7)?,
+        attrs_json: row.get::<_, String>(8)?,
+        events_json: row.get::<_, String>(9)?,
+    })
+}
+
 fn compute_search_stats(records: &[LogRecord]) -> SearchStats {
     let mut by_service: HashMap<String, usize> = HashMap::new();
     let mut by_severity: HashMap<String, usize> = HashMap::new();
@@ -606,6 +1334,17 @@ fn compute_search_stats(records: &[LogRecord]) -> SearchStats {
     }
 }
 
+fn compute_rollup(records: &[LogRecord], unit: RollupUnit) -> Vec<RollupEntry> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for record in records {
+        *counts.entry(unit.label(record.ts)).or_insert(0) += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(period, count)| RollupEntry { period, count })
+        .collect()
+}
+
 fn severity_label(level: i32) -> &'static str {
     match level {
         1..=4 => "TRACE",
@@ -620,39 +1359,36 @@ fn severity_label(level: i32) -> &'static str {
 fn aggregate_metrics(
     points: &[MetricPoint],
     group_by: Option<&str>,
-    agg: Option<&str>,
+    agg: &str,
+    bucket_secs: Option<u64>,
+    window: &TimeWindow,
     limit: usize,
 ) -> Vec<MetricSeries> {
-    let mut groups: HashMap<String, Vec<f64>> = HashMap::new();
+    // Shared by every series so their buckets line up.
+    let buckets = bucket_secs.map(|secs| (secs, bucket_starts(secs, window, points)));
+    let mut groups: HashMap<String, Vec<&MetricPoint>> = HashMap::new();
     for p in points {
         let group = if group_by == Some("service") {
             p.service.clone()
         } else {
             "all".to_string()
         };
-        groups.entry(group).or_default().push(p.value);
+        groups.entry(group).or_default().push(p);
     }
 
     let mut series = groups
         .into_iter()
-        .map(|(group, mut values)| {
-            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-            let value = match agg.unwrap_or("avg") {
-                "count" => values.len() as f64,
-                "min" => *values.first().unwrap_or(&0.0),
-                "max" => *values.last().unwrap_or(&0.0),
-                "p50" => percentile(&values, 0.50),
-                "p95" => percentile(&values, 0.95),
-                "p99" => percentile(&values, 0.99),
-                _ => {
-                    if values.is_empty() {
-                        0.0
-                    } else {
-                        values.iter().sum::<f64>() / values.len() as f64
-                    }
-                }
-            };
-            MetricSeries { group, value }
+        .map(|(group, group_points)| {
+            let value = aggregate_value(&group_points, agg);
+            let points = buckets
+                .as_ref()
+                .map(|(secs, starts)| bucket_metric_points(&group_points, agg, *secs, starts))
+                .unwrap_or_default();
+            MetricSeries {
+                group,
+                value,
+                points,
+            }
         })
         .collect::<Vec<_>>();
 
@@ -661,6 +1397,117 @@ fn aggregate_metrics(
     series
 }
 
+fn aggregate_value(points: &[&MetricPoint], agg: &str) -> f64 {
+    let mut values = points.iter().map(|p| p.value).collect::<Vec<_>>();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    match agg {
+        "rate" => rate_per_second(points),
+        "count" => values.len() as f64,
+        "min" => *values.first().unwrap_or(&0.0),
+        "max" => *values.last().unwrap_or(&0.0),
+        "p50" => percentile(&values, 0.50),
+        "p95" => percentile(&values, 0.95),
+        "p99" => percentile(&values, 0.99),
+        _ => {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / values.len() as f64
+            }
+        }
+    }
+}
+
+/// Start (Unix seconds) of every `bucket_secs`-wide bucket from the window's
+/// start to its end, aligned to the epoch. An open side of the window falls
+/// back to the first or last point; at most `METRIC_BUCKETS_MAX`, newest kept.
+fn bucket_starts(bucket_secs: u64, window: &TimeWindow, points: &[MetricPoint]) -> Vec<i64> {
+    let width = i64::try_from(bucket_secs).unwrap_or(i64::MAX);
+    let first = window.since.or_else(|| points.iter().map(|p| p.ts).min());
+    let last = window.until.or_else(|| points.iter().map(|p| p.ts).max());
+    let (Some(first), Some(last)) = (first, last) else {
+        return Vec::new();
+    };
+    let start = first.timestamp().div_euclid(width) * width;
+    let end = last.timestamp().div_euclid(width) * width;
+    if end < start {
+        return Vec::new();
+    }
+    let count = ((end - start) / width)
+        .saturating_add(1)
+        .min(METRIC_BUCKETS_MAX as i64);
+    (0..count).rev().map(|i| end - i * width).collect()
+}
+
+/// Applies `agg` to the `points` in each bucket of `starts`; buckets without
+/// points get no value.
+fn bucket_metric_points(
+    points: &[&MetricPoint],
+    agg: &str,
+    bucket_secs: u64,
+    starts: &[i64],
+) -> Vec<MetricBucket> {
+    let width = i64::try_from(bucket_secs).unwrap_or(i64::MAX);
+    let mut buckets: BTreeMap<i64, Vec<&MetricPoint>> = BTreeMap::new();
+    for p in points {
+        buckets
+            .entry(p.ts.timestamp().div_euclid(width) * width)
+            .or_default()
+            .push(p);
+    }
+    starts
+        .iter()
+        .filter_map(|start| {
+            Some(MetricBucket {
+                ts: DateTime::from_timestamp(*start, 0)?,
+                value: buckets
+                    .get(start)
+                    .map(|bucket| aggregate_value(bucket, agg)),
+            })
+        })
+        .collect()
+}
+
+/// Per-second increase of counters, summed across the distinct series in
+/// `points`. Cumulative series count the growth between points, treating a
+/// drop in value as a reset; delta series add up every point after the first,
+/// since each reports the increase since the previous one.
+fn rate_per_second(points: &[&MetricPoint]) -> f64 {
+    let mut by_series: HashMap<(&str, &str), Vec<&MetricPoint>> = HashMap::new();
+    for p in points {
+        by_series
+            .entry((p.service.as_str(), p.attrs_json.as_str()))
+            .or_default()
+            .push(p);
+    }
+
+    by_series
+        .into_values()
+        .map(|mut series| {
+            series.sort_by_key(|p| p.ts);
+            let (Some(first), Some(last)) = (series.first(), series.last()) else {
+                return 0.0;
+            };
+            let elapsed = (last.ts - first.ts).num_milliseconds() as f64 / 1000.0;
+            if elapsed <= 0.0 {
+                return 0.0;
+            }
+            let increase = if series.iter().all(|p| p.temporality == "delta") {
+                series[1..].iter().map(|p| p.value).sum::<f64>()
+            } else {
+                series
+                    .windows(2)
+                    .map(|w| {
+                        let delta = w[1].value - w[0].value;
+                        if delta < 0.0 { w[1].value } else { delta }
+                    })
+                    .sum::<f64>()
+            };
+            increase / elapsed
+        })
+        .sum()
+}
+
 fn percentile(sorted: &[f64], pct: f64) -> f64 {
     if sorted.is_empty() {
         return 0.0;
@@ -720,7 +1567,7 @@ fn in_window(
     true
 }
 
-fn matches_attr_filters(attrs_json: &str, filters: &[otell_core::filter::AttrFilter]) -> bool {
+fn matches_attr_filters(attrs_json: &str, filters: &[AttrFilter], ignore_case: bool) -> bool {
     if filters.is_empty() {
         return true;
     }
@@ -729,15 +1576,18 @@ fn matches_attr_filters(attrs_json: &str, filters: &[otell_core::filter::AttrFil
         serde_json::from_str::<serde_json::Value>(attrs_json).unwrap_or(serde_json::Value::Null);
     for filter in filters {
         let key = filter.key.trim_start_matches("attrs.");
-        let value = parsed.get(key).and_then(|v| v.as_str()).unwrap_or_default();
-        if !filter.matches(value) {
+        if !filter.matches_json(parsed.get(key), ignore_case) {
             return false;
         }
     }
     true
 }
 
-fn apply_pattern(mut rows: Vec<LogRecord>, req: &SearchRequest) -> Result<Vec<LogRecord>> {
+fn apply_pattern(
+    mut rows: Vec<LogRecord>,
+    req: &SearchRequest,
+    regex_size_limit: usize,
+) -> Result<Vec<LogRecord>> {
     let Some(pattern) = &req.pattern else {
         return Ok(rows);
     };
@@ -759,15 +1609,49 @@ fn apply_pattern(mut rows: Vec<LogRecord>, req: &SearchRequest) -> Result<Vec<Lo
         return Ok(rows);
     }
 
-    let regex = RegexBuilder::new(pattern)
-        .case_insensitive(req.ignore_case)
-        .build()
-        .map_err(|e| OtellError::Parse(format!("invalid regex pattern: {e}")))?;
-
+    let regex = compile_pattern(pattern, req.ignore_case, regex_size_limit)?;
     rows.retain(|r| regex.is_match(&r.body));
     Ok(rows)
 }
 
+/// Builds a search/tail regex whose compiled program and lazy DFA are each
+/// capped at `size_limit` bytes.
+pub fn compile_pattern(pattern: &str, ignore_case: bool, size_limit: usize) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .size_limit(size_limit)
+        .dfa_size_limit(size_limit)
+        .build()
+        .map_err(|e| match e {
+            regex::Error::CompiledTooBig(limit) => OtellError::Parse(format!(
+                "regex pattern too large: compiled size exceeds {limit} bytes \
+                 (raise OTELL_QUERY_REGEX_SIZE_LIMIT or use --fixed)"
+            )),
+            e => OtellError::Parse(format!("invalid regex pattern: {e}")),
+        })
+}
+
+const DEFAULT_RELEVANCE_HALF_LIFE_SECS: u64 = 300;
+
+/// Orders matches by severity weight decayed by age, highest first. Weight
+/// doubles per severity level (INFO=4, WARN=8, ERROR=16, ...) and halves per
+/// half-life of age relative to the newest match, so an ERROR ties an INFO
+/// two half-lives newer. Ties keep time order.
+fn sort_by_relevance(rows: &mut [LogRecord], half_life_secs: Option<u64>) {
+    let Some(newest) = rows.iter().map(|r| r.ts).max() else {
+        return;
+    };
+    let half_life = half_life_secs
+        .unwrap_or(DEFAULT_RELEVANCE_HALF_LIFE_SECS)
+        .max(1) as f64;
+    let score = |r: &LogRecord| {
+        let level = (r.severity.clamp(1, 24) - 1) / 4;
+        let age = (newest - r.ts).num_milliseconds() as f64 / 1000.0;
+        2f64.powi(level) * 0.5f64.powf(age / half_life)
+    };
+    rows.sort_by(|a, b| score(b).total_cmp(&score(a)));
+}
+
 fn dedupe_logs(logs: &mut Vec<LogRecord>) {
     let mut seen = HashSet::new();
     logs.retain(|l| seen.insert((l.ts, l.body.clone(), l.span_id.clone())));
@@ -782,7 +1666,9 @@ mod tests {
     use otell_core::model::metric::MetricPoint;
     use otell_core::model::span::SpanRecord;
     use otell_core::query::{
-        LogContextMode, MetricsRequest, SearchRequest, TraceRequest, TracesRequest,
+        ContextGroup, FailedSpansRequest, LogContextMode, MetricsRequest, RollupEntry, RollupUnit,
+        SamplingStatsRequest, SearchRequest, ServiceSampling, SpansInWindowRequest,
+        TraceLogsRequest, TraceRequest, TracesRequest,
     };
 
     use crate::Store;
@@ -802,6 +1688,8 @@ mod tests {
                     body: "timeout from redis".into(),
                     attrs_json: "{\"peer\":\"redis:6379\"}".into(),
                     attrs_text: "peer=redis:6379".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
                 LogRecord {
                     ts: ts + chrono::Duration::seconds(1),
@@ -812,6 +1700,8 @@ mod tests {
                     body: "healthy".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
             ])
             .unwrap();
@@ -825,6 +1715,156 @@ mod tests {
         assert_eq!(res.records[0].body, "timeout from redis");
     }
 
+    #[test]
+    fn observed_ts_round_trips_separately_from_ts() {
+        let store = Store::open_in_memory().unwrap();
+        let ts = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let log = |body: &str, observed_ts| LogRecord {
+            ts,
+            service: "api".into(),
+            severity: 9,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts,
+        };
+        store
+            .insert_logs(&[
+                log("late", Some(ts + chrono::Duration::milliseconds(1500))),
+                log("unset", None),
+            ])
+            .unwrap();
+
+        let res = store
+            .search_logs(&SearchRequest {
+                sort: SortOrder::TsAsc,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        let late = res.records.iter().find(|r| r.body == "late").unwrap();
+        assert_eq!(late.ts, ts);
+        assert_eq!(
+            late.observed_ts,
+            Some(ts + chrono::Duration::milliseconds(1500))
+        );
+        assert_eq!(late.observed_delay_ms(), Some(1500));
+        let unset = res.records.iter().find(|r| r.body == "unset").unwrap();
+        assert_eq!(unset.observed_ts, None);
+    }
+
+    #[test]
+    fn context_expansion_stays_inside_search_window() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let logs = (0..10)
+            .map(|i| LogRecord {
+                ts: t0 + chrono::Duration::seconds(i),
+                service: "api".into(),
+                severity: 9,
+                trace_id: None,
+                span_id: None,
+                body: if i == 5 {
+                    "boom".into()
+                } else {
+                    format!("line {i}")
+                },
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+
+        let window = TimeWindow {
+            since: Some(t0 + chrono::Duration::seconds(4)),
+            until: Some(t0 + chrono::Duration::seconds(6)),
+        };
+        let bodies = |res: otell_core::query::SearchResponse| {
+            res.records.into_iter().map(|r| r.body).collect::<Vec<_>>()
+        };
+        let lines = store
+            .search_logs(&SearchRequest {
+                pattern: Some("boom".into()),
+                window: window.clone(),
+                context_lines: 3,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        assert_eq!(bodies(lines), vec!["line 4", "boom", "line 6"]);
+
+        let timed = store
+            .search_logs(&SearchRequest {
+                pattern: Some("boom".into()),
+                window,
+                context_seconds: Some(3),
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        assert_eq!(bodies(timed), vec!["line 4", "boom", "line 6"]);
+
+        let capped = store.clone().with_context_scan_limit(5);
+        let err = capped
+            .search_logs(&SearchRequest {
+                pattern: Some("boom".into()),
+                context_seconds: Some(10),
+                ..SearchRequest::default()
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("more than 5 logs"), "{err}");
+
+        // Line context reads only the neighbours, whatever the window holds.
+        let lines = capped
+            .search_logs(&SearchRequest {
+                pattern: Some("boom".into()),
+                context_lines: 1,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        assert_eq!(bodies(lines), vec!["line 4", "boom", "line 6"]);
+    }
+
+    #[test]
+    fn oversized_pattern_is_rejected() {
+        let store = Store::open_in_memory()
+            .unwrap()
+            .with_regex_size_limit(10_000);
+        let ts = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        store
+            .insert_logs(&[LogRecord {
+                ts,
+                service: "api".into(),
+                severity: 9,
+                trace_id: None,
+                span_id: None,
+                body: "aaaa".into(),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            }])
+            .unwrap();
+
+        let search = |pattern: &str| {
+            store.search_logs(&SearchRequest {
+                pattern: Some(pattern.into()),
+                ..SearchRequest::default()
+            })
+        };
+        let err = search(r"\w{1000}").unwrap_err().to_string();
+        assert!(err.contains("regex pattern too large"), "{err}");
+        assert_eq!(search("a+").unwrap().total_matches, 1);
+        assert!(
+            search("(")
+                .unwrap_err()
+                .to_string()
+                .contains("invalid regex")
+        );
+    }
+
     #[test]
     fn bounded_trace_context_limits_output() {
         let store = Store::open_in_memory().unwrap();
@@ -853,6 +1893,8 @@ mod tests {
                 body: format!("line {i}"),
                 attrs_json: "{}".into(),
                 attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
             })
             .collect::<Vec<_>>();
         store.insert_logs(&logs).unwrap();
@@ -862,12 +1904,140 @@ mod tests {
                 trace_id: "t1".into(),
                 root_span_id: None,
                 logs: LogContextMode::Bounded,
+                logs_offset: 0,
+                logs_limit: None,
             })
             .unwrap();
         assert!(trace.logs.len() <= 50);
         assert_eq!(trace.context.policy, "bounded");
     }
 
+    #[test]
+    fn trace_log_pages_cover_equal_timestamps_once() {
+        let store = Store::open_in_memory().unwrap();
+        let base = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        // Every log shares one timestamp, so only the row id orders them.
+        let logs = (0..100)
+            .map(|i| LogRecord {
+                ts: base,
+                service: "api".into(),
+                severity: 9,
+                trace_id: Some("t1".into()),
+                span_id: Some("root".into()),
+                body: format!("line {i}"),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+
+        let page = |offset| {
+            store
+                .get_trace(&TraceRequest {
+                    trace_id: "t1".into(),
+                    root_span_id: None,
+                    logs: LogContextMode::All,
+                    logs_offset: offset,
+                    logs_limit: Some(40),
+                })
+                .unwrap()
+        };
+        let first = page(0);
+        assert_eq!(first.logs.len(), 40);
+        assert_eq!(first.logs[39].body, "line 39");
+        assert_eq!(first.context.next_offset, Some(40));
+        assert!(first.context.truncated);
+
+        let second = page(first.context.next_offset.unwrap());
+        assert_eq!(second.logs[0].body, "line 40");
+        assert_eq!(second.context.next_offset, Some(80));
+
+        let last = page(80);
+        assert_eq!(last.logs.len(), 20);
+        assert_eq!(last.logs[19].body, "line 99");
+        assert_eq!(last.context.next_offset, None);
+        assert!(!last.context.truncated);
+
+        let unpaged = store
+            .get_trace(&TraceRequest {
+                trace_id: "t1".into(),
+                root_span_id: None,
+                logs: LogContextMode::All,
+                logs_offset: 0,
+                logs_limit: Some(0),
+            })
+            .unwrap();
+        assert_eq!(unpaged.logs.len(), 100);
+        assert_eq!(unpaged.context.next_offset, None);
+    }
+
+    #[test]
+    fn trace_logs_returns_only_that_traces_logs() {
+        let store = Store::open_in_memory().unwrap();
+        let base = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        store
+            .insert_spans(&[SpanRecord {
+                trace_id: "t1".into(),
+                span_id: "root".into(),
+                parent_span_id: None,
+                service: "api".into(),
+                name: "root".into(),
+                start_ts: base,
+                end_ts: base + chrono::Duration::seconds(10),
+                status: "ERROR".into(),
+                attrs_json: "{}".into(),
+                events_json: "[]".into(),
+            }])
+            .unwrap();
+        let logs = (0..10)
+            .map(|i| LogRecord {
+                ts: base + chrono::Duration::milliseconds(i * 50),
+                service: "api".into(),
+                severity: 17,
+                trace_id: Some(if i < 8 { "t1" } else { "t2" }.into()),
+                span_id: Some("root".into()),
+                body: format!("line {i}"),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+
+        let req = |mode, limit| TraceLogsRequest {
+            trace_id: "t1".into(),
+            mode,
+            limit,
+        };
+        let all = store
+            .get_trace_logs(&req(LogContextMode::All, None))
+            .unwrap();
+        assert_eq!(all.trace_id, "t1");
+        assert_eq!(all.logs.len(), 8);
+        assert!(all.logs.iter().all(|l| l.trace_id.as_deref() == Some("t1")));
+        assert!(!all.context.truncated);
+
+        let capped = store
+            .get_trace_logs(&req(LogContextMode::All, Some(5)))
+            .unwrap();
+        assert_eq!(capped.logs.len(), 5);
+        assert!(capped.context.truncated);
+
+        let bounded = store
+            .get_trace_logs(&req(LogContextMode::Bounded, Some(4)))
+            .unwrap();
+        assert!(bounded.logs.len() <= 4);
+        assert_eq!(bounded.context.policy, "bounded");
+
+        let none = store
+            .get_trace_logs(&req(LogContextMode::None, None))
+            .unwrap();
+        assert!(none.logs.is_empty());
+    }
+
     #[test]
     fn search_attr_and_severity_filters() {
         let store = Store::open_in_memory().unwrap();
@@ -883,6 +2053,8 @@ mod tests {
                     body: "redis timeout".into(),
                     attrs_json: "{\"peer\":\"redis:6379\"}".into(),
                     attrs_text: "peer=redis:6379".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
                 LogRecord {
                     ts: ts + chrono::Duration::seconds(1),
@@ -893,6 +2065,8 @@ mod tests {
                     body: "postgres timeout".into(),
                     attrs_json: "{\"peer\":\"postgres:5432\"}".into(),
                     attrs_text: "peer=postgres:5432".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
             ])
             .unwrap();
@@ -908,45 +2082,219 @@ mod tests {
     }
 
     #[test]
-    fn list_traces_sorts_by_duration() {
+    fn search_ignore_case_applies_to_service_and_attrs() {
         let store = Store::open_in_memory().unwrap();
-        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let ts = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
         store
-            .insert_spans(&[
-                SpanRecord {
-                    trace_id: "t1".into(),
-                    span_id: "r1".into(),
-                    parent_span_id: None,
-                    service: "api".into(),
-                    name: "short".into(),
-                    start_ts: t0,
-                    end_ts: t0 + chrono::Duration::milliseconds(50),
-                    status: "OK".into(),
-                    attrs_json: "{}".into(),
-                    events_json: "[]".into(),
-                },
-                SpanRecord {
-                    trace_id: "t2".into(),
-                    span_id: "r2".into(),
-                    parent_span_id: None,
-                    service: "api".into(),
-                    name: "long".into(),
-                    start_ts: t0,
-                    end_ts: t0 + chrono::Duration::milliseconds(200),
-                    status: "ERROR".into(),
-                    attrs_json: "{}".into(),
-                    events_json: "[]".into(),
-                },
-            ])
+            .insert_logs(&[LogRecord {
+                ts,
+                service: "API".into(),
+                severity: 17,
+                trace_id: None,
+                span_id: None,
+                body: "redis timeout".into(),
+                attrs_json: "{\"peer\":\"Redis:6379\"}".into(),
+                attrs_text: "peer=Redis:6379".into(),
+                flags: 0,
+                observed_ts: None,
+            }])
             .unwrap();
 
-        let traces = store
-            .list_traces(&TracesRequest {
-                service: Some("api".into()),
-                status: None,
-                window: TimeWindow::all(),
+        let mut req = SearchRequest {
+            service: Some("api".into()),
+            attr_filters: vec![AttrFilter::parse("attrs.peer=redis:*").unwrap()],
+            ..SearchRequest::default()
+        };
+        assert_eq!(store.search_logs(&req).unwrap().total_matches, 0);
+
+        req.ignore_case = true;
+        let res = store.search_logs(&req).unwrap();
+        assert_eq!(res.total_matches, 1);
+        assert_eq!(res.records[0].service, "API");
+    }
+
+    #[test]
+    fn spans_in_window_spans_traces() {
+        let store = Store::open_in_memory().unwrap();
+        let base = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let span = |trace: &str, id: &str, offset_s: i64, dur_ms: i64| SpanRecord {
+            trace_id: trace.into(),
+            span_id: id.into(),
+            parent_span_id: None,
+            service: "api".into(),
+            name: id.into(),
+            start_ts: base + chrono::Duration::seconds(offset_s),
+            end_ts: base
+                + chrono::Duration::seconds(offset_s)
+                + chrono::Duration::milliseconds(dur_ms),
+            status: "OK".into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        };
+        store
+            .insert_spans(&[
+                span("t1", "early", 0, 900),
+                span("t1", "a", 60, 100),
+                span("t2", "b", 90, 400),
+                span("t2", "late", 600, 50),
+            ])
+            .unwrap();
+
+        let req = |sort| SpansInWindowRequest {
+            window: TimeWindow {
+                since: Some(base + chrono::Duration::seconds(30)),
+                until: Some(base + chrono::Duration::seconds(120)),
+            },
+            service: None,
+            limit: 10,
+            sort,
+        };
+        let spans = store.spans_in_window(&req(SortOrder::TsAsc)).unwrap();
+        let ids = spans
+            .iter()
+            .map(|s| (s.trace_id.as_str(), s.span_id.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![("t1", "a"), ("t2", "b")]);
+
+        let spans = store
+            .spans_in_window(&req(SortOrder::DurationDesc))
+            .unwrap();
+        assert_eq!(spans[0].span_id, "b");
+
+        // The limit applies after sorting, so it keeps the newest span.
+        let newest = store
+            .spans_in_window(&SpansInWindowRequest {
+                limit: 1,
+                ..req(SortOrder::TsDesc)
+            })
+            .unwrap();
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].span_id, "b");
+
+        // Error spans stored with their status message rank first too.
+        store
+            .insert_spans(&[SpanRecord {
+                status: "connection refused".into(),
+                ..span("t3", "err", 45, 10)
+            }])
+            .unwrap();
+        let spans = store.spans_in_window(&req(SortOrder::Relevance)).unwrap();
+        let ids = spans.iter().map(|s| s.span_id.as_str()).collect::<Vec<_>>();
+        assert_eq!(ids, vec!["err", "b", "a"]);
+    }
+
+    #[test]
+    fn equal_keys_sort_deterministically() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let spans = ["t3", "t1", "t2"]
+            .into_iter()
+            .map(|trace_id| SpanRecord {
+                trace_id: trace_id.into(),
+                span_id: format!("{trace_id}-root"),
+                parent_span_id: None,
+                service: "api".into(),
+                name: "GET /".into(),
+                start_ts: t0,
+                end_ts: t0 + chrono::Duration::milliseconds(100),
+                status: "OK".into(),
+                attrs_json: "{}".into(),
+                events_json: "[]".into(),
+            })
+            .collect::<Vec<_>>();
+        store.insert_spans(&spans).unwrap();
+        let logs = ["b", "c", "a"]
+            .into_iter()
+            .map(|body| LogRecord {
+                ts: t0,
+                service: "api".into(),
+                severity: 9,
+                trace_id: None,
+                span_id: None,
+                body: body.into(),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&logs).unwrap();
+
+        for sort in [
+            SortOrder::DurationDesc,
+            SortOrder::TsAsc,
+            SortOrder::TsDesc,
+            SortOrder::Relevance,
+        ] {
+            let ids = store
+                .list_traces(&TracesRequest {
+                    service: None,
+                    status: None,
+                    window: TimeWindow::all(),
+                    sort,
+                    limit: 10,
+                    count_only: false,
+                    attr_filters: Vec::new(),
+                })
+                .unwrap()
+                .into_iter()
+                .map(|t| t.trace_id)
+                .collect::<Vec<_>>();
+            assert_eq!(ids, vec!["t1", "t2", "t3"]);
+        }
+
+        let bodies = store
+            .search_logs(&SearchRequest::default())
+            .unwrap()
+            .records
+            .into_iter()
+            .map(|r| r.body)
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn list_traces_sorts_by_duration() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        store
+            .insert_spans(&[
+                SpanRecord {
+                    trace_id: "t1".into(),
+                    span_id: "r1".into(),
+                    parent_span_id: None,
+                    service: "api".into(),
+                    name: "short".into(),
+                    start_ts: t0,
+                    end_ts: t0 + chrono::Duration::milliseconds(50),
+                    status: "OK".into(),
+                    attrs_json: "{}".into(),
+                    events_json: "[]".into(),
+                },
+                SpanRecord {
+                    trace_id: "t2".into(),
+                    span_id: "r2".into(),
+                    parent_span_id: None,
+                    service: "api".into(),
+                    name: "long".into(),
+                    start_ts: t0,
+                    end_ts: t0 + chrono::Duration::milliseconds(200),
+                    status: "ERROR".into(),
+                    attrs_json: "{}".into(),
+                    events_json: "[]".into(),
+                },
+            ])
+            .unwrap();
+
+        let traces = store
+            .list_traces(&TracesRequest {
+                service: Some("api".into()),
+                status: None,
+                window: TimeWindow::all(),
                 sort: SortOrder::DurationDesc,
                 limit: 10,
+                count_only: false,
+                attr_filters: Vec::new(),
             })
             .unwrap();
 
@@ -954,6 +2302,397 @@ mod tests {
         assert_eq!(traces[0].trace_id, "t2");
     }
 
+    #[test]
+    fn list_traces_relevance_puts_any_error_status_first() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let root = |trace_id: &str, status: &str, dur_ms: i64| SpanRecord {
+            trace_id: trace_id.into(),
+            span_id: format!("{trace_id}-root"),
+            parent_span_id: None,
+            service: "api".into(),
+            name: "GET /".into(),
+            start_ts: t0,
+            end_ts: t0 + chrono::Duration::milliseconds(dur_ms),
+            status: status.into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        };
+        store
+            .insert_spans(&[
+                root("t1", "OK", 500),
+                root("t2", "connection refused", 30),
+                root("t3", "ERROR", 20),
+            ])
+            .unwrap();
+
+        let ids = store
+            .list_traces(&TracesRequest {
+                service: None,
+                status: None,
+                window: TimeWindow::all(),
+                sort: SortOrder::Relevance,
+                limit: 10,
+                count_only: false,
+                attr_filters: Vec::new(),
+            })
+            .unwrap()
+            .into_iter()
+            .map(|t| t.trace_id)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec!["t2", "t3", "t1"]);
+    }
+
+    #[test]
+    fn count_traces_ignores_limit_and_applies_filters() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let root = |trace_id: &str, status: &str| SpanRecord {
+            trace_id: trace_id.into(),
+            span_id: format!("{trace_id}-root"),
+            parent_span_id: None,
+            service: "api".into(),
+            name: "GET /".into(),
+            start_ts: t0,
+            end_ts: t0 + chrono::Duration::milliseconds(10),
+            status: status.into(),
+            attrs_json: "{}".into(),
+            events_json: "[]".into(),
+        };
+        store
+            .insert_spans(&[root("t1", "OK"), root("t2", "ERROR"), root("t3", "OK")])
+            .unwrap();
+
+        let mut req = TracesRequest {
+            service: Some("api".into()),
+            status: None,
+            window: TimeWindow::all(),
+            sort: SortOrder::DurationDesc,
+            limit: 1,
+            count_only: true,
+            attr_filters: Vec::new(),
+        };
+        assert_eq!(store.list_traces(&req).unwrap().len(), 1);
+        assert_eq!(store.count_traces(&req).unwrap(), 3);
+
+        req.status = Some("ERROR".into());
+        assert_eq!(store.count_traces(&req).unwrap(), 1);
+    }
+
+    #[test]
+    fn where_operators_filter_traces_and_logs() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let span = |trace_id: &str, span_id: &str, parent: Option<&str>, attrs: &str| SpanRecord {
+            trace_id: trace_id.into(),
+            span_id: span_id.into(),
+            parent_span_id: parent.map(Into::into),
+            service: "api".into(),
+            name: span_id.into(),
+            start_ts: t0,
+            end_ts: t0 + chrono::Duration::milliseconds(10),
+            status: "OK".into(),
+            attrs_json: attrs.into(),
+            events_json: "[]".into(),
+        };
+        store
+            .insert_spans(&[
+                span("t1", "r1", None, "{}"),
+                span("t1", "c1", Some("r1"), r#"{"http.status_code":503}"#),
+                span("t2", "r2", None, r#"{"http.status_code":200}"#),
+            ])
+            .unwrap();
+        let log = |body: &str, attrs: &str| LogRecord {
+            ts: t0,
+            service: "api".into(),
+            severity: 17,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: attrs.into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+        store
+            .insert_logs(&[
+                log("a", r#"{"http.status_code":500,"peer":"redis"}"#),
+                log("b", r#"{"http.status_code":404,"peer":"postgres"}"#),
+            ])
+            .unwrap();
+
+        let traces = |filter: &str| {
+            store
+                .list_traces(&TracesRequest {
+                    service: None,
+                    status: None,
+                    window: TimeWindow::all(),
+                    sort: SortOrder::DurationDesc,
+                    limit: 10,
+                    count_only: false,
+                    attr_filters: vec![AttrFilter::parse(filter).unwrap()],
+                })
+                .unwrap()
+                .into_iter()
+                .map(|t| t.trace_id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(traces("http.status_code>=500"), vec!["t1"]);
+        assert_eq!(traces("http.status_code<300"), vec!["t2"]);
+
+        let scoped = |since: chrono::DateTime<chrono::Utc>, service: &str| {
+            store
+                .count_traces(&TracesRequest {
+                    service: Some(service.into()),
+                    status: None,
+                    window: TimeWindow {
+                        since: Some(since),
+                        until: None,
+                    },
+                    sort: SortOrder::DurationDesc,
+                    limit: 10,
+                    count_only: true,
+                    attr_filters: vec![AttrFilter::parse("http.status_code>=200").unwrap()],
+                })
+                .unwrap()
+        };
+        assert_eq!(scoped(t0, "api"), 2);
+        assert_eq!(scoped(t0 + chrono::Duration::seconds(1), "api"), 0);
+        assert_eq!(scoped(t0, "db"), 0);
+
+        let bodies = |filter: &str| {
+            store
+                .search_logs(&SearchRequest {
+                    attr_filters: vec![AttrFilter::parse(filter).unwrap()],
+                    ..SearchRequest::default()
+                })
+                .unwrap()
+                .records
+                .into_iter()
+                .map(|r| r.body)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(bodies("http.status_code>=500"), vec!["a"]);
+        assert_eq!(bodies("peer!=redis"), vec!["b"]);
+    }
+
+    #[test]
+    fn service_graph_links_parent_and_child_services() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let span = |span_id: &str, parent: Option<&str>, service: &str, status: &str, secs: i64| {
+            let start_ts = t0 + chrono::Duration::seconds(secs);
+            SpanRecord {
+                trace_id: "t1".into(),
+                span_id: span_id.into(),
+                parent_span_id: parent.map(Into::into),
+                service: service.into(),
+                name: span_id.into(),
+                start_ts,
+                end_ts: start_ts + chrono::Duration::milliseconds(10),
+                status: status.into(),
+                attrs_json: "{}".into(),
+                events_json: "[]".into(),
+            }
+        };
+        store
+            .insert_spans(&[
+                span("root", None, "api", "OK", 0),
+                span("local", Some("root"), "api", "OK", 0),
+                span("db", Some("local"), "postgres", "ERROR", 0),
+                span("db2", Some("local"), "postgres", "connection refused", 1),
+                span("cache", Some("root"), "redis", "OK", 2),
+                span("late", Some("root"), "redis", "OK", 3600),
+            ])
+            .unwrap();
+
+        let graph = store
+            .service_graph(&otell_core::query::ServiceGraphRequest {
+                window: TimeWindow {
+                    since: Some(t0),
+                    until: Some(t0 + chrono::Duration::seconds(60)),
+                },
+            })
+            .unwrap();
+        assert_eq!(
+            graph.edges,
+            vec![
+                otell_core::query::ServiceEdge {
+                    caller: "api".into(),
+                    callee: "postgres".into(),
+                    calls: 2,
+                    errors: 2,
+                },
+                otell_core::query::ServiceEdge {
+                    caller: "api".into(),
+                    callee: "redis".into(),
+                    calls: 1,
+                    errors: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_spans_group_by_service_and_name() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let span = |trace_id: &str, service: &str, name: &str, status: &str, secs: i64| {
+            let start_ts = t0 + chrono::Duration::seconds(secs);
+            SpanRecord {
+                trace_id: trace_id.into(),
+                span_id: format!("{trace_id}-{name}"),
+                parent_span_id: None,
+                service: service.into(),
+                name: name.into(),
+                start_ts,
+                end_ts: start_ts + chrono::Duration::milliseconds(5),
+                status: status.into(),
+                attrs_json: "{}".into(),
+                events_json: "[]".into(),
+            }
+        };
+        store
+            .insert_spans(&[
+                span("t1", "cache", "redis GET", "ERROR", 10),
+                span("t2", "cache", "redis GET", "connection refused", 20),
+                span("t3", "cache", "redis GET", "OK", 30),
+                span("t3", "db", "SELECT users", "ERROR", 30),
+                span("t0", "cache", "redis GET", "ERROR", -60),
+            ])
+            .unwrap();
+
+        let resp = store
+            .failed_spans(&FailedSpansRequest {
+                window: TimeWindow {
+                    since: Some(t0),
+                    until: None,
+                },
+                service: None,
+                limit: 10,
+            })
+            .unwrap();
+        assert_eq!(resp.total_spans, 3);
+        assert_eq!(resp.groups.len(), 2);
+        let redis = &resp.groups[0];
+        assert_eq!(
+            (redis.service.as_str(), redis.name.as_str(), redis.count),
+            ("cache", "redis GET", 2)
+        );
+        assert_eq!(redis.status_message.as_deref(), Some("connection refused"));
+        assert_eq!(redis.example_trace_id, "t2");
+        assert_eq!(redis.last_ts, t0 + chrono::Duration::seconds(20));
+        assert_eq!(resp.groups[1].name, "SELECT users");
+        assert_eq!(resp.groups[1].status_message, None);
+
+        let resp = store
+            .failed_spans(&FailedSpansRequest {
+                window: TimeWindow::all(),
+                service: Some("db".into()),
+                limit: 10,
+            })
+            .unwrap();
+        assert_eq!(resp.groups.len(), 1);
+        assert_eq!(resp.groups[0].example_trace_id, "t3");
+
+        let resp = store
+            .failed_spans(&FailedSpansRequest {
+                window: TimeWindow::all(),
+                service: None,
+                limit: 1,
+            })
+            .unwrap();
+        assert_eq!(resp.groups.len(), 1);
+        assert_eq!(resp.groups[0].name, "redis GET");
+        assert_eq!(resp.total_spans, 4);
+    }
+
+    #[test]
+    fn sampling_stats_split_logs_by_sampled_flag() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let log = |service: &str, trace_id: Option<&str>, flags: u32, secs: i64| LogRecord {
+            ts: t0 + chrono::Duration::seconds(secs),
+            service: service.into(),
+            severity: 9,
+            trace_id: trace_id.map(Into::into),
+            span_id: None,
+            body: "x".into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags,
+            observed_ts: None,
+        };
+        store
+            .insert_logs(&[
+                log("api", Some("t1"), 1, 0),
+                log("api", Some("t2"), 1, 1),
+                log("api", Some("t3"), 0, 2),
+                log("api", None, 0, 3),
+                log("worker", Some("t4"), 0, 4),
+                log("worker", Some("t5"), 1, 60),
+            ])
+            .unwrap();
+
+        let mut req = SamplingStatsRequest {
+            window: TimeWindow::all(),
+            service: None,
+        };
+        let res = store.sampling_stats(&req).unwrap();
+        assert_eq!(
+            res.services,
+            vec![
+                ServiceSampling {
+                    service: "api".into(),
+                    sampled: 2,
+                    unsampled: 1,
+                    unknown: 1,
+                },
+                ServiceSampling {
+                    service: "worker".into(),
+                    sampled: 1,
+                    unsampled: 1,
+                    unknown: 0,
+                },
+            ]
+        );
+
+        req.service = Some("worker".into());
+        req.window.until = Some(t0 + chrono::Duration::seconds(30));
+        let res = store.sampling_stats(&req).unwrap();
+        assert_eq!(res.services.len(), 1);
+        assert_eq!(res.services[0].unsampled, 1);
+        assert_eq!(res.services[0].sampled, 0);
+    }
+
+    #[test]
+    fn flags_beyond_trace_flags_byte_are_masked_on_insert() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        store
+            .insert_logs(&[LogRecord {
+                ts: t0,
+                service: "api".into(),
+                severity: 9,
+                trace_id: Some("t1".into()),
+                span_id: None,
+                body: "high bits".into(),
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0x8000_0101,
+                observed_ts: None,
+            }])
+            .unwrap();
+
+        let req = SearchRequest {
+            sampled: Some(true),
+            ..SearchRequest::default()
+        };
+        let res = store.search_logs(&req).unwrap();
+        assert_eq!(res.records.len(), 1);
+        assert_eq!(res.records[0].flags, 0x01);
+    }
+
     #[test]
     fn metrics_query_aggregates() {
         let store = Store::open_in_memory().unwrap();
@@ -966,6 +2705,9 @@ mod tests {
                     service: "api".into(),
                     value: 10.0,
                     attrs_json: "{}".into(),
+                    unit: "".into(),
+                    kind: "gauge".into(),
+                    temporality: "".into(),
                 },
                 MetricPoint {
                     ts: t0 + chrono::Duration::seconds(1),
@@ -973,6 +2715,9 @@ mod tests {
                     service: "api".into(),
                     value: 20.0,
                     attrs_json: "{}".into(),
+                    unit: "".into(),
+                    kind: "gauge".into(),
+                    temporality: "".into(),
                 },
             ])
             .unwrap();
@@ -985,6 +2730,7 @@ mod tests {
                 group_by: Some("service".into()),
                 agg: Some("p95".into()),
                 limit: 10,
+                bucket_secs: None,
             })
             .unwrap();
 
@@ -993,6 +2739,147 @@ mod tests {
         assert!(res.series[0].value >= 10.0);
     }
 
+    #[test]
+    fn metrics_store_unit_kind_and_default_to_rate_for_counters() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let points = [0.0, 50.0, 100.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| MetricPoint {
+                ts: t0 + chrono::Duration::seconds(10 * i as i64),
+                name: "http.server.requests".into(),
+                service: "api".into(),
+                value,
+                attrs_json: "{}".into(),
+                unit: "{request}".into(),
+                kind: "monotonic_sum".into(),
+                temporality: "cumulative".into(),
+            })
+            .collect::<Vec<_>>();
+        store.insert_metrics(&points).unwrap();
+
+        let res = store
+            .query_metrics(&MetricsRequest {
+                name: "http.server.requests".into(),
+                service: None,
+                window: TimeWindow::all(),
+                group_by: None,
+                agg: None,
+                limit: 10,
+                bucket_secs: None,
+            })
+            .unwrap();
+
+        assert_eq!(res.unit, "{request}");
+        assert_eq!(res.kind, "monotonic_sum");
+        assert_eq!(res.points[0].unit, "{request}");
+        assert_eq!(res.points[0].kind, "monotonic_sum");
+        assert!((res.series[0].value - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn delta_counters_rate_sums_increments_over_elapsed_time() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        // Each point is the increase since the previous one; read as cumulative
+        // these would look like resets and rate at (40 + 20) / 20s.
+        let points = [50.0, 40.0, 60.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, value)| MetricPoint {
+                ts: t0 + chrono::Duration::seconds(10 * i as i64),
+                name: "http.server.requests".into(),
+                service: "api".into(),
+                value,
+                attrs_json: "{}".into(),
+                unit: "{request}".into(),
+                kind: "monotonic_sum".into(),
+                temporality: "delta".into(),
+            })
+            .collect::<Vec<_>>();
+        store.insert_metrics(&points).unwrap();
+
+        let res = store
+            .query_metrics(&MetricsRequest {
+                name: "http.server.requests".into(),
+                service: None,
+                window: TimeWindow::all(),
+                group_by: None,
+                agg: None,
+                limit: 10,
+                bucket_secs: None,
+            })
+            .unwrap();
+
+        assert_eq!(res.points[0].temporality, "delta");
+        assert!((res.series[0].value - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn metrics_query_buckets_each_series() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let points = [(0, 10.0), (30, 30.0), (65, 5.0), (200, 7.0)]
+            .into_iter()
+            .map(|(offset, value)| MetricPoint {
+                ts: t0 + chrono::Duration::seconds(offset),
+                name: "queue.depth".into(),
+                service: "worker".into(),
+                value,
+                attrs_json: "{}".into(),
+                unit: "".into(),
+                kind: "gauge".into(),
+                temporality: "".into(),
+            })
+            .collect::<Vec<_>>();
+        store.insert_metrics(&points).unwrap();
+
+        let res = store
+            .query_metrics(&MetricsRequest {
+                name: "queue.depth".into(),
+                service: None,
+                window: TimeWindow::all(),
+                group_by: None,
+                agg: Some("max".into()),
+                limit: 10,
+                bucket_secs: Some(60),
+            })
+            .unwrap();
+
+        let buckets = &res.series[0].points;
+        let values = buckets.iter().map(|b| b.value).collect::<Vec<_>>();
+        assert_eq!(values, vec![Some(30.0), Some(5.0), None, Some(7.0)]);
+        assert_eq!(buckets[0].ts, t0);
+        assert_eq!(buckets[3].ts, t0 + chrono::Duration::minutes(3));
+        assert_eq!(res.series[0].value, 30.0);
+
+        // A bounded window gets its empty leading and trailing buckets too.
+        let res = store
+            .query_metrics(&MetricsRequest {
+                name: "queue.depth".into(),
+                service: None,
+                window: TimeWindow {
+                    since: Some(t0 - chrono::Duration::minutes(1)),
+                    until: Some(t0 + chrono::Duration::minutes(5)),
+                },
+                group_by: None,
+                agg: Some("max".into()),
+                limit: 10,
+                bucket_secs: Some(60),
+            })
+            .unwrap();
+        let values = res.series[0]
+            .points
+            .iter()
+            .map(|b| b.value)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            vec![None, Some(30.0), Some(5.0), None, Some(7.0), None, None]
+        );
+    }
+
     #[test]
     fn search_context_lines_returns_neighbors() {
         let store = Store::open_in_memory().unwrap();
@@ -1011,6 +2898,8 @@ mod tests {
                 },
                 attrs_json: "{}".into(),
                 attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
             })
             .collect::<Vec<_>>();
         store.insert_logs(&rows).unwrap();
@@ -1027,6 +2916,107 @@ mod tests {
         assert_eq!(res.records[1].body, "needle");
     }
 
+    #[test]
+    fn search_context_lines_groups_distant_matches() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let rows = (0..10)
+            .map(|i| LogRecord {
+                ts: t0 + chrono::Duration::seconds(i),
+                service: "api".into(),
+                severity: 9,
+                trace_id: None,
+                span_id: None,
+                body: if i == 1 || i == 8 {
+                    format!("needle{i}")
+                } else {
+                    format!("line{i}")
+                },
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&rows).unwrap();
+
+        let res = store
+            .search_logs(&SearchRequest {
+                pattern: Some("needle".into()),
+                context_lines: 1,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+
+        let bodies = res
+            .records
+            .iter()
+            .map(|r| r.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            vec!["line0", "needle1", "line2", "line7", "needle8", "line9"]
+        );
+        assert_eq!(
+            res.context_groups.unwrap(),
+            vec![
+                ContextGroup { start: 0, len: 3 },
+                ContextGroup { start: 3, len: 3 },
+            ]
+        );
+    }
+
+    #[test]
+    fn search_context_lines_merges_touching_matches() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        // Rows 2 and 5 are identical, so only their order tells them apart.
+        let rows = (0..8)
+            .map(|i| LogRecord {
+                ts: if i == 2 || i == 5 {
+                    t0 + chrono::Duration::seconds(3)
+                } else {
+                    t0 + chrono::Duration::seconds(i)
+                },
+                service: "api".into(),
+                severity: 9,
+                trace_id: None,
+                span_id: None,
+                body: if i == 2 || i == 5 {
+                    "needle".into()
+                } else {
+                    format!("line{i}")
+                },
+                attrs_json: "{}".into(),
+                attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
+            })
+            .collect::<Vec<_>>();
+        store.insert_logs(&rows).unwrap();
+
+        let res = store
+            .search_logs(&SearchRequest {
+                pattern: Some("needle".into()),
+                context_lines: 1,
+                sort: SortOrder::TsAsc,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+
+        let bodies = res
+            .records
+            .iter()
+            .map(|r| r.body.as_str())
+            .collect::<Vec<_>>();
+        // Store order: line0, line1, needle, line3(3s), needle, line4, line6, line7.
+        assert_eq!(bodies, vec!["line1", "needle", "line3", "needle", "line4"]);
+        assert_eq!(
+            res.context_groups.unwrap(),
+            vec![ContextGroup { start: 0, len: 5 }]
+        );
+    }
+
     #[test]
     fn search_count_only_with_stats() {
         let store = Store::open_in_memory().unwrap();
@@ -1042,6 +3032,8 @@ mod tests {
                     body: "timeout".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
                 LogRecord {
                     ts: t0 + chrono::Duration::seconds(1),
@@ -1052,6 +3044,8 @@ mod tests {
                     body: "timeout".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
             ])
             .unwrap();
@@ -1072,6 +3066,110 @@ mod tests {
         assert_eq!(stats.by_service[0], ("api".to_string(), 2));
     }
 
+    #[test]
+    fn search_rollup_buckets_by_calendar_hour() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 59, 30).unwrap();
+        let log = |ts| LogRecord {
+            ts,
+            service: "api".into(),
+            severity: 17,
+            trace_id: None,
+            span_id: None,
+            body: "timeout".into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+        store
+            .insert_logs(&[
+                log(t0),
+                log(t0 + chrono::Duration::seconds(20)),
+                log(t0 + chrono::Duration::seconds(40)),
+                log(t0 + chrono::Duration::minutes(50)),
+            ])
+            .unwrap();
+
+        let res = store
+            .search_logs(&SearchRequest {
+                pattern: Some("timeout".into()),
+                rollup: Some(RollupUnit::Hour),
+                limit: 1,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+
+        // Under an hour apart, but the 01:00 boundary splits them.
+        assert_eq!(
+            res.rollup.unwrap(),
+            vec![
+                RollupEntry {
+                    period: "2026-02-01T00:00".into(),
+                    count: 2
+                },
+                RollupEntry {
+                    period: "2026-02-01T01:00".into(),
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(res.returned, 1);
+    }
+
+    #[test]
+    fn relevance_sort_prefers_recent_errors() {
+        let store = Store::open_in_memory().unwrap();
+        let now = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 12, 0, 0).unwrap();
+        let log = |ts, severity, body: &str| LogRecord {
+            ts,
+            service: "api".into(),
+            severity,
+            trace_id: None,
+            span_id: None,
+            body: body.into(),
+            attrs_json: "{}".into(),
+            attrs_text: "".into(),
+            flags: 0,
+            observed_ts: None,
+        };
+        store
+            .insert_logs(&[
+                log(now - chrono::Duration::hours(1), 17, "old error"),
+                log(now - chrono::Duration::seconds(30), 17, "recent error"),
+                log(now, 9, "recent info"),
+            ])
+            .unwrap();
+
+        let res = store
+            .search_logs(&SearchRequest {
+                sort: SortOrder::Relevance,
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        let bodies = res
+            .records
+            .iter()
+            .map(|r| r.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["recent error", "recent info", "old error"]);
+
+        // A long half-life lets severity dominate age.
+        let res = store
+            .search_logs(&SearchRequest {
+                sort: SortOrder::Relevance,
+                relevance_half_life_secs: Some(86_400),
+                ..SearchRequest::default()
+            })
+            .unwrap();
+        let bodies = res
+            .records
+            .iter()
+            .map(|r| r.body.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, vec!["recent error", "old error", "recent info"]);
+    }
+
     #[test]
     fn search_time_context_includes_neighbors_by_time() {
         let store = Store::open_in_memory().unwrap();
@@ -1087,6 +3185,8 @@ mod tests {
                     body: "pre".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
                 LogRecord {
                     ts: t0 + chrono::Duration::milliseconds(500),
@@ -1097,6 +3197,8 @@ mod tests {
                     body: "needle".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
                 LogRecord {
                     ts: t0 + chrono::Duration::seconds(2),
@@ -1107,6 +3209,8 @@ mod tests {
                     body: "post".into(),
                     attrs_json: "{}".into(),
                     attrs_text: "".into(),
+                    flags: 0,
+                    observed_ts: None,
                 },
             ])
             .unwrap();
@@ -1133,6 +3237,9 @@ mod tests {
                     service: "api".into(),
                     value: 1.0,
                     attrs_json: "{}".into(),
+                    unit: "".into(),
+                    kind: "gauge".into(),
+                    temporality: "".into(),
                 },
                 MetricPoint {
                     ts: t0 + chrono::Duration::seconds(1),
@@ -1140,6 +3247,9 @@ mod tests {
                     service: "api".into(),
                     value: 1.0,
                     attrs_json: "{}".into(),
+                    unit: "".into(),
+                    kind: "gauge".into(),
+                    temporality: "".into(),
                 },
                 MetricPoint {
                     ts: t0 + chrono::Duration::seconds(2),
@@ -1147,6 +3257,9 @@ mod tests {
                     service: "api".into(),
                     value: 1.0,
                     attrs_json: "{}".into(),
+                    unit: "".into(),
+                    kind: "gauge".into(),
+                    temporality: "".into(),
                 },
             ])
             .unwrap();
@@ -1156,9 +3269,97 @@ mod tests {
                 service: Some("api".into()),
                 window: TimeWindow::all(),
                 limit: 10,
+                sort: otell_core::query::MetricsListSort::Count,
+                count_only: false,
+                with_services: false,
             })
             .unwrap();
         assert_eq!(res.metrics[0].name, "a");
         assert_eq!(res.metrics[0].count, 2);
     }
+
+    #[test]
+    fn metrics_list_sorts_by_cardinality() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let mut points = (0..10)
+            .map(|i| MetricPoint {
+                ts: t0 + chrono::Duration::seconds(i),
+                name: "busy".into(),
+                service: "api".into(),
+                value: 1.0,
+                attrs_json: "{}".into(),
+                unit: "".into(),
+                kind: "gauge".into(),
+                temporality: "".into(),
+            })
+            .collect::<Vec<_>>();
+        points.extend((0..3).map(|i| MetricPoint {
+            ts: t0 + chrono::Duration::seconds(i),
+            name: "wide".into(),
+            service: "api".into(),
+            value: 1.0,
+            attrs_json: format!("{{\"user\":\"u{i}\"}}"),
+            unit: "".into(),
+            kind: "gauge".into(),
+            temporality: "".into(),
+        }));
+        store.insert_metrics(&points).unwrap();
+
+        let req = otell_core::query::MetricsListRequest {
+            service: None,
+            window: TimeWindow::all(),
+            limit: 10,
+            sort: otell_core::query::MetricsListSort::Cardinality,
+            count_only: false,
+            with_services: false,
+        };
+        let res = store.list_metric_names(&req).unwrap();
+        assert_eq!(res.metrics[0].name, "wide");
+        assert_eq!(res.metrics[0].cardinality, 3);
+        assert_eq!(res.metrics[1].name, "busy");
+        assert_eq!(res.metrics[1].cardinality, 1);
+        assert_eq!(store.count_metric_names(&req).unwrap(), res.metrics.len());
+    }
+
+    #[test]
+    fn metrics_list_with_services_lists_every_emitter() {
+        let store = Store::open_in_memory().unwrap();
+        let t0 = chrono::Utc.with_ymd_and_hms(2026, 2, 1, 0, 0, 0).unwrap();
+        let point = |name: &str, service: &str| MetricPoint {
+            ts: t0,
+            name: name.into(),
+            service: service.into(),
+            value: 1.0,
+            attrs_json: "{}".into(),
+            unit: "".into(),
+            kind: "gauge".into(),
+            temporality: "".into(),
+        };
+        store
+            .insert_metrics(&[
+                point("latency", "worker"),
+                point("latency", "api"),
+                point("latency", "api"),
+                point("queue.depth", "worker"),
+            ])
+            .unwrap();
+
+        let mut req = otell_core::query::MetricsListRequest {
+            service: None,
+            window: TimeWindow::all(),
+            limit: 10,
+            sort: otell_core::query::MetricsListSort::Count,
+            count_only: false,
+            with_services: true,
+        };
+        let res = store.list_metric_names(&req).unwrap();
+        assert_eq!(res.metrics[0].name, "latency");
+        assert_eq!(res.metrics[0].services, vec!["api", "worker"]);
+        assert_eq!(res.metrics[1].services, vec!["worker"]);
+
+        req.with_services = false;
+        let res = store.list_metric_names(&req).unwrap();
+        assert!(res.metrics.iter().all(|m| m.services.is_empty()));
+    }
 }
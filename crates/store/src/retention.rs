@@ -88,6 +88,8 @@ mod tests {
                 body: "old".into(),
                 attrs_json: "{}".into(),
                 attrs_text: "".into(),
+                flags: 0,
+                observed_ts: None,
             }])
             .unwrap();
 
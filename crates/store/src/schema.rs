@@ -34,6 +34,14 @@ CREATE TABLE IF NOT EXISTS metric_points (
   attrs_json TEXT NOT NULL
 );
 
+-- DuckDB rejects constraints on ADD COLUMN, so added columns stay nullable.
+ALTER TABLE metric_points ADD COLUMN IF NOT EXISTS unit TEXT DEFAULT '';
+ALTER TABLE metric_points ADD COLUMN IF NOT EXISTS kind TEXT DEFAULT '';
+ALTER TABLE metric_points ADD COLUMN IF NOT EXISTS temporality TEXT DEFAULT '';
+-- NULL flags (rows written before the column existed) mean "unknown", not unsampled.
+ALTER TABLE logs ADD COLUMN IF NOT EXISTS flags INTEGER;
+ALTER TABLE logs ADD COLUMN IF NOT EXISTS observed_ts TIMESTAMP;
+
 CREATE SEQUENCE IF NOT EXISTS logs_id_seq;
 CREATE SEQUENCE IF NOT EXISTS metric_id_seq;
 
@@ -20,8 +20,8 @@ impl Store {
         {
             let mut stmt = tx
                 .prepare(
-                    "INSERT INTO logs (id, ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text)
-                     VALUES (nextval('logs_id_seq'), ?, ?, ?, ?, ?, ?, ?, ?)",
+                    "INSERT INTO logs (id, ts, service, severity, trace_id, span_id, body, attrs_json, attrs_text, flags, observed_ts)
+                     VALUES (nextval('logs_id_seq'), ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
                 )
                 .map_err(|e| OtellError::Store(format!("prepare insert logs failed: {e}")))?;
 
@@ -35,6 +35,10 @@ impl Store {
                     log.body,
                     log.attrs_json,
                     log.attrs_text,
+                    // Only the W3C trace-flags byte is defined for logs; masking
+                    // also keeps any u32 inside the signed INTEGER column.
+                    log.flags & 0xFF,
+                    log.observed_ts.map(|ts| ts.to_rfc3339()),
                 ])
                 .map_err(|e| OtellError::Store(format!("insert log failed: {e}")))?;
             }
@@ -102,8 +106,9 @@ impl Store {
         {
             let mut stmt = tx
                 .prepare(
-                    "INSERT INTO metric_points (id, ts, name, service, value, attrs_json)
-                     VALUES (nextval('metric_id_seq'), ?, ?, ?, ?, ?)",
+                    "INSERT INTO metric_points
+                       (id, ts, name, service, value, attrs_json, unit, kind, temporality)
+                     VALUES (nextval('metric_id_seq'), ?, ?, ?, ?, ?, ?, ?, ?)",
                 )
                 .map_err(|e| OtellError::Store(format!("prepare insert metrics failed: {e}")))?;
 
@@ -114,6 +119,9 @@ impl Store {
                     metric.service,
                     metric.value,
                     metric.attrs_json,
+                    metric.unit,
+                    metric.kind,
+                    metric.temporality,
                 ])
                 .map_err(|e| OtellError::Store(format!("insert metric failed: {e}")))?;
             }
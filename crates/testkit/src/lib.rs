@@ -41,6 +41,8 @@ pub fn sample_trace(trace_id: &str) -> (Vec<SpanRecord>, Vec<LogRecord>) {
             body: "retrying attempt=2".to_string(),
             attrs_json: "{}".to_string(),
             attrs_text: "attempt=2".to_string(),
+            flags: 0,
+            observed_ts: None,
         },
         LogRecord {
             ts: base + Duration::milliseconds(1200),
@@ -51,6 +53,8 @@ pub fn sample_trace(trace_id: &str) -> (Vec<SpanRecord>, Vec<LogRecord>) {
             body: "context deadline exceeded".to_string(),
             attrs_json: "{\"peer\":\"redis:6379\"}".to_string(),
             attrs_text: "peer=redis:6379".to_string(),
+            flags: 0,
+            observed_ts: None,
         },
     ];
 